@@ -0,0 +1,18 @@
+use std::process::ExitCode;
+
+use plate_tool::cli;
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let read = |path: &str| std::fs::read_to_string(path).map_err(|e| format!("{}: {}", path, e));
+    match cli::run(&args, read) {
+        Ok(output) => {
+            println!("{}", output);
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            eprintln!("{}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -0,0 +1,131 @@
+// The command line tool (src/bin/plate-tool-cli.rs), for working with
+// exported projects without a browser.
+
+use crate::data::estimate::{estimate_state, TimingParameters};
+use crate::data::schema::from_json;
+
+pub const USAGE: &str = "\
+Usage: plate-tool-cli estimate <state.json> [options]
+
+Prints the run estimate for a project saved with \"Export as JSON\",
+using the row order and timing figures saved with it.
+
+Options (override the saved timing):
+    --droplet-volume <nL>
+    --seconds-per-droplet <s>
+    --seconds-per-well <s>
+    --seconds-per-plate-swap <s>";
+
+type SetParameter = fn(&mut TimingParameters, f32);
+
+/// Runs a command, returning what to print.
+/// `read` returns the contents of the file at a path.
+pub fn run(
+    args: &[String],
+    read: impl Fn(&str) -> Result<String, String>,
+) -> Result<String, String> {
+    match args.first().map(String::as_str) {
+        Some("estimate") => estimate(&args[1..], read),
+        Some("help" | "--help" | "-h") => Ok(USAGE.to_string()),
+        Some(command) => Err(format!("Unknown command \"{}\"\n\n{}", command, USAGE)),
+        None => Err(USAGE.to_string()),
+    }
+}
+
+fn estimate(
+    args: &[String],
+    read: impl Fn(&str) -> Result<String, String>,
+) -> Result<String, String> {
+    let mut path = None;
+    let mut overrides: Vec<(SetParameter, f32)> = Vec::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let set: SetParameter = match arg.as_str() {
+            "--droplet-volume" => |p, v| p.droplet_volume = v,
+            "--seconds-per-droplet" => |p, v| p.seconds_per_droplet = v,
+            "--seconds-per-well" => |p, v| p.seconds_per_well = v,
+            "--seconds-per-plate-swap" => |p, v| p.seconds_per_plate_swap = v,
+            _ if path.is_none() && !arg.starts_with('-') => {
+                path = Some(arg.as_str());
+                continue;
+            }
+            _ => return Err(format!("Unexpected argument \"{}\"\n\n{}", arg, USAGE)),
+        };
+        let value = args
+            .next()
+            .and_then(|v| v.parse::<f32>().ok())
+            .filter(|v| *v >= 0.0)
+            .ok_or(format!("{} needs a number of at least 0", arg))?;
+        overrides.push((set, value));
+    }
+    let path = path.ok_or(USAGE)?;
+
+    let state = from_json(&read(path)?).map_err(|e| format!("{}: {}", path, e))?;
+    let mut params = state.preferences.timing;
+    for (set, value) in overrides {
+        set(&mut params, value);
+    }
+    estimate_state(&state, &params).map(|estimate| estimate.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use wasm_bindgen_test::*;
+
+    use super::*;
+    use crate::components::states::MainState;
+    use crate::data::plate::{PlateFormat, PlateType};
+    use crate::data::plate_instances::PlateInstance;
+    use crate::data::schema::to_json;
+    use crate::data::transfer::Transfer;
+    use crate::data::transfer_region::Region;
+
+    fn args(line: &str) -> Vec<String> {
+        line.split_whitespace().map(String::from).collect()
+    }
+
+    #[test]
+    #[wasm_bindgen_test]
+    fn test_estimate_command() {
+        let mut state = MainState::default();
+        let source = PlateInstance::new(PlateType::Source, PlateFormat::W96, "S1".to_string());
+        let dest = PlateInstance::new(PlateType::Destination, PlateFormat::W96, "D1".to_string());
+        let mut transfer = Transfer::new(
+            source.clone(),
+            dest.clone(),
+            Default::default(),
+            "T".to_string(),
+        );
+        transfer.transfer_region.source_region = Region::Rect((1, 1), (1, 2));
+        transfer.transfer_region.dest_region = Region::Point((1, 1));
+        transfer.volume = 5.0;
+        state.add_source_plate(source);
+        state.add_dest_plate(dest);
+        state.transfers.push(transfer);
+        let json = to_json(&state).unwrap();
+        let read = |path: &str| match path {
+            "state.json" => Ok(json.clone()),
+            _ => Err(format!("{}: not found", path)),
+        };
+
+        let output = run(
+            &args(
+                "estimate state.json --droplet-volume 2.5 --seconds-per-droplet 1 \
+                 --seconds-per-well 10 --seconds-per-plate-swap 100",
+            ),
+            read,
+        )
+        .unwrap();
+        // 4 droplets, 2 wells and 2 plates loaded
+        assert!(output.starts_with("Estimated run time: 3m 44s\n"));
+        assert!(output.contains("Plate swaps: 2\n"));
+        assert!(output.contains("\nS1\t4"));
+
+        assert!(run(&args("estimate missing.json"), read).is_err());
+        assert!(run(&args("estimate state.json --seconds-per-well"), read).is_err());
+        assert!(run(&args("estimate state.json --seconds-per-well -1"), read).is_err());
+        assert!(run(&args("estimate"), read).is_err());
+        assert!(run(&args("frobnicate"), read).is_err());
+        assert_eq!(run(&args("help"), read), Ok(USAGE.to_string()));
+    }
+}
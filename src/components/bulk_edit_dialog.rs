@@ -0,0 +1,254 @@
+#![allow(non_snake_case)]
+
+use uuid::Uuid;
+use wasm_bindgen::JsCast;
+use web_sys::{HtmlDialogElement, HtmlInputElement, HtmlSelectElement};
+use yew::prelude::*;
+use yewdux::prelude::*;
+
+use crate::components::notifications::{confirm, notify, Level};
+use crate::components::project_manager::take_snapshot;
+use crate::components::states::{CurrentTransfer, MainState};
+use crate::data::transfer::apply_name_pattern;
+
+#[derive(PartialEq, Properties)]
+pub struct BulkEditDialogProps {
+    pub ids: Vec<Uuid>,
+    pub close_callback: Callback<()>,
+}
+
+#[function_component]
+pub fn BulkEditDialog(props: &BulkEditDialogProps) -> Html {
+    let (main_state, main_dispatch) = use_store::<MainState>();
+    let (_, ct_dispatch) = use_store::<CurrentTransfer>();
+    let volume = use_state(|| 2.5f32);
+    let pattern = use_state(|| "{name}".to_string());
+    let source_id = use_state(|| main_state.selected_source_plate);
+    let dest_id = use_state(|| main_state.selected_dest_plate);
+
+    // In the order they appear in the list
+    let ids: Vec<Uuid> = main_state
+        .transfers
+        .iter()
+        .map(|t| t.get_uuid())
+        .filter(|id| props.ids.contains(id))
+        .collect();
+
+    // Applies an edit to the project, keeping the transfer being edited in step
+    let edit = {
+        let main_dispatch = main_dispatch.clone();
+        move |f: Box<dyn Fn(&mut MainState)>| {
+            main_dispatch.reduce_mut(|state| f(state));
+            let state = main_dispatch.get();
+            if let Some(transfer) = state
+                .transfers
+                .iter()
+                .find(|t| t.get_uuid() == state.selected_transfer)
+            {
+                ct_dispatch.reduce_mut(|ct| ct.transfer = transfer.clone());
+            }
+        }
+    };
+
+    let on_volume_change = {
+        let volume = volume.clone();
+        Callback::from(move |e: Event| {
+            let input = e
+                .target()
+                .and_then(|t| t.dyn_into::<HtmlInputElement>().ok());
+            if let Some(value) = input.and_then(|i| i.value().parse::<f32>().ok()) {
+                volume.set(value);
+            }
+        })
+    };
+    let on_pattern_change = {
+        let pattern = pattern.clone();
+        Callback::from(move |e: Event| {
+            if let Some(input) = e
+                .target()
+                .and_then(|t| t.dyn_into::<HtmlInputElement>().ok())
+            {
+                pattern.set(input.value());
+            }
+        })
+    };
+    let on_plate_change = |handle: &UseStateHandle<Uuid>| {
+        let handle = handle.clone();
+        Callback::from(move |e: Event| {
+            let select = e
+                .target()
+                .and_then(|t| t.dyn_into::<HtmlSelectElement>().ok());
+            if let Some(id) = select.and_then(|s| s.value().parse::<u128>().ok()) {
+                handle.set(Uuid::from_u128(id));
+            }
+        })
+    };
+
+    let on_set_volume = {
+        let edit = edit.clone();
+        let ids = ids.clone();
+        let volume = *volume;
+        Callback::from(move |_: MouseEvent| {
+            let ids = ids.clone();
+            edit(Box::new(move |state| {
+                for transfer in state.transfers.iter_mut() {
+                    if ids.contains(&transfer.get_uuid()) {
+                        transfer.volume = volume;
+                    }
+                }
+            }));
+        })
+    };
+    let on_rename = {
+        let edit = edit.clone();
+        let ids = ids.clone();
+        let pattern = (*pattern).clone();
+        Callback::from(move |_: MouseEvent| {
+            let ids = ids.clone();
+            let pattern = pattern.clone();
+            edit(Box::new(move |state| {
+                let selected = state
+                    .transfers
+                    .iter_mut()
+                    .filter(|t| ids.contains(&t.get_uuid()));
+                for (n, transfer) in selected.enumerate() {
+                    transfer.name = apply_name_pattern(&pattern, &transfer.name, n + 1);
+                }
+            }));
+        })
+    };
+    let on_move = {
+        let edit = edit.clone();
+        let ids = ids.clone();
+        let (source_id, dest_id) = (*source_id, *dest_id);
+        Callback::from(move |_: MouseEvent| {
+            let ids = ids.clone();
+            edit(Box::new(move |state| {
+                let errors: Vec<String> = ids
+                    .iter()
+                    .filter_map(|id| state.move_transfer_to_plates(*id, source_id, dest_id).err())
+                    .collect();
+                if !errors.is_empty() {
+                    notify(Level::Warning, errors.join(" "));
+                }
+            }));
+        })
+    };
+    let on_duplicate = {
+        let edit = edit.clone();
+        let ids = ids.clone();
+        Callback::from(move |_: MouseEvent| {
+            let ids = ids.clone();
+            edit(Box::new(move |state| {
+                for id in ids.iter() {
+                    state.duplicate_transfer(*id);
+                }
+            }));
+        })
+    };
+
+    let dialog_ref = use_node_ref();
+    let on_delete = {
+        let ids = ids.clone();
+        let names: Vec<String> = main_state
+            .transfers
+            .iter()
+            .filter(|t| ids.contains(&t.get_uuid()))
+            .map(|t| t.name.clone())
+            .collect();
+        let dialog_ref = dialog_ref.clone();
+        Callback::from(move |_: MouseEvent| {
+            let main_dispatch = main_dispatch.clone();
+            let ids = ids.clone();
+            let dialog_ref = dialog_ref.clone();
+            confirm(
+                format!("Delete {} Transfers?", ids.len()),
+                format!("This will delete: {}.", names.join(", ")),
+                "Delete Transfers",
+                Callback::from(move |_| {
+                    take_snapshot("Before deleting transfers");
+                    main_dispatch.reduce_mut(|state| state.del_transfers(&ids));
+                    if let Some(dialog) = dialog_ref.cast::<HtmlDialogElement>() {
+                        dialog.close();
+                    }
+                }),
+            );
+        })
+    };
+    let onclose = {
+        let close_callback = props.close_callback.clone();
+        Callback::from(move |_: Event| {
+            close_callback.emit(());
+        })
+    };
+
+    {
+        let dialog_ref = dialog_ref.clone();
+
+        use_effect_with_deps(
+            |dialog_ref| {
+                dialog_ref
+                    .cast::<HtmlDialogElement>()
+                    .unwrap()
+                    .show_modal()
+                    .ok();
+            },
+            dialog_ref,
+        );
+    }
+
+    let source_options = main_state
+        .source_plates
+        .iter()
+        .map(|spi| {
+            html! {
+                <option value={spi.get_uuid().as_u128().to_string()}
+                selected={spi.get_uuid() == *source_id}>{spi.name.clone()}</option>
+            }
+        })
+        .collect::<Html>();
+    let dest_options = main_state
+        .destination_plates
+        .iter()
+        .map(|dpi| {
+            html! {
+                <option value={dpi.get_uuid().as_u128().to_string()}
+                selected={dpi.get_uuid() == *dest_id}>{dpi.name.clone()}</option>
+            }
+        })
+        .collect::<Html>();
+
+    html! {
+        <dialog ref={dialog_ref} class="dialog bulk_edit_dialog" onclose={onclose}>
+            <h2>{format!("Edit {} Transfers", ids.len())}</h2>
+            <form>
+                <label>{"Volume:"}
+                <input type="number" min="0" step="0.1" value={volume.to_string()}
+                 onchange={on_volume_change}/>
+                </label>
+                <input type="button" value="Set Volume" onclick={on_set_volume}/>
+            </form>
+            <form>
+                <label>{"Name:"}
+                <input type="text" value={(*pattern).clone()} onchange={on_pattern_change}/>
+                </label>
+                <input type="button" value="Rename" onclick={on_rename}/>
+                <p>{"{name} is replaced by the current name and {n} counts up from 1."}</p>
+            </form>
+            <form>
+                <label>{"Source:"}
+                <select onchange={on_plate_change(&source_id)}>{source_options}</select>
+                </label>
+                <label>{"Destination:"}
+                <select onchange={on_plate_change(&dest_id)}>{dest_options}</select>
+                </label>
+                <input type="button" value="Move to Plates" onclick={on_move}/>
+            </form>
+            <div>
+            <button onclick={on_duplicate}>{"Duplicate"}</button>
+            <button onclick={on_delete}>{"Delete"}</button>
+            </div>
+            <form class="modal_close" method="dialog"><button /></form>
+        </dialog>
+    }
+}
@@ -0,0 +1,240 @@
+#![allow(non_snake_case)]
+
+use std::collections::BTreeSet;
+
+use wasm_bindgen::JsCast;
+use web_sys::HtmlSelectElement;
+use yew::prelude::*;
+use yewdux::prelude::*;
+
+use crate::components::states::MainState;
+use crate::data::cherry_pick::{cherry_picks_to_region, parse_cherry_picks, CherryPick};
+use crate::data::transfer::Transfer;
+use crate::data::transfer_region::TransferRegion;
+use crate::data::well_name::{num_to_letters, IndexOrder, WellNameStyle};
+
+const PREVIEW_ROWS: usize = 20;
+
+#[derive(PartialEq, Properties)]
+pub struct CherryPickImportProps {
+    // Contents of the pick list
+    pub text: String,
+    // Called after importing or cancelling
+    pub on_done: Callback<()>,
+}
+
+#[function_component]
+pub fn CherryPickImport(props: &CherryPickImportProps) -> Html {
+    let (main_state, main_dispatch) = use_store::<MainState>();
+    // Plate names to keep, when the file lists several plates
+    let source_filter: UseStateHandle<Option<String>> = use_state(|| None);
+    let dest_filter: UseStateHandle<Option<String>> = use_state(|| None);
+    // How wells given as plain numbers are counted, starting from the export setting
+    let index_order = {
+        let export_well_style = main_state.preferences.export_well_style;
+        use_state(move || match export_well_style {
+            WellNameStyle::Index(order) => order,
+            _ => IndexOrder::RowMajor,
+        })
+    };
+
+    let spi = main_state
+        .source_plates
+        .iter()
+        .find(|spi| spi.get_uuid() == main_state.selected_source_plate)
+        .cloned();
+    let dpi = main_state
+        .destination_plates
+        .iter()
+        .find(|dpi| dpi.get_uuid() == main_state.selected_dest_plate)
+        .cloned();
+
+    let parsed = match (&spi, &dpi) {
+        (Some(spi), Some(dpi)) => Some(parse_cherry_picks(
+            &props.text,
+            spi.plate.size(),
+            dpi.plate.size(),
+            *index_order,
+        )),
+        _ => None,
+    };
+    let (source_names, dest_names) = match &parsed {
+        Some(Ok((picks, _))) => (
+            picks
+                .iter()
+                .filter_map(|p| p.source_plate.clone())
+                .collect::<BTreeSet<String>>(),
+            picks
+                .iter()
+                .filter_map(|p| p.destination_plate.clone())
+                .collect::<BTreeSet<String>>(),
+        ),
+        _ => (BTreeSet::new(), BTreeSet::new()),
+    };
+    let keep = |filter: &Option<String>, names: &BTreeSet<String>, name: &Option<String>| match (
+        filter.as_ref().or(names.iter().next()),
+        name,
+    ) {
+        (Some(filter), Some(name)) => filter == name,
+        _ => true,
+    };
+    let picks: Vec<CherryPick> = match &parsed {
+        Some(Ok((picks, _))) => picks
+            .iter()
+            .filter(|p| keep(&source_filter, &source_names, &p.source_plate))
+            .filter(|p| keep(&dest_filter, &dest_names, &p.destination_plate))
+            .cloned()
+            .collect(),
+        _ => Vec::new(),
+    };
+
+    let on_source_filter_change = {
+        let source_filter = source_filter.clone();
+        Callback::from(move |e: Event| {
+            if let Some(select) = e
+                .target()
+                .and_then(|t| t.dyn_into::<HtmlSelectElement>().ok())
+            {
+                source_filter.set(Some(select.value()));
+            }
+        })
+    };
+    let on_dest_filter_change = {
+        let dest_filter = dest_filter.clone();
+        Callback::from(move |e: Event| {
+            if let Some(select) = e
+                .target()
+                .and_then(|t| t.dyn_into::<HtmlSelectElement>().ok())
+            {
+                dest_filter.set(Some(select.value()));
+            }
+        })
+    };
+
+    let on_index_order_change = {
+        let index_order = index_order.clone();
+        Callback::from(move |e: Event| {
+            if let Some(select) = e
+                .target()
+                .and_then(|t| t.dyn_into::<HtmlSelectElement>().ok())
+            {
+                index_order.set(match select.value().as_str() {
+                    "column" => IndexOrder::ColumnMajor,
+                    _ => IndexOrder::RowMajor,
+                });
+            }
+        })
+    };
+
+    let on_import = {
+        let picks = picks.clone();
+        let (spi, dpi) = (spi.clone(), dpi.clone());
+        let on_done = props.on_done.clone();
+        Callback::from(move |_: MouseEvent| {
+            if let (Some(spi), Some(dpi)) = (spi.clone(), dpi.clone()) {
+                if picks.is_empty() {
+                    return;
+                }
+                let custom_region = cherry_picks_to_region(&picks);
+                let transfer_region = TransferRegion {
+                    source_region: custom_region.clone(),
+                    dest_region: custom_region,
+                    interleave_source: (1, 1),
+                    interleave_dest: (1, 1),
+                    fill_order: Default::default(),
+                    source_plate: spi.plate,
+                    dest_plate: dpi.plate,
+                };
+                let transfer = Transfer::new(spi, dpi, transfer_region, "Cherry Pick".to_string());
+                main_dispatch.reduce_mut(|state| {
+                    state.transfers.push(transfer);
+                    state.selected_transfer = state
+                        .transfers
+                        .last()
+                        .expect("An element should have just been added")
+                        .get_uuid();
+                });
+                on_done.emit(());
+            }
+        })
+    };
+    let on_cancel = {
+        let on_done = props.on_done.clone();
+        Callback::from(move |_: MouseEvent| {
+            on_done.emit(());
+        })
+    };
+
+    let well = |w: (u8, u8)| format!("{}{}", num_to_letters(w.0).unwrap_or_default(), w.1);
+    let preview = picks
+        .iter()
+        .take(PREVIEW_ROWS)
+        .map(|p| {
+            html! {
+                <tr>
+                    <td>{well(p.source_well)}</td>
+                    <td>{well(p.destination_well)}</td>
+                    <td>{p.volume.map(|v| v.to_string()).unwrap_or("Default".to_string())}</td>
+                </tr>
+            }
+        })
+        .collect::<Html>();
+    let errors = match &parsed {
+        Some(Ok((_, errors))) => errors
+            .iter()
+            .map(|e| html! { <li>{format!("Line {}: {}", e.line, e.message)}</li> })
+            .collect::<Html>(),
+        Some(Err(e)) => html! { <li>{e}</li> },
+        None => html! {},
+    };
+    let name_options = |names: &BTreeSet<String>| {
+        names
+            .iter()
+            .map(|n| html! { <option value={n.clone()}>{n}</option> })
+            .collect::<Html>()
+    };
+
+    html! {
+        <div class="cherry_pick_import">
+            if let (Some(spi), Some(dpi)) = (&spi, &dpi) {
+                <p>{format!("Importing from {} into {}", spi.name, dpi.name)}</p>
+                <label>{"Numbered wells count:"}
+                <select onchange={on_index_order_change}>
+                    <option value="row" selected={*index_order == IndexOrder::RowMajor}>{"Across rows"}</option>
+                    <option value="column" selected={*index_order == IndexOrder::ColumnMajor}>{"Down columns"}</option>
+                </select>
+                </label>
+                if source_names.len() > 1 {
+                    <label>{"Source plate in file:"}
+                    <select onchange={on_source_filter_change}>{name_options(&source_names)}</select>
+                    </label>
+                }
+                if dest_names.len() > 1 {
+                    <label>{"Destination plate in file:"}
+                    <select onchange={on_dest_filter_change}>{name_options(&dest_names)}</select>
+                    </label>
+                }
+                if parsed.is_some() {
+                    <h3>{format!("{} transfers", picks.len())}</h3>
+                    <table>
+                        <tr><th>{"Source"}</th><th>{"Destination"}</th><th>{"Volume"}</th></tr>
+                        { preview }
+                    </table>
+                    if picks.len() > PREVIEW_ROWS {
+                        <p>{format!("...and {} more", picks.len() - PREVIEW_ROWS)}</p>
+                    }
+                    <ul class="import_errors">{ errors }</ul>
+                }
+                <div>
+                <button onclick={on_import} disabled={picks.is_empty()}>{"Import"}</button>
+                <button onclick={on_cancel}>{"Cancel"}</button>
+                </div>
+            } else {
+                <p>{"Select a source and destination plate first."}</p>
+                <div>
+                <button onclick={on_cancel}>{"Cancel"}</button>
+                </div>
+            }
+        </div>
+    }
+}
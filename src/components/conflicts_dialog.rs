@@ -0,0 +1,114 @@
+#![allow(non_snake_case)]
+
+use web_sys::HtmlDialogElement;
+use yew::prelude::*;
+use yewdux::prelude::*;
+
+use crate::components::states::MainState;
+use crate::data::conflicts::find_conflicts;
+use crate::data::well_name::{format_well, WellNameStyle};
+
+#[derive(PartialEq, Properties)]
+pub struct ConflictsDialogProps {
+    pub close_callback: Callback<()>,
+}
+
+#[function_component]
+pub fn ConflictsDialog(props: &ConflictsDialogProps) -> Html {
+    let (main_state, _) = use_store::<MainState>();
+    let conflicts = find_conflicts(&main_state.transfers, &main_state.destination_plates);
+
+    let dialog_ref = use_node_ref();
+    let onclose = {
+        let close_callback = props.close_callback.clone();
+        Callback::from(move |_: Event| {
+            close_callback.emit(());
+        })
+    };
+
+    {
+        let dialog_ref = dialog_ref.clone();
+
+        use_effect_with_deps(
+            |dialog_ref| {
+                dialog_ref
+                    .cast::<HtmlDialogElement>()
+                    .unwrap()
+                    .show_modal()
+                    .ok();
+            },
+            dialog_ref,
+        );
+    }
+
+    let plate = |id| {
+        main_state
+            .source_plates
+            .iter()
+            .chain(main_state.destination_plates.iter())
+            .find(|pi| pi.get_uuid() == id)
+    };
+    let well = |id, w| match plate(id) {
+        Some(pi) => format_well(w, pi.plate.size(), WellNameStyle::Letters),
+        None => format_well(w, (0, 0), WellNameStyle::Letters),
+    };
+    let transfer_name = |id| {
+        main_state
+            .transfers
+            .iter()
+            .find(|t| t.get_uuid() == id)
+            .map(|t| t.name.clone())
+            .unwrap_or_default()
+    };
+    let rows = conflicts
+        .iter()
+        .map(|c| {
+            let sources = c
+                .sources
+                .iter()
+                .map(|(id, w)| {
+                    let name = plate(*id).map(|pi| pi.name.clone()).unwrap_or_default();
+                    format!("{} {}", name, well(*id, *w))
+                })
+                .collect::<Vec<_>>()
+                .join(", ");
+            let transfers = c
+                .transfers
+                .iter()
+                .map(|id| transfer_name(*id))
+                .collect::<Vec<_>>()
+                .join(", ");
+            html! {
+                <tr class={classes!(if c.pooling_allowed { "pooled" } else { "conflict" })}>
+                    <td>{plate(c.dest_plate).map(|pi| pi.name.clone()).unwrap_or_default()}</td>
+                    <td>{well(c.dest_plate, c.well)}</td>
+                    <td>{transfers}</td>
+                    <td>{sources}</td>
+                    <td>{if c.pooling_allowed { "Pooled" } else { "Conflict" }}</td>
+                </tr>
+            }
+        })
+        .collect::<Html>();
+    let unintended = conflicts.iter().filter(|c| !c.pooling_allowed).count();
+
+    html! {
+        <dialog ref={dialog_ref} class="dialog conflicts_dialog" onclose={onclose}>
+            <h2>{"Destination Conflicts"}</h2>
+            if conflicts.is_empty() {
+                <p>{"No destination well is written more than once."}</p>
+            } else {
+                <p>{format!("{} wells written more than once, {} not marked as pooling.",
+                            conflicts.len(), unintended)}</p>
+                <p>{"Tick \"Pooling allowed\" on a transfer if its overlaps are intended."}</p>
+                <table>
+                    <tr>
+                        <th>{"Plate"}</th><th>{"Well"}</th><th>{"Transfers"}</th>
+                        <th>{"Sources"}</th><th>{"Status"}</th>
+                    </tr>
+                    { rows }
+                </table>
+            }
+            <form class="modal_close" method="dialog"><button /></form>
+        </dialog>
+    }
+}
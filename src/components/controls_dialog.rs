@@ -0,0 +1,330 @@
+#![allow(non_snake_case)]
+
+use std::collections::HashSet;
+
+use uuid::Uuid;
+use wasm_bindgen::JsCast;
+use web_sys::{HtmlDialogElement, HtmlInputElement, HtmlSelectElement};
+use yew::prelude::*;
+use yewdux::prelude::*;
+
+use crate::components::plates::destination_plate::set_plate_masks;
+use crate::components::states::MainState;
+use crate::data::controls::{
+    control_layout, control_transfers, sample_wells, Control, ControlPattern,
+};
+use crate::data::well_name::{format_well_list, parse_well};
+
+#[derive(PartialEq, Properties)]
+pub struct ControlsDialogProps {
+    pub close_callback: Callback<()>,
+}
+
+#[function_component]
+pub fn ControlsDialog(props: &ControlsDialogProps) -> Html {
+    let (main_state, main_dispatch) = use_store::<MainState>();
+    // (name, source well as typed)
+    let controls: UseStateHandle<Vec<(String, String)>> = use_state(|| {
+        vec![
+            ("Positive".to_string(), "A1".to_string()),
+            ("Negative".to_string(), "B1".to_string()),
+        ]
+    });
+    let pattern = use_state(|| ControlPattern::EdgeColumns { width: 2 });
+    let destinations: UseStateHandle<HashSet<Uuid>> =
+        use_state(|| HashSet::from([main_state.selected_dest_plate]));
+
+    let spi = main_state
+        .source_plates
+        .iter()
+        .find(|spi| spi.get_uuid() == main_state.selected_source_plate)
+        .cloned();
+    let dest_plates: Vec<_> = main_state
+        .destination_plates
+        .iter()
+        .filter(|dpi| destinations.contains(&dpi.get_uuid()))
+        .cloned()
+        .collect();
+
+    let parsed: Option<Result<Vec<Control>, String>> = spi.as_ref().map(|spi| {
+        controls
+            .iter()
+            .map(|(name, well)| {
+                parse_well(well, spi.plate.size())
+                    .map(|source_well| Control {
+                        name: name.clone(),
+                        source_well,
+                    })
+                    .map_err(|e| format!("{}: {}", name, e))
+            })
+            .collect()
+    });
+
+    let on_control_change = {
+        let controls = controls.clone();
+        Callback::from(move |(index, is_name, e): (usize, bool, Event)| {
+            if let Some(input) = e
+                .target()
+                .and_then(|t| t.dyn_into::<HtmlInputElement>().ok())
+            {
+                let mut list = (*controls).clone();
+                if let Some(control) = list.get_mut(index) {
+                    if is_name {
+                        control.0 = input.value();
+                    } else {
+                        control.1 = input.value();
+                    }
+                }
+                controls.set(list);
+            }
+        })
+    };
+    let on_control_remove = {
+        let controls = controls.clone();
+        Callback::from(move |index: usize| {
+            let mut list = (*controls).clone();
+            if index < list.len() {
+                list.remove(index);
+            }
+            controls.set(list);
+        })
+    };
+    let on_control_add = {
+        let controls = controls.clone();
+        Callback::from(move |_: MouseEvent| {
+            let mut list = (*controls).clone();
+            list.push((format!("Control {}", list.len() + 1), String::new()));
+            controls.set(list);
+        })
+    };
+    let on_pattern_change = {
+        let pattern = pattern.clone();
+        Callback::from(move |e: Event| {
+            if let Some(select) = e
+                .target()
+                .and_then(|t| t.dyn_into::<HtmlSelectElement>().ok())
+            {
+                pattern.set(match select.value().as_str() {
+                    "checkerboard" => ControlPattern::Checkerboard { width: 2 },
+                    "rows" => ControlPattern::DistributedRows { per_row: 2 },
+                    _ => ControlPattern::EdgeColumns { width: 2 },
+                });
+            }
+        })
+    };
+    let on_pattern_size_change = {
+        let pattern = pattern.clone();
+        Callback::from(move |e: Event| {
+            let input = e
+                .target()
+                .and_then(|t| t.dyn_into::<HtmlInputElement>().ok());
+            if let Some(n) = input.and_then(|i| i.value().parse::<u8>().ok()) {
+                pattern.set(match *pattern {
+                    ControlPattern::EdgeColumns { .. } => ControlPattern::EdgeColumns { width: n },
+                    ControlPattern::Checkerboard { .. } => {
+                        ControlPattern::Checkerboard { width: n }
+                    }
+                    ControlPattern::DistributedRows { .. } => {
+                        ControlPattern::DistributedRows { per_row: n }
+                    }
+                });
+            }
+        })
+    };
+    let on_destination_toggle = {
+        let destinations = destinations.clone();
+        Callback::from(move |(id, e): (Uuid, Event)| {
+            if let Some(input) = e
+                .target()
+                .and_then(|t| t.dyn_into::<HtmlInputElement>().ok())
+            {
+                let mut set = (*destinations).clone();
+                if input.checked() {
+                    set.insert(id);
+                } else {
+                    set.remove(&id);
+                }
+                destinations.set(set);
+            }
+        })
+    };
+
+    let on_exclude = {
+        let pattern = *pattern;
+        let count = controls.len();
+        let dest_plates = dest_plates.clone();
+        Callback::from(move |_: MouseEvent| {
+            // Adds the control wells to each plate's excluded wells
+            let masks = dest_plates
+                .iter()
+                .map(|dpi| {
+                    let mut mask = dpi.mask.clone();
+                    for (_, well) in control_layout(pattern, dpi.plate.size(), count) {
+                        if !mask.contains(&well) {
+                            mask.push(well);
+                        }
+                    }
+                    (dpi.get_uuid(), mask)
+                })
+                .collect();
+            set_plate_masks(masks);
+        })
+    };
+
+    let dialog_ref = use_node_ref();
+    let on_create = {
+        let parsed = parsed.clone();
+        let pattern = *pattern;
+        let (spi, dest_plates) = (spi.clone(), dest_plates.clone());
+        let dialog_ref = dialog_ref.clone();
+        Callback::from(move |_: MouseEvent| {
+            if let (Some(spi), Some(Ok(controls))) = (&spi, &parsed) {
+                let transfers = control_transfers(controls, pattern, spi, &dest_plates);
+                if transfers.is_empty() {
+                    return;
+                }
+                main_dispatch.reduce_mut(|state| {
+                    state.transfers.extend(transfers);
+                    state.selected_transfer = state
+                        .transfers
+                        .last()
+                        .expect("Elements should have just been added")
+                        .get_uuid();
+                });
+                if let Some(dialog) = dialog_ref.cast::<HtmlDialogElement>() {
+                    dialog.close();
+                }
+            }
+        })
+    };
+    let on_cancel = {
+        let dialog_ref = dialog_ref.clone();
+        Callback::from(move |_: MouseEvent| {
+            if let Some(dialog) = dialog_ref.cast::<HtmlDialogElement>() {
+                dialog.close();
+            }
+        })
+    };
+    let onclose = {
+        let close_callback = props.close_callback.clone();
+        Callback::from(move |_: Event| {
+            close_callback.emit(());
+        })
+    };
+
+    {
+        let dialog_ref = dialog_ref.clone();
+
+        use_effect_with_deps(
+            |dialog_ref| {
+                dialog_ref
+                    .cast::<HtmlDialogElement>()
+                    .unwrap()
+                    .show_modal()
+                    .ok();
+            },
+            dialog_ref,
+        );
+    }
+
+    let control_rows = controls
+        .iter()
+        .enumerate()
+        .map(|(index, (name, well))| {
+            let on_name = on_control_change.reform(move |e| (index, true, e));
+            let on_well = on_control_change.reform(move |e| (index, false, e));
+            let on_remove = on_control_remove.reform(move |_: MouseEvent| index);
+            html! {
+                <tr>
+                    <td><input type="text" value={name.clone()} onchange={on_name}/></td>
+                    <td><input type="text" value={well.clone()} onchange={on_well}/></td>
+                    <td><input type="button" value="Remove" onclick={on_remove}/></td>
+                </tr>
+            }
+        })
+        .collect::<Html>();
+    let dest_options = main_state
+        .destination_plates
+        .iter()
+        .map(|dpi| {
+            let id = dpi.get_uuid();
+            let onchange = on_destination_toggle.reform(move |e| (id, e));
+            html! {
+                <label>
+                <input type="checkbox" checked={destinations.contains(&id)} {onchange}/>
+                {&dpi.name}
+                </label>
+            }
+        })
+        .collect::<Html>();
+    // Wells taken by controls, to keep samples out of them
+    let masks = dest_plates
+        .iter()
+        .map(|dpi| {
+            let layout = control_layout(*pattern, dpi.plate.size(), controls.len());
+            let wells: Vec<(u8, u8)> = layout.iter().map(|(_, w)| *w).collect();
+            let free = sample_wells(&layout, dpi.plate.size()).len();
+            html! {
+                <li>{format!("{}: {} ({} wells left for samples)",
+                             dpi.name, format_well_list(&wells, dpi.plate.size()), free)}</li>
+            }
+        })
+        .collect::<Html>();
+    let (pattern_value, pattern_size, size_label) = match *pattern {
+        ControlPattern::EdgeColumns { width } => ("edges", width, "Columns per side:"),
+        ControlPattern::Checkerboard { width } => ("checkerboard", width, "Columns per side:"),
+        ControlPattern::DistributedRows { per_row } => ("rows", per_row, "Wells per row:"),
+    };
+    let can_create = matches!(&parsed, Some(Ok(c)) if !c.is_empty()) && !dest_plates.is_empty();
+
+    html! {
+        <dialog ref={dialog_ref} class="dialog controls_dialog" onclose={onclose}>
+            <h2>{"Place Controls"}</h2>
+            if let Some(spi) = &spi {
+                <p>{format!("Controls come from {}", spi.name)}</p>
+                <table>
+                    <tr><th>{"Control"}</th><th>{"Source Well"}</th><th /></tr>
+                    { control_rows }
+                </table>
+                <input type="button" value="Add Control" onclick={on_control_add}/>
+                <div>
+                <label>{"Pattern:"}
+                <select onchange={on_pattern_change}>
+                    <option value="edges" selected={pattern_value == "edges"}>
+                        {ControlPattern::EdgeColumns { width: 0 }.label()}</option>
+                    <option value="checkerboard" selected={pattern_value == "checkerboard"}>
+                        {ControlPattern::Checkerboard { width: 0 }.label()}</option>
+                    <option value="rows" selected={pattern_value == "rows"}>
+                        {ControlPattern::DistributedRows { per_row: 0 }.label()}</option>
+                </select>
+                </label>
+                <label>{size_label}
+                <input type="number" min="1" value={pattern_size.to_string()}
+                 onchange={on_pattern_size_change}/>
+                </label>
+                </div>
+                <h3>{"Destination plates"}</h3>
+                <div>{ dest_options }</div>
+                if let Some(Err(e)) = &parsed {
+                    <p class="validation_error">{e}</p>
+                }
+                if !dest_plates.is_empty() {
+                    <h3>{"Control wells"}</h3>
+                    <p>{"Keep samples out of these wells, e.g. as excluded wells in a randomized layout."}</p>
+                    <ul class="control_wells">{ masks }</ul>
+                    <p>{"Excluding them on the plates keeps other transfers out. \
+                         Control transfers into excluded wells are left out too, \
+                         so only do this if the controls are placed another way."}</p>
+                    <button onclick={on_exclude}>{"Exclude Control Wells"}</button>
+                }
+                <div>
+                <button onclick={on_create} disabled={!can_create}>{"Create Transfers"}</button>
+                <button onclick={on_cancel}>{"Cancel"}</button>
+                </div>
+            } else {
+                <p>{"Select a source plate first."}</p>
+            }
+            <form class="modal_close" method="dialog"><button /></form>
+        </dialog>
+    }
+}
@@ -0,0 +1,201 @@
+#![allow(non_snake_case)]
+
+use wasm_bindgen::JsCast;
+use web_sys::{HtmlDialogElement, HtmlInputElement, HtmlSelectElement};
+use yew::prelude::*;
+use yewdux::prelude::*;
+
+use crate::components::notifications::{notify, Level};
+use crate::components::states::{CurrentTransfer, MainState};
+use crate::data::csv::{state_to_csv, CsvExportOptions, TransferOrdering};
+use crate::data::lint::{has_errors, lint_state, LintSeverity};
+use crate::data::well_name::{IndexOrder, WellNameStyle};
+
+#[derive(PartialEq, Properties)]
+pub struct ExportDialogProps {
+    pub close_callback: Callback<()>,
+    pub save_callback: Callback<String>,
+}
+
+#[function_component]
+pub fn ExportDialog(props: &ExportDialogProps) -> Html {
+    let (main_state, main_dispatch) = use_store::<MainState>();
+    let (ct_state, _) = use_store::<CurrentTransfer>();
+    let issues = lint_state(&main_state, Some(&ct_state.transfer));
+    let errors = has_errors(&issues);
+    // Exporting with errors has to be asked for explicitly
+    let confirmed = use_state_eq(|| false);
+    let well_style = main_state.preferences.export_well_style;
+    let ordering = main_state.preferences.export_ordering;
+
+    let on_style_change = {
+        let main_dispatch = main_dispatch.clone();
+        Callback::from(move |e: Event| {
+            let select = e
+                .target()
+                .and_then(|t| t.dyn_into::<HtmlSelectElement>().ok());
+            if let Some(select) = select {
+                if let Some(style) = select
+                    .value()
+                    .parse::<usize>()
+                    .ok()
+                    .and_then(|i| WellNameStyle::ALL.get(i))
+                {
+                    main_dispatch.reduce_mut(|state| {
+                        state.preferences.export_well_style = *style;
+                    });
+                }
+            }
+        })
+    };
+
+    let on_ordering_change = {
+        let main_dispatch = main_dispatch.clone();
+        Callback::from(move |e: Event| {
+            let select = e
+                .target()
+                .and_then(|t| t.dyn_into::<HtmlSelectElement>().ok());
+            if let Some(select) = select {
+                if let Some(ordering) = select
+                    .value()
+                    .parse::<usize>()
+                    .ok()
+                    .and_then(|i| TransferOrdering::ALL.get(i))
+                {
+                    main_dispatch.reduce_mut(|state| {
+                        state.preferences.export_ordering = *ordering;
+                    });
+                }
+            }
+        })
+    };
+
+    let on_confirm_change = {
+        let confirmed = confirmed.clone();
+        Callback::from(move |e: Event| {
+            let input = e
+                .target()
+                .and_then(|t| t.dyn_into::<HtmlInputElement>().ok());
+            if let Some(input) = input {
+                confirmed.set(input.checked());
+            }
+        })
+    };
+
+    let dialog_ref = use_node_ref();
+    let on_export = {
+        let main_state = main_state.clone();
+        let save_callback = props.save_callback.clone();
+        let dialog_ref = dialog_ref.clone();
+        Callback::from(move |_: MouseEvent| {
+            let options = CsvExportOptions {
+                well_style: main_state.preferences.export_well_style,
+                ordering: main_state.preferences.export_ordering,
+            };
+            match state_to_csv(&main_state, &options) {
+                Ok(csv) => save_callback.emit(csv),
+                Err(e) => notify(Level::Error, format!("CSV export failed: {}", e)),
+            }
+            if let Some(dialog) = dialog_ref.cast::<HtmlDialogElement>() {
+                dialog.close();
+            }
+        })
+    };
+    let on_cancel = {
+        let dialog_ref = dialog_ref.clone();
+        Callback::from(move |_: MouseEvent| {
+            if let Some(dialog) = dialog_ref.cast::<HtmlDialogElement>() {
+                dialog.close();
+            }
+        })
+    };
+    let onclose = {
+        let close_callback = props.close_callback.clone();
+        Callback::from(move |_: Event| {
+            close_callback.emit(());
+        })
+    };
+
+    {
+        let dialog_ref = dialog_ref.clone();
+
+        use_effect_with_deps(
+            |dialog_ref| {
+                dialog_ref
+                    .cast::<HtmlDialogElement>()
+                    .unwrap()
+                    .show_modal()
+                    .ok();
+            },
+            dialog_ref,
+        );
+    }
+
+    let style_options = WellNameStyle::ALL
+        .iter()
+        .enumerate()
+        .map(|(i, style)| {
+            html! {
+                <option value={i.to_string()} selected={*style == well_style}>
+                    {style.label()}
+                </option>
+            }
+        })
+        .collect::<Html>();
+    let ordering_options = TransferOrdering::ALL
+        .iter()
+        .enumerate()
+        .map(|(i, o)| {
+            html! {
+                <option value={i.to_string()} selected={*o == ordering}>
+                    {o.label()}
+                </option>
+            }
+        })
+        .collect::<Html>();
+
+    let lint_report = issues
+        .iter()
+        .map(|issue| {
+            let (class, prefix) = match issue.severity {
+                LintSeverity::Error => ("lint_error", "Error: "),
+                LintSeverity::Warning => ("lint_warning", "Warning: "),
+            };
+            html! { <li class={class}>{prefix}{&issue.message}</li> }
+        })
+        .collect::<Html>();
+
+    html! {
+        <dialog ref={dialog_ref} class="dialog export_dialog" onclose={onclose}>
+            <h2>{"Export as CSV"}</h2>
+            <label>{"Well names:"}
+            <select onchange={on_style_change}>{ style_options }</select>
+            </label>
+            <label>{"Row order:"}
+            <select onchange={on_ordering_change}>{ ordering_options }</select>
+            </label>
+            if ordering != TransferOrdering::AsEntered {
+                <p>{"Rows are grouped by source and destination plate to avoid plate swaps."}</p>
+            }
+            if matches!(well_style, WellNameStyle::Index(IndexOrder::ColumnMajor) | WellNameStyle::RowColumn) {
+                <p>{"Transfers exported with these well names can't be imported from CSV again."}</p>
+            }
+            <p>{"CSV export is not a backup of your project. Export as JSON if you'd like to back up your work!"}</p>
+            if !issues.is_empty() {
+                <h3>{"Before you export"}</h3>
+                <ul class="lint_report">{ lint_report }</ul>
+            }
+            if errors {
+                <label>
+                <input type="checkbox" onchange={on_confirm_change} checked={*confirmed}/>
+                {"Export anyway, despite the errors above"}
+                </label>
+            }
+            <div>
+            <button onclick={on_export} disabled={errors && !*confirmed}>{"Export"}</button>
+            <button onclick={on_cancel}>{"Cancel"}</button>
+            </div>
+            <form class="modal_close" method="dialog"><button /></form>
+        </dialog>
+    }
+}
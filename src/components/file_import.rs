@@ -0,0 +1,108 @@
+#![allow(non_snake_case)]
+
+use wasm_bindgen::closure::Closure;
+use wasm_bindgen::JsCast;
+use web_sys::{DragEvent, File, HtmlInputElement};
+use yew::prelude::*;
+
+use super::notifications::{notify, Level};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LoadedFile {
+    pub name: String,
+    pub text: String,
+}
+
+/// Reads `file` as text and passes it on once it has loaded.
+pub fn read_file(file: File, on_load: Callback<LoadedFile>) {
+    let fr = web_sys::FileReader::new().unwrap();
+    let name = file.name();
+    let reader = fr.clone();
+    // Called exactly once, whether or not the read worked, which frees the closure
+    let onloadend =
+        Closure::once_into_js(
+            move || match reader.result().ok().and_then(|v| v.as_string()) {
+                Some(text) => on_load.emit(LoadedFile { name, text }),
+                None => notify(Level::Error, format!("Could not read {}.", name)),
+            },
+        );
+    fr.set_onloadend(Some(onloadend.unchecked_ref()));
+    if fr.read_as_text(&file).is_err() {
+        notify(Level::Error, format!("Could not read {}.", file.name()));
+    }
+}
+
+#[derive(PartialEq, Properties)]
+pub struct FilePickerProps {
+    // File types offered by the browser's picker, as in <input accept="...">
+    #[prop_or_default]
+    pub accept: AttrValue,
+    pub on_load: Callback<LoadedFile>,
+}
+
+#[function_component]
+pub fn FilePicker(props: &FilePickerProps) -> Html {
+    let onchange = {
+        let on_load = props.on_load.clone();
+        Callback::from(move |e: Event| {
+            let input = e
+                .target()
+                .and_then(|t| t.dyn_into::<HtmlInputElement>().ok());
+            if let Some(file) = input.and_then(|i| i.files()).and_then(|f| f.get(0)) {
+                read_file(file, on_load.clone());
+            }
+        })
+    };
+
+    html! {
+        <div class="file_picker">
+            <input type="file" accept={props.accept.clone()} {onchange}/>
+            <span>{"or drop a file anywhere on the window"}</span>
+        </div>
+    }
+}
+
+/// Calls `on_drop` with the first file dropped anywhere on the page.
+#[hook]
+pub fn use_file_drop(on_drop: Callback<File>) {
+    use_effect_with_deps(
+        |on_drop| {
+            // The browser would open the file itself unless both are cancelled
+            let dragover = Closure::<dyn Fn(DragEvent)>::new(|e: DragEvent| {
+                e.prevent_default();
+            });
+            let drop = {
+                let on_drop = on_drop.clone();
+                Closure::<dyn Fn(DragEvent)>::new(move |e: DragEvent| {
+                    e.prevent_default();
+                    let file = e
+                        .data_transfer()
+                        .and_then(|dt| dt.files())
+                        .and_then(|files| files.get(0));
+                    if let Some(file) = file {
+                        on_drop.emit(file);
+                    }
+                })
+            };
+            let window = web_sys::window().unwrap();
+            window
+                .add_event_listener_with_callback("dragover", dragover.as_ref().unchecked_ref())
+                .ok();
+            window
+                .add_event_listener_with_callback("drop", drop.as_ref().unchecked_ref())
+                .ok();
+            move || {
+                window
+                    .remove_event_listener_with_callback(
+                        "dragover",
+                        dragover.as_ref().unchecked_ref(),
+                    )
+                    .ok();
+                window
+                    .remove_event_listener_with_callback("drop", drop.as_ref().unchecked_ref())
+                    .ok();
+            }
+        },
+        on_drop,
+    );
+}
@@ -0,0 +1,133 @@
+#![allow(non_snake_case)]
+
+use web_sys::HtmlDialogElement;
+use yew::prelude::*;
+use yewdux::prelude::*;
+
+use super::notifications::{notify, Level};
+use super::project_manager::{format_time, load_history, take_snapshot};
+use super::states::{CurrentTransfer, MainState, Projects};
+use crate::data::history::diff_summary;
+
+// Changes listed per snapshot before summarizing the rest
+const SHOWN_CHANGES: usize = 4;
+
+#[derive(PartialEq, Properties)]
+pub struct HistoryDialogProps {
+    pub close_callback: Callback<()>,
+}
+
+#[function_component]
+pub fn HistoryDialog(props: &HistoryDialogProps) -> Html {
+    let (main_state, main_dispatch) = use_store::<MainState>();
+    let (_, ct_dispatch) = use_store::<CurrentTransfer>();
+    let (projects, _) = use_store::<Projects>();
+    let (history, error) = match load_history(projects.current) {
+        Ok(history) => (history, None),
+        Err(e) => (Vec::new(), Some(e)),
+    };
+    // The history is read from storage, which doesn't trigger a render by itself
+    let force_update = use_force_update();
+
+    let on_snapshot = Callback::from(move |_: MouseEvent| {
+        take_snapshot("Manual");
+        force_update.force_update();
+    });
+    let on_restore = |index: usize| {
+        let main_dispatch = main_dispatch.clone();
+        let ct_dispatch = ct_dispatch.clone();
+        let snapshot = history[index].clone();
+        Callback::from(move |_: MouseEvent| {
+            // So that restoring can be undone too
+            take_snapshot("Before restoring");
+            main_dispatch.set(snapshot.state.clone());
+            ct_dispatch.set(CurrentTransfer::default());
+            notify(
+                Level::Info,
+                format!("Restored the snapshot from {}.", format_time(snapshot.time)),
+            );
+        })
+    };
+
+    let dialog_ref = use_node_ref();
+    let onclose = {
+        let close_callback = props.close_callback.clone();
+        Callback::from(move |_: Event| {
+            close_callback.emit(());
+        })
+    };
+
+    {
+        let dialog_ref = dialog_ref.clone();
+
+        use_effect_with_deps(
+            |dialog_ref| {
+                dialog_ref
+                    .cast::<HtmlDialogElement>()
+                    .unwrap()
+                    .show_modal()
+                    .ok();
+            },
+            dialog_ref,
+        );
+    }
+
+    // Newest first
+    let rows = history
+        .iter()
+        .enumerate()
+        .rev()
+        .map(|(index, snapshot)| {
+            let changes = diff_summary(&snapshot.state, &main_state);
+            let summary = if snapshot.state == *main_state {
+                html! { <li>{"Same as now"}</li> }
+            } else if changes.is_empty() {
+                html! { <li>{"Settings only"}</li> }
+            } else {
+                let mut lines = changes
+                    .iter()
+                    .take(SHOWN_CHANGES)
+                    .map(|c| html! { <li>{c}</li> })
+                    .collect::<Vec<Html>>();
+                if changes.len() > SHOWN_CHANGES {
+                    lines.push(html! {
+                        <li>{format!("...and {} more", changes.len() - SHOWN_CHANGES)}</li>
+                    });
+                }
+                lines.into_iter().collect::<Html>()
+            };
+            html! {
+                <tr>
+                    <td>{format_time(snapshot.time)}</td>
+                    <td>{snapshot.reason.clone()}</td>
+                    <td><ul class="changes">{summary}</ul></td>
+                    <td><button onclick={on_restore(index)}
+                        disabled={snapshot.state == *main_state}>{"Restore"}</button></td>
+                </tr>
+            }
+        })
+        .collect::<Html>();
+
+    html! {
+        <dialog ref={dialog_ref} class="dialog history_dialog" onclose={onclose}>
+            <h2>{"History"}</h2>
+            <p>{"Snapshots are taken every few minutes while you work, \
+                and before anything that replaces or deletes part of the project. \
+                Each lists what has changed since it was taken."}</p>
+            if let Some(e) = &error {
+                <p class="validation_error">
+                {format!("The snapshots couldn't be read, and are left as they were stored: {}", e)}
+                </p>
+            } else if history.is_empty() {
+                <p>{"No snapshots yet."}</p>
+            } else {
+                <table>
+                    <tr><th>{"Taken"}</th><th>{"Reason"}</th><th>{"Changed Since"}</th><th /></tr>
+                    { rows }
+                </table>
+            }
+            <button onclick={on_snapshot} disabled={error.is_some()}>{"Take Snapshot Now"}</button>
+            <form class="modal_close" method="dialog"><button /></form>
+        </dialog>
+    }
+}
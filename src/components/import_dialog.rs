@@ -0,0 +1,286 @@
+#![allow(non_snake_case)]
+
+use wasm_bindgen::JsCast;
+use web_sys::{HtmlDialogElement, HtmlSelectElement};
+use yew::prelude::*;
+use yewdux::prelude::*;
+
+use super::cherry_pick_import::CherryPickImport;
+use super::file_import::{FilePicker, LoadedFile};
+use super::notifications::{notify, Level};
+use super::project_manager::take_snapshot;
+use super::states::{CurrentTransfer, MainState};
+use crate::data::import::{detect_import, parse_plate_map, wells_to_region, ImportKind};
+use crate::data::schema::from_json;
+use crate::data::well_name::num_to_letters;
+
+#[derive(PartialEq, Properties)]
+pub struct ImportDialogProps {
+    // The file being imported, if one was picked or dropped
+    pub file: Option<LoadedFile>,
+    pub on_file: Callback<LoadedFile>,
+    pub close_callback: Callback<()>,
+}
+
+#[function_component]
+pub fn ImportDialog(props: &ImportDialogProps) -> Html {
+    let dialog_ref = use_node_ref();
+
+    let on_done = {
+        let dialog_ref = dialog_ref.clone();
+        Callback::from(move |_| {
+            if let Some(dialog) = dialog_ref.cast::<HtmlDialogElement>() {
+                dialog.close();
+            }
+        })
+    };
+    let on_cancel = {
+        let on_done = on_done.clone();
+        Callback::from(move |_: MouseEvent| on_done.emit(()))
+    };
+    let onclose = {
+        let close_callback = props.close_callback.clone();
+        Callback::from(move |_: Event| {
+            close_callback.emit(());
+        })
+    };
+
+    {
+        let dialog_ref = dialog_ref.clone();
+
+        use_effect_with_deps(
+            |dialog_ref| {
+                dialog_ref
+                    .cast::<HtmlDialogElement>()
+                    .unwrap()
+                    .show_modal()
+                    .ok();
+            },
+            dialog_ref,
+        );
+    }
+
+    let body = match &props.file {
+        Some(file) => {
+            let kind = detect_import(&file.text);
+            let text = file.text.clone();
+            html! {
+                <>
+                <p>{format!("{}: {}", file.name, kind.label())}</p>
+                {match kind {
+                    ImportKind::Project => html! {
+                        <ProjectImport {text} on_done={on_done.clone()}/>
+                    },
+                    ImportKind::PickList => html! {
+                        <CherryPickImport {text} on_done={on_done.clone()}/>
+                    },
+                    ImportKind::PlateMap => html! {
+                        <PlateMapImport {text} on_done={on_done.clone()}/>
+                    },
+                }}
+                </>
+            }
+        }
+        None => html! {
+            <div>
+            <button onclick={on_cancel}>{"Cancel"}</button>
+            </div>
+        },
+    };
+
+    html! {
+        <dialog ref={dialog_ref} class="dialog import_dialog" onclose={onclose}>
+            <h2>{"Import"}</h2>
+            <p>{"Projects saved as JSON, pick lists (including Plate Tool's CSV export), \
+                and plate maps are recognized automatically."}</p>
+            <FilePicker accept=".json,.csv,.txt" on_load={props.on_file.clone()}/>
+            { body }
+            <form class="modal_close" method="dialog"><button /></form>
+        </dialog>
+    }
+}
+
+#[derive(PartialEq, Properties)]
+struct ImportBodyProps {
+    text: String,
+    on_done: Callback<()>,
+}
+
+#[function_component]
+fn ProjectImport(props: &ImportBodyProps) -> Html {
+    let (main_state, main_dispatch) = use_store::<MainState>();
+    let (_, ct_dispatch) = use_store::<CurrentTransfer>();
+    // Older files are upgraded to the current format
+    let parsed = from_json(&props.text);
+
+    let on_import = {
+        let parsed = parsed.as_ref().ok().cloned();
+        let on_done = props.on_done.clone();
+        Callback::from(move |_: MouseEvent| {
+            if let Some(ms) = parsed.clone() {
+                take_snapshot("Before import");
+                main_dispatch.set(ms);
+                ct_dispatch.set(CurrentTransfer::default());
+                notify(Level::Info, "Project imported.");
+                on_done.emit(());
+            }
+        })
+    };
+    let on_cancel = {
+        let on_done = props.on_done.clone();
+        Callback::from(move |_: MouseEvent| on_done.emit(()))
+    };
+
+    let current_plates = main_state.source_plates.len() + main_state.destination_plates.len();
+    html! {
+        <div class="project_import">
+            {match &parsed {
+                Ok(ms) => html! {
+                    <>
+                    <p>{format!("{} source plates, {} destination plates and {} transfers.",
+                        ms.source_plates.len(), ms.destination_plates.len(), ms.transfers.len())}</p>
+                    if current_plates > 0 || !main_state.transfers.is_empty() {
+                        <p class="validation_error">
+                        {format!("This replaces the open project ({} plates and {} transfers).",
+                            current_plates, main_state.transfers.len())}
+                        </p>
+                    }
+                    </>
+                },
+                Err(e) => html! { <ul class="import_errors"><li>{e}</li></ul> },
+            }}
+            <div>
+            <button onclick={on_import} disabled={parsed.is_err()}>{"Import"}</button>
+            <button onclick={on_cancel}>{"Cancel"}</button>
+            </div>
+        </div>
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum MapTarget {
+    Source,
+    Destination,
+}
+
+#[function_component]
+fn PlateMapImport(props: &ImportBodyProps) -> Html {
+    let (main_state, _) = use_store::<MainState>();
+    let (_, ct_dispatch) = use_store::<CurrentTransfer>();
+    let target = use_state(|| MapTarget::Source);
+    let map = parse_plate_map(&props.text);
+
+    let plate = match *target {
+        MapTarget::Source => main_state
+            .source_plates
+            .iter()
+            .find(|spi| spi.get_uuid() == main_state.selected_source_plate),
+        MapTarget::Destination => main_state
+            .destination_plates
+            .iter()
+            .find(|dpi| dpi.get_uuid() == main_state.selected_dest_plate),
+    };
+    let error = match (&map, plate) {
+        (Err(e), _) => Some(e.clone()),
+        (Ok(_), None) => Some("Select a plate first.".to_string()),
+        (Ok(map), Some(pi)) => {
+            let size = pi.plate.size();
+            (map.size.0 > size.0 || map.size.1 > size.1).then(|| {
+                format!(
+                    "The map is {}x{} but {} is {}x{}.",
+                    map.size.0, map.size.1, pi.name, size.0, size.1
+                )
+            })
+        }
+    };
+
+    let on_target_change = {
+        let target = target.clone();
+        Callback::from(move |e: Event| {
+            let select = e
+                .target()
+                .and_then(|t| t.dyn_into::<HtmlSelectElement>().ok());
+            if let Some(select) = select {
+                target.set(match select.value().as_str() {
+                    "destination" => MapTarget::Destination,
+                    _ => MapTarget::Source,
+                });
+            }
+        })
+    };
+    let on_import = {
+        let wells: Vec<(u8, u8)> = match &map {
+            Ok(map) => map.wells.iter().map(|(w, _)| *w).collect(),
+            Err(_) => Vec::new(),
+        };
+        let target = *target;
+        let on_done = props.on_done.clone();
+        Callback::from(move |_: MouseEvent| {
+            let region = wells_to_region(&wells);
+            ct_dispatch.reduce_mut(|state| match target {
+                MapTarget::Source => state.transfer.transfer_region.source_region = region,
+                MapTarget::Destination => state.transfer.transfer_region.dest_region = region,
+            });
+            notify(
+                Level::Info,
+                format!("Selected {} wells from the plate map.", wells.len()),
+            );
+            on_done.emit(());
+        })
+    };
+    let on_cancel = {
+        let on_done = props.on_done.clone();
+        Callback::from(move |_: MouseEvent| on_done.emit(()))
+    };
+
+    let preview = match &map {
+        Ok(map) => {
+            let header = (1..=map.size.1)
+                .map(|j| html! { <th>{j}</th> })
+                .collect::<Html>();
+            let rows = (1..=map.size.0)
+                .map(|i| {
+                    let cells = (1..=map.size.1)
+                        .map(|j| {
+                            let name = map
+                                .wells
+                                .iter()
+                                .find(|(w, _)| *w == (i, j))
+                                .map(|(_, name)| name.clone());
+                            html! { <td>{name.unwrap_or_default()}</td> }
+                        })
+                        .collect::<Html>();
+                    html! { <tr><th>{num_to_letters(i).unwrap_or_default()}</th>{cells}</tr> }
+                })
+                .collect::<Html>();
+            html! {
+                <>
+                <p>{format!("{} filled wells", map.wells.len())}</p>
+                <table class="plate_map_preview"><tr><th />{header}</tr>{rows}</table>
+                </>
+            }
+        }
+        Err(_) => html! {},
+    };
+
+    html! {
+        <div class="plate_map_import">
+            <label>{"Select the filled wells on the"}
+            <select onchange={on_target_change}>
+                <option value="source" selected={*target == MapTarget::Source}>
+                {"Selected source plate"}</option>
+                <option value="destination" selected={*target == MapTarget::Destination}>
+                {"Selected destination plate"}</option>
+            </select>
+            </label>
+            { preview }
+            if let Some(e) = &error {
+                <ul class="import_errors"><li>{e}</li></ul>
+            }
+            <div>
+            <button onclick={on_import} disabled={error.is_some()}>{"Import"}</button>
+            <button onclick={on_cancel}>{"Cancel"}</button>
+            </div>
+        </div>
+    }
+}
@@ -1,32 +1,36 @@
 #![allow(non_snake_case)]
-use std::collections::HashSet;
-
 use js_sys::Array;
-use lazy_static::lazy_static;
-use regex::Regex;
-use wasm_bindgen::{prelude::*, JsCast, JsValue};
-use web_sys::{
-    Blob, HtmlAnchorElement, HtmlButtonElement, HtmlDialogElement, HtmlFormElement,
-    HtmlInputElement, HtmlOptionElement, HtmlSelectElement, Url,
-};
+use wasm_bindgen::{JsCast, JsValue};
+use web_sys::{Blob, HtmlAnchorElement, Url};
 use yew::prelude::*;
 use yewdux::prelude::*;
 
+use super::conflicts_dialog::ConflictsDialog;
+use super::controls_dialog::ControlsDialog;
+use super::export_dialog::ExportDialog;
+use super::file_import::{read_file, use_file_drop, LoadedFile};
+use super::history_dialog::HistoryDialog;
+use super::import_dialog::ImportDialog;
 use super::new_plate_dialog::NewPlateDialog;
+use super::notifications::{confirm, notify, Level, NotificationArea};
+use super::plates::overview::Overview;
 use super::plates::plate_container::PlateContainer;
-use super::states::{CurrentTransfer, MainState};
-use super::transfer_menu::{letters_to_num, RegionDisplay, TransferMenu};
+use super::project_manager::{take_snapshot, use_project_sync, ProjectManagerDialog};
+use super::randomize_dialog::RandomizeDialog;
+use super::run_estimate_dialog::RunEstimateDialog;
+use super::states::{CurrentTransfer, MainState, Projects};
+use super::transfer_menu::{RegionDisplay, TransferMenu};
 use super::tree::Tree;
 
-use crate::data::csv::state_to_csv;
 use crate::data::plate_instances::PlateInstance;
-use crate::data::transfer::Transfer;
-use crate::data::transfer_region::{Region, TransferRegion};
+use crate::data::schema::{state_schema, to_json, SCHEMA_ID};
 
 #[function_component]
 pub fn MainWindow() -> Html {
     let (main_state, main_dispatch) = use_store::<MainState>();
     let (_, ct_dispatch) = use_store::<CurrentTransfer>();
+    let (projects, _) = use_store::<Projects>();
+    use_project_sync();
 
     let source_plate_instance: Option<PlateInstance> = main_state
         .source_plates
@@ -58,6 +62,21 @@ pub fn MainWindow() -> Html {
         })
     };
 
+    // Shows every destination plate at once instead of the plate editor
+    let overview_is_open = use_state_eq(|| false);
+    let toggle_overview_callback = {
+        let overview_is_open = overview_is_open.clone();
+        Callback::from(move |_| {
+            overview_is_open.set(!*overview_is_open);
+        })
+    };
+    let overview_close_callback = {
+        let overview_is_open = overview_is_open.clone();
+        Callback::from(move |_| {
+            overview_is_open.set(false);
+        })
+    };
+
     let new_plate_dialog_is_open = use_state_eq(|| false);
     let new_plate_dialog_callback = {
         let new_plate_dialog_is_open = new_plate_dialog_is_open.clone();
@@ -72,397 +91,180 @@ pub fn MainWindow() -> Html {
         })
     };
 
+    let project_manager_is_open = use_state_eq(|| false);
+    let project_manager_callback = {
+        let project_manager_is_open = project_manager_is_open.clone();
+        Callback::from(move |_| {
+            project_manager_is_open.set(false);
+        })
+    };
+    let open_project_manager_callback = {
+        let project_manager_is_open = project_manager_is_open.clone();
+        Callback::from(move |_| {
+            project_manager_is_open.set(true);
+        })
+    };
+
+    let history_dialog_is_open = use_state_eq(|| false);
+    let history_dialog_callback = {
+        let history_dialog_is_open = history_dialog_is_open.clone();
+        Callback::from(move |_| {
+            history_dialog_is_open.set(false);
+        })
+    };
+    let open_history_dialog_callback = {
+        let history_dialog_is_open = history_dialog_is_open.clone();
+        Callback::from(move |_| {
+            history_dialog_is_open.set(true);
+        })
+    };
+
     let new_button_callback = {
         let main_dispatch = main_dispatch.clone();
         Callback::from(move |_| {
-            let window = web_sys::window().unwrap();
-            let confirm =
-                window.confirm_with_message("This will reset all plates and transfers. Proceed?");
-            if let Ok(confirm) = confirm {
-                if confirm {
+            let state = main_dispatch.get();
+            let main_dispatch = main_dispatch.clone();
+            let ct_dispatch = ct_dispatch.clone();
+            confirm(
+                "Start a New Project?",
+                format!(
+                    "This will remove {} source plates, {} destination plates and {} transfers. \
+                     Export as JSON first to keep a copy.",
+                    state.source_plates.len(),
+                    state.destination_plates.len(),
+                    state.transfers.len()
+                ),
+                "Remove Everything",
+                Callback::from(move |_| {
+                    take_snapshot("Before New");
                     main_dispatch.set(MainState::default());
                     ct_dispatch.set(CurrentTransfer::default());
-                }
-            }
+                }),
+            );
         })
     };
 
-    let export_csv_button_callback = {
-        let main_state = main_state.clone();
+    let conflicts_dialog_is_open = use_state_eq(|| false);
+    let conflicts_dialog_callback = {
+        let conflicts_dialog_is_open = conflicts_dialog_is_open.clone();
         Callback::from(move |_| {
-            if main_state.transfers.is_empty() {
-                web_sys::window()
-                    .unwrap()
-                    .alert_with_message("No transfers to export.")
-                    .unwrap();
-                return;
-            }
-            web_sys::window().unwrap().alert_with_message("CSV export is currently not importable. Export as JSON if you'd like to back up your work!").unwrap();
-            if let Ok(csv) = state_to_csv(&main_state) {
-                save_str(&csv, "transfers.csv");
-            }
+            conflicts_dialog_is_open.set(false);
         })
     };
-
-    let export_json_button_callback = {
+    let open_conflicts_dialog_callback = {
+        let conflicts_dialog_is_open = conflicts_dialog_is_open.clone();
         Callback::from(move |_| {
-            if let Ok(json) = serde_json::to_string(&main_state) {
-                save_str(&json, "plate-tool-state.json");
-            } else {
-                web_sys::window()
-                    .unwrap()
-                    .alert_with_message("Failed to export.")
-                    .unwrap();
-            }
+            conflicts_dialog_is_open.set(true);
         })
     };
 
-    let import_json_button_callback = {
-        let main_dispatch = main_dispatch.clone();
+    let controls_dialog_is_open = use_state_eq(|| false);
+    let controls_dialog_callback = {
+        let controls_dialog_is_open = controls_dialog_is_open.clone();
         Callback::from(move |_| {
-            let window = web_sys::window().unwrap();
-            let document = window.document().unwrap();
-            let body = document.body().unwrap();
-            let modal = document
-                .create_element("dialog")
-                .unwrap()
-                .dyn_into::<HtmlDialogElement>()
-                .unwrap();
-            modal.set_text_content(Some("Import File:"));
-            let onclose_callback = {
-                let modal = modal.clone();
-                Closure::<dyn FnMut(_)>::new(move |_: Event| {
-                    modal.remove();
-                })
-            };
-            modal.set_onclose(Some(onclose_callback.as_ref().unchecked_ref()));
-            onclose_callback.forget();
-
-            let form = document
-                .create_element("form")
-                .unwrap()
-                .dyn_into::<HtmlFormElement>()
-                .unwrap();
-            let input = document
-                .create_element("input")
-                .unwrap()
-                .dyn_into::<HtmlInputElement>()
-                .unwrap();
-            input.set_type("file");
-            input.set_accept(".json");
-            form.append_child(&input).unwrap();
-
-            let input_callback = {
-                let main_dispatch = main_dispatch.clone();
-                let modal = modal.clone();
-                Closure::<dyn FnMut(_)>::new(move |e: Event| {
-                    if let Some(input) = e.current_target() {
-                        let input = input
-                            .dyn_into::<HtmlInputElement>()
-                            .expect("We know this is an input.");
-                        if let Some(files) = input.files() {
-                            if let Some(file) = files.get(0) {
-                                let fr = web_sys::FileReader::new().unwrap();
-                                fr.read_as_text(&file).unwrap();
-                                let fr1 = fr.clone(); // Clone to avoid outliving closure
-                                let main_dispatch = main_dispatch.clone(); // Clone to satisfy FnMut
-                                                                           // trait
-                                let modal = modal.clone();
-                                let onload = Closure::<dyn FnMut(_)>::new(move |_: Event| {
-                                    if let Some(value) =
-                                        &fr1.result().ok().and_then(|v| v.as_string())
-                                    {
-                                        let ms = serde_json::from_str::<MainState>(value);
-                                        match ms {
-                                            Ok(ms) => main_dispatch.set(ms),
-                                            Err(e) => log::debug!("{:?}", e),
-                                        };
-                                        modal.close();
-                                    }
-                                });
-                                fr.set_onload(Some(onload.as_ref().unchecked_ref()));
-                                onload.forget(); // Magic (don't touch)
-                            }
-                        }
-                    }
-                })
-            };
-            input.set_onchange(Some(input_callback.as_ref().unchecked_ref()));
-            input_callback.forget(); // Magic straight from the docs, don't touch :(
-
-            modal.append_child(&form).unwrap();
-            body.append_child(&modal).unwrap();
-            modal.show_modal().unwrap();
+            controls_dialog_is_open.set(false);
         })
     };
-
-    let import_transfer_csv_callback = {
+    let open_controls_dialog_callback = {
+        let controls_dialog_is_open = controls_dialog_is_open.clone();
         Callback::from(move |_| {
-            let window = web_sys::window().unwrap();
-            let document = window.document().unwrap();
-            let body = document.body().unwrap();
-            let modal = document
-                .create_element("dialog")
-                .unwrap()
-                .dyn_into::<HtmlDialogElement>()
-                .unwrap();
-            modal.set_text_content(Some("Import File:"));
-            let onclose_callback = {
-                let modal = modal.clone();
-                Closure::<dyn FnMut(_)>::new(move |_: Event| {
-                    modal.remove();
-                })
-            };
-            modal.set_onclose(Some(onclose_callback.as_ref().unchecked_ref()));
-            onclose_callback.forget();
-
-            let form = document
-                .create_element("form")
-                .unwrap()
-                .dyn_into::<HtmlFormElement>()
-                .unwrap();
-            let input = document
-                .create_element("input")
-                .unwrap()
-                .dyn_into::<HtmlInputElement>()
-                .unwrap();
-            input.set_type("file");
-            input.set_accept(".csv");
-            form.append_child(&input).unwrap();
-
-            let input_callback = {
-                let main_dispatch = main_dispatch.clone();
-                let modal = modal.clone();
-                Closure::<dyn FnMut(_)>::new(move |e: Event| {
-                    if let Some(input) = e.current_target() {
-                        let input = input
-                            .dyn_into::<HtmlInputElement>()
-                            .expect("We know this is an input.");
-                        if let Some(files) = input.files() {
-                            if let Some(file) = files.get(0) {
-                                let fr = web_sys::FileReader::new().unwrap();
-                                fr.read_as_text(&file).unwrap();
-                                let fr1 = fr.clone(); // Clone to avoid outliving closure
-                                let main_dispatch = main_dispatch.clone(); // Clone to satisfy FnMut
-                                                                           // trait
-                                let modal = modal.clone();
-                                let onload = Closure::<dyn FnMut(_)>::new(move |_: Event| {
-                                    if let Some(value) =
-                                        &fr1.result().ok().and_then(|v| v.as_string())
-                                    {
-                                        let mut rdr = csv::Reader::from_reader(value.as_bytes());
-                                        let mut records = Vec::new();
-                                        for record in
-                                            rdr.deserialize::<crate::data::csv::TransferRecord>()
-                                        {
-                                            match record {
-                                                Ok(r) => {
-                                                    //log::debug!("{:?}", r);
-                                                    records.push(r);
-                                                }
-                                                Err(e) => {
-                                                    log::debug!("{:?}", e);
-                                                }
-                                            }
-                                        }
-
-                                        let mut sources: HashSet<String> = HashSet::new();
-                                        let mut destinations: HashSet<String> = HashSet::new();
-                                        for record in records.iter() {
-                                            sources.insert(record.source_plate.clone());
-                                            destinations.insert(record.destination_plate.clone());
-                                        }
-
-                                        let window = web_sys::window().unwrap();
-                                        let document = window.document().unwrap();
-                                        let form = document
-                                            .create_element("form")
-                                            .unwrap()
-                                            .dyn_into::<HtmlFormElement>()
-                                            .unwrap();
-                                        let from_source = document
-                                            .create_element("select")
-                                            .unwrap()
-                                            .dyn_into::<HtmlSelectElement>()
-                                            .unwrap();
-                                        for source in sources {
-                                            let option = document
-                                                .create_element("option")
-                                                .unwrap()
-                                                .dyn_into::<HtmlOptionElement>()
-                                                .unwrap();
-                                            option.set_value(&source);
-                                            option.set_text(&source);
-                                            from_source.append_child(&option).unwrap();
-                                        }
-                                        let to_source = document
-                                            .create_element("select")
-                                            .unwrap()
-                                            .dyn_into::<HtmlSelectElement>()
-                                            .unwrap();
-                                        for source in &main_dispatch.get().source_plates {
-                                            let option = document
-                                                .create_element("option")
-                                                .unwrap()
-                                                .dyn_into::<HtmlOptionElement>()
-                                                .unwrap();
-                                            option.set_value(&source.name);
-                                            option.set_text(&source.name);
-                                            to_source.append_child(&option).unwrap();
-                                        }
-                                        let from_dest = document
-                                            .create_element("select")
-                                            .unwrap()
-                                            .dyn_into::<HtmlSelectElement>()
-                                            .unwrap();
-                                        for dest in destinations {
-                                            let option = document
-                                                .create_element("option")
-                                                .unwrap()
-                                                .dyn_into::<HtmlOptionElement>()
-                                                .unwrap();
-                                            option.set_value(&dest);
-                                            option.set_text(&dest);
-                                            from_dest.append_child(&option).unwrap();
-                                        }
-                                        let to_dest = document
-                                            .create_element("select")
-                                            .unwrap()
-                                            .dyn_into::<HtmlSelectElement>()
-                                            .unwrap();
-                                        for dest in &main_dispatch.get().destination_plates {
-                                            let option = document
-                                                .create_element("option")
-                                                .unwrap()
-                                                .dyn_into::<HtmlOptionElement>()
-                                                .unwrap();
-                                            option.set_value(&dest.name);
-                                            option.set_text(&dest.name);
-                                            to_dest.append_child(&option).unwrap();
-                                        }
-                                        let submit = document
-                                            .create_element("button")
-                                            .unwrap()
-                                            .dyn_into::<HtmlButtonElement>()
-                                            .unwrap();
-                                        submit.set_value("Submit");
-                                        submit.set_inner_text("Submit");
-                                        let submit_callback = {
-                                            let main_dispatch = main_dispatch.clone();
-                                            let from_source = from_source.clone();
-                                            let to_source = to_source.clone();
-                                            let from_dest = from_dest.clone();
-                                            let to_dest = to_dest.clone();
-                                            Closure::<dyn FnMut(_)>::new(move |_: Event| {
-                                                let from_source = from_source.value();
-                                                let to_source = to_source.value();
-                                                let from_dest = from_dest.value();
-                                                let to_dest = to_dest.value();
-
-                                                lazy_static! {
-                                                    static ref REGEX: Regex =
-                                                        Regex::new(r"([A-Z]+)(\d+)").unwrap();
-                                                }
-                                                let records: Vec<((u8, u8), (u8, u8))> = records
-                                                    .iter()
-                                                    .filter(|record| {
-                                                        record.source_plate == from_source
-                                                    })
-                                                    .filter(|record| {
-                                                        record.destination_plate == from_dest
-                                                    })
-                                                    .map(|record| {
-                                                        let c1 = REGEX
-                                                            .captures(&record.source_well)
-                                                            .unwrap();
-                                                        let c2 = REGEX
-                                                            .captures(&record.destination_well)
-                                                            .unwrap();
-                                                        log::debug!("{} {}", &record.source_well, &record.destination_well);
-                                                        log::debug!("{},{}  {},{}", &c1[1], &c1[2], &c2[1], &c2[2]);
+            controls_dialog_is_open.set(true);
+        })
+    };
 
-                                                        (
-                                                            (
-                                                                letters_to_num(&c1[1]).unwrap(),
-                                                                c1[2].parse::<u8>().unwrap(),
-                                                            ),
-                                                            (
-                                                                letters_to_num(&c2[1]).unwrap(),
-                                                                c2[2].parse::<u8>().unwrap(),
-                                                            ),
-                                                        )
-                                                    })
-                                                    .collect();
+    let randomize_dialog_is_open = use_state_eq(|| false);
+    let randomize_dialog_callback = {
+        let randomize_dialog_is_open = randomize_dialog_is_open.clone();
+        Callback::from(move |_| {
+            randomize_dialog_is_open.set(false);
+        })
+    };
+    let open_randomize_dialog_callback = {
+        let randomize_dialog_is_open = randomize_dialog_is_open.clone();
+        Callback::from(move |_| {
+            randomize_dialog_is_open.set(true);
+        })
+    };
 
-                                                let spi = main_dispatch
-                                                    .get()
-                                                    .source_plates
-                                                    .iter()
-                                                    .find(|src| src.name == to_source)
-                                                    .unwrap()
-                                                    .clone();
-                                                let dpi = main_dispatch
-                                                    .get()
-                                                    .destination_plates
-                                                    .iter()
-                                                    .find(|dest| dest.name == to_dest)
-                                                    .unwrap()
-                                                    .clone();
+    let run_estimate_dialog_is_open = use_state_eq(|| false);
+    let run_estimate_dialog_callback = {
+        let run_estimate_dialog_is_open = run_estimate_dialog_is_open.clone();
+        Callback::from(move |_| {
+            run_estimate_dialog_is_open.set(false);
+        })
+    };
+    let open_run_estimate_dialog_callback = {
+        let run_estimate_dialog_is_open = run_estimate_dialog_is_open.clone();
+        Callback::from(move |_| {
+            run_estimate_dialog_is_open.set(true);
+        })
+    };
 
-                                                let custom_region = Region::new_custom(&records);
-                                                let transfer_region = TransferRegion {
-                                                    source_region: custom_region.clone(),
-                                                    dest_region: custom_region,
-                                                    interleave_source: (1, 1),
-                                                    interleave_dest: (1, 1),
-                                                    source_plate: spi.plate,
-                                                    dest_plate: dpi.plate,
-                                                };
+    let export_dialog_is_open = use_state_eq(|| false);
+    let export_csv_button_callback = {
+        let main_state = main_state.clone();
+        let export_dialog_is_open = export_dialog_is_open.clone();
+        Callback::from(move |_| {
+            if main_state.transfers.is_empty() {
+                notify(Level::Warning, "No transfers to export.");
+                return;
+            }
+            export_dialog_is_open.set(true);
+        })
+    };
+    let export_dialog_callback = {
+        let export_dialog_is_open = export_dialog_is_open.clone();
+        Callback::from(move |_| {
+            export_dialog_is_open.set(false);
+        })
+    };
+    let save_csv_callback = Callback::from(|csv: String| save_str(&csv, "transfers.csv"));
 
-                                                let transfer = Transfer::new(
-                                                    spi,
-                                                    dpi,
-                                                    transfer_region,
-                                                    "Custom Transfer".to_string(),
-                                                );
-                                                main_dispatch.reduce_mut(|state| {
-                                                    state.transfers.push(transfer);
-                                                    state.selected_transfer = state
-                                .transfers
-                                .last()
-                                .expect("An element should have just been added")
-                                .get_uuid();
-                                                });
-                                            })
-                                        };
-                                        submit.set_onclick(Some(
-                                            submit_callback.as_ref().unchecked_ref(),
-                                        ));
-                                        submit_callback.forget();
+    let export_json_button_callback = {
+        Callback::from(move |_| match to_json(&main_state) {
+            Ok(json) => save_str(&json, "plate-tool-state.json"),
+            Err(e) => notify(Level::Error, format!("Failed to export: {}", e)),
+        })
+    };
 
-                                        form.append_child(&from_source).unwrap();
-                                        form.append_child(&to_source).unwrap();
-                                        form.append_child(&from_dest).unwrap();
-                                        form.append_child(&to_dest).unwrap();
-                                        modal.append_child(&submit).unwrap();
-                                        modal.append_child(&form).unwrap();
-                                    }
-                                });
-                                fr.set_onload(Some(onload.as_ref().unchecked_ref()));
-                                onload.forget(); // Magic (don't touch)
-                            }
-                        }
-                    }
-                })
-            };
-            input.set_onchange(Some(input_callback.as_ref().unchecked_ref()));
-            input_callback.forget(); // Magic straight from the docs, don't touch :(
+    // For tools that write project files of their own
+    let export_schema_button_callback =
+        Callback::from(|_| match serde_json::to_string_pretty(&state_schema()) {
+            Ok(json) => save_str(&json, SCHEMA_ID),
+            Err(e) => notify(Level::Error, format!("Failed to export: {}", e)),
+        });
 
-            modal.append_child(&form).unwrap();
-            body.append_child(&modal).unwrap();
-            modal.show_modal().unwrap();
+    // Files can be picked in the import dialog or dropped anywhere on the window
+    let import_dialog_is_open = use_state_eq(|| false);
+    let import_file: UseStateHandle<Option<LoadedFile>> = use_state(|| None);
+    let import_file_callback = {
+        let import_file = import_file.clone();
+        Callback::from(move |file: LoadedFile| {
+            import_file.set(Some(file));
         })
     };
+    let open_import_dialog_callback = {
+        let import_dialog_is_open = import_dialog_is_open.clone();
+        let import_file = import_file.clone();
+        Callback::from(move |_| {
+            import_file.set(None);
+            import_dialog_is_open.set(true);
+        })
+    };
+    let import_dialog_callback = {
+        let import_dialog_is_open = import_dialog_is_open.clone();
+        Callback::from(move |_| {
+            import_dialog_is_open.set(false);
+        })
+    };
+    {
+        let import_dialog_is_open = import_dialog_is_open.clone();
+        let import_file_callback = import_file_callback.clone();
+        use_file_drop(Callback::from(move |file| {
+            read_file(file, import_file_callback.clone());
+            import_dialog_is_open.set(true);
+        }));
+    }
 
     html! {
         <>
@@ -470,20 +272,29 @@ pub fn MainWindow() -> Html {
             <div class="dropdown">
                 <button>{"File"}</button>
                 <button onclick={new_button_callback}>{"New"}</button>
+                <button onclick={open_project_manager_callback}>{"Projects"}</button>
+                <button onclick={open_history_dialog_callback}>{"History"}</button>
                 <div class="dropdown-sub">
                     <button>{"Export"}</button>
                     <div>
                         <button onclick={export_csv_button_callback}>{"Export as CSV"}</button>
                         <button onclick={export_json_button_callback}>{"Export as JSON"}</button>
+                        <button onclick={export_schema_button_callback}>{"JSON Schema"}</button>
                     </div>
                 </div>
-                <div class="dropdown-sub">
-                    <button>{"Import"}</button>
-                    <div>
-                        <button onclick={import_json_button_callback}>{"Import from JSON"}</button>
-                        <button onclick={import_transfer_csv_callback}>{"Import Transfer from CSV"}</button>
-                    </div>
-                </div>
+                <button onclick={open_import_dialog_callback}>{"Import"}</button>
+            </div>
+            <div class="dropdown">
+                <button>{"Tools"}</button>
+                <button onclick={open_controls_dialog_callback}>{"Place Controls"}</button>
+                <button onclick={open_randomize_dialog_callback}>{"Randomized Layout"}</button>
+                <button onclick={open_run_estimate_dialog_callback}>{"Run Estimate"}</button>
+                <button onclick={open_conflicts_dialog_callback}>{"Destination Conflicts"}</button>
+            </div>
+            <div class="dropdown">
+                <button>{"View"}</button>
+                <button onclick={toggle_overview_callback}>
+                {if *overview_is_open { "Plate Editor" } else { "Plate Overview" }}</button>
             </div>
             <div class="dropdown">
                 <button>{"Options"}</button>
@@ -494,15 +305,49 @@ pub fn MainWindow() -> Html {
                     </div>
                 </div>
             </div>
+            if let Some(project) = projects.get(projects.current) {
+                <span class="project_name">{project.name.clone()}</span>
+            }
         </div>
+        <NotificationArea />
         <div class="main_container">
             <Tree open_new_plate_callback={open_new_plate_dialog_callback}/>
             <TransferMenu />
+            if *overview_is_open {
+            <Overview close_callback={overview_close_callback}/>
+            } else {
             <PlateContainer source_dims={source_plate_instance}
              destination_dims={destination_plate_instance}/>
+            }
             if {*new_plate_dialog_is_open} {
             <NewPlateDialog close_callback={new_plate_dialog_callback}/>
             }
+            if {*project_manager_is_open} {
+            <ProjectManagerDialog close_callback={project_manager_callback}/>
+            }
+            if {*history_dialog_is_open} {
+            <HistoryDialog close_callback={history_dialog_callback}/>
+            }
+            if {*import_dialog_is_open} {
+            <ImportDialog file={(*import_file).clone()} on_file={import_file_callback}
+             close_callback={import_dialog_callback}/>
+            }
+            if {*export_dialog_is_open} {
+            <ExportDialog close_callback={export_dialog_callback}
+             save_callback={save_csv_callback}/>
+            }
+            if {*controls_dialog_is_open} {
+            <ControlsDialog close_callback={controls_dialog_callback}/>
+            }
+            if {*randomize_dialog_is_open} {
+            <RandomizeDialog close_callback={randomize_dialog_callback}/>
+            }
+            if {*run_estimate_dialog_is_open} {
+            <RunEstimateDialog close_callback={run_estimate_dialog_callback}/>
+            }
+            if {*conflicts_dialog_is_open} {
+            <ConflictsDialog close_callback={conflicts_dialog_callback}/>
+            }
         </div>
         </>
     }
@@ -1,6 +1,19 @@
+pub mod bulk_edit_dialog;
+pub mod cherry_pick_import;
+pub mod conflicts_dialog;
+pub mod controls_dialog;
+pub mod export_dialog;
+pub mod file_import;
+pub mod history_dialog;
+pub mod import_dialog;
 pub mod main_window;
 pub mod new_plate_dialog;
+pub mod notifications;
 pub mod plates;
+pub mod project_manager;
+pub mod randomize_dialog;
+pub mod run_estimate_dialog;
+pub mod shortcuts;
 pub mod states;
 pub mod transfer_menu;
 pub mod tree;
@@ -1,10 +1,13 @@
 use yew::prelude::*;
 use yewdux::prelude::*;
 
+use uuid::Uuid;
 use wasm_bindgen::JsCast;
 use web_sys::{EventTarget, FormData, HtmlDialogElement, HtmlFormElement};
 
+use crate::components::notifications::{notify, Level};
 use crate::components::states::MainState;
+use crate::data::labware::{get_labware, PlateChemistry};
 use crate::data::plate::*;
 use crate::data::plate_instances::PlateInstance;
 
@@ -15,9 +18,11 @@ pub struct NewPlateDialogProps {
 
 #[function_component]
 pub fn NewPlateDialog(props: &NewPlateDialogProps) -> Html {
-    let (_, dispatch) = use_store::<MainState>();
+    let (main_state, dispatch) = use_store::<MainState>();
 
     let new_plate_callback = {
+        let main_state = main_state.clone();
+        let dispatch = dispatch.clone();
         let close_callback = props.close_callback.clone();
         Callback::from(move |e: SubmitEvent| {
             e.prevent_default();
@@ -36,7 +41,24 @@ pub fn NewPlateDialog(props: &NewPlateDialogProps) -> Html {
                         "384" => PlateFormat::W384,
                         "1536" => PlateFormat::W1536,
                         "3456" => PlateFormat::W3456,
-                        _ => unreachable!(),
+                        other => match Uuid::parse_str(other)
+                            .ok()
+                            .and_then(|id| main_state.get_custom_format(id))
+                        {
+                            Some(cf) => cf.format(),
+                            None => return,
+                        },
+                    };
+                    let chemistry = match form_data
+                        .get("plate_chemistry")
+                        .as_string()
+                        .unwrap_or_default()
+                        .as_str()
+                    {
+                        "PP" => PlateChemistry::PP,
+                        "LDV" => PlateChemistry::LDV,
+                        "RES" => PlateChemistry::Reservoir,
+                        _ => PlateChemistry::Standard,
                     };
                     if let Some(pt_string) = form_data.get("new_plate_type").as_string() {
                         let plate_type = match pt_string.as_str() {
@@ -44,19 +66,13 @@ pub fn NewPlateDialog(props: &NewPlateDialogProps) -> Html {
                             "dest" => PlateType::Destination,
                             _ => PlateType::Source,
                         };
+                        let mut plate = PlateInstance::new(plate_type, format, name);
+                        plate.plate.chemistry = chemistry;
                         dispatch.reduce_mut(|s| {
                             if plate_type == PlateType::Source {
-                                s.add_source_plate(PlateInstance::new(
-                                    PlateType::Source,
-                                    format,
-                                    name,
-                                ))
+                                s.add_source_plate(plate)
                             } else {
-                                s.add_dest_plate(PlateInstance::new(
-                                    PlateType::Destination,
-                                    format,
-                                    name,
-                                ))
+                                s.add_dest_plate(plate)
                             }
                         });
                     }
@@ -65,6 +81,136 @@ pub fn NewPlateDialog(props: &NewPlateDialogProps) -> Html {
         })
     };
 
+    let new_format_callback = {
+        let dispatch = dispatch.clone();
+        Callback::from(move |e: SubmitEvent| {
+            e.prevent_default();
+            let target: Option<EventTarget> = e.target();
+            let form = target.and_then(|t| t.dyn_into::<HtmlFormElement>().ok());
+            if let Some(form) = form {
+                if let Ok(form_data) = FormData::new_with_form(&form) {
+                    let get = |key: &str| form_data.get(key).as_string().unwrap_or_default();
+                    let (Ok(rows), Ok(columns)) =
+                        (get("rows").parse::<u8>(), get("columns").parse::<u8>())
+                    else {
+                        return;
+                    };
+                    if rows == 0 || columns == 0 {
+                        return;
+                    }
+                    let name = match get("format_name") {
+                        name if name.is_empty() => format!("{}x{}", rows, columns),
+                        name => name,
+                    };
+                    let format = CustomFormat::new(
+                        name,
+                        rows,
+                        columns,
+                        get("well_volume").parse::<f32>().unwrap_or(0.0),
+                        get("dead_volume").parse::<f32>().unwrap_or(0.0),
+                        get("vendor_type"),
+                    );
+                    dispatch.reduce_mut(|s| s.add_custom_format(format));
+                    form.reset();
+                }
+            }
+        })
+    };
+
+    let edit_format_callback = {
+        let main_state = main_state.clone();
+        let dispatch = dispatch.clone();
+        Callback::from(move |e: SubmitEvent| {
+            e.prevent_default();
+            let target: Option<EventTarget> = e.target();
+            let form = target.and_then(|t| t.dyn_into::<HtmlFormElement>().ok());
+            if let Some(form) = form {
+                if let Ok(form_data) = FormData::new_with_form(&form) {
+                    let get = |key: &str| form_data.get(key).as_string().unwrap_or_default();
+                    let Some(mut format) = Uuid::parse_str(&get("format_id"))
+                        .ok()
+                        .and_then(|id| main_state.get_custom_format(id))
+                        .cloned()
+                    else {
+                        return;
+                    };
+                    let (Ok(rows), Ok(columns)) =
+                        (get("rows").parse::<u8>(), get("columns").parse::<u8>())
+                    else {
+                        return;
+                    };
+                    if rows == 0 || columns == 0 {
+                        return;
+                    }
+                    if !get("format_name").is_empty() {
+                        format.name = get("format_name");
+                    }
+                    format.rows = rows;
+                    format.columns = columns;
+                    format.well_volume = get("well_volume").parse::<f32>().unwrap_or(0.0);
+                    format.dead_volume = get("dead_volume").parse::<f32>().unwrap_or(0.0);
+                    format.vendor_type = get("vendor_type");
+                    dispatch.reduce_mut(|s| {
+                        if let Err(e) = s.update_custom_format(format) {
+                            notify(Level::Warning, e);
+                        }
+                    });
+                }
+            }
+        })
+    };
+
+    let custom_format_editors = main_state
+        .custom_formats
+        .iter()
+        .map(|cf| {
+            let on_delete = {
+                let dispatch = dispatch.clone();
+                let id = cf.get_uuid();
+                Callback::from(move |_: MouseEvent| {
+                    dispatch.reduce_mut(|s| {
+                        if let Err(e) = s.del_custom_format(id) {
+                            notify(Level::Warning, e);
+                        }
+                    });
+                })
+            };
+            html! {
+                <details key={cf.get_uuid().to_string()}>
+                <summary>{format!("{} ({}x{})", cf.name, cf.rows, cf.columns)}</summary>
+                <form onsubmit={edit_format_callback.clone()}>
+                <input type="hidden" name="format_id" value={cf.get_uuid().to_string()}/>
+                <input type="text" name="format_name" placeholder="Name" value={cf.name.clone()}/>
+                <input type="number" name="rows" placeholder="Rows" min="1" max="255" required={true}
+                    value={cf.rows.to_string()}/>
+                <input type="number" name="columns" placeholder="Columns" min="1" max="255" required={true}
+                    value={cf.columns.to_string()}/>
+                <input type="number" name="well_volume" placeholder="Well volume (µL)" min="0" step="0.1"
+                    value={cf.well_volume.to_string()}/>
+                <input type="number" name="dead_volume" placeholder="Dead volume (µL)" min="0" step="0.1"
+                    value={cf.dead_volume.to_string()}/>
+                <input type="text" name="vendor_type" placeholder="Vendor plate type"
+                    value={cf.vendor_type.clone()}/>
+                <input type="submit" value="Save" />
+                <button type="button" onclick={on_delete}>{"Delete"}</button>
+                </form>
+                </details>
+            }
+        })
+        .collect::<Html>();
+
+    let custom_format_options = main_state
+        .custom_formats
+        .iter()
+        .map(|cf| {
+            html! {
+                <option value={cf.get_uuid().to_string()}>
+                {format!("{} ({}x{})", cf.name, cf.rows, cf.columns)}
+                </option>
+            }
+        })
+        .collect::<Html>();
+
     let onclose = {
         let close_callback = props.close_callback.clone();
         Callback::from(move |_: Event| {
@@ -103,6 +249,13 @@ pub fn NewPlateDialog(props: &NewPlateDialogProps) -> Html {
                 <option value="384">{"384"}</option>
                 <option value="1536">{"1536"}</option>
                 <option value="3456">{"3456"}</option>
+                { custom_format_options }
+            </select>
+            <select name="plate_chemistry">
+                <option value="Standard" selected={true}>{"Standard"}</option>
+                <option value="PP">{"PP"}</option>
+                <option value="LDV">{"LDV"}</option>
+                <option value="RES">{"Reservoir"}</option>
             </select>
             <input type="radio" name="new_plate_type" id="npt_src" value="src" />
             <label for="npt_src">{"Source"}</label>
@@ -110,6 +263,25 @@ pub fn NewPlateDialog(props: &NewPlateDialogProps) -> Html {
             <label for="npt_dest">{"Destination"}</label>
             <input type="submit" name="new_plate_button" value="Create" />
             </form>
+            <details>
+            <summary>{"Define a custom format"}</summary>
+            <form onsubmit={new_format_callback}>
+            <input type="text" name="format_name" placeholder="Name"/>
+            <input type="number" name="rows" placeholder="Rows" min="1" max="255" required={true}/>
+            <input type="number" name="columns" placeholder="Columns" min="1" max="255" required={true}/>
+            <input type="number" name="well_volume" placeholder="Well volume (µL)" min="0" step="0.1"/>
+            <input type="number" name="dead_volume" placeholder="Dead volume (µL)" min="0" step="0.1"/>
+            <input type="text" name="vendor_type" placeholder="Vendor plate type"/>
+            <input type="submit" name="new_format_button" value="Add Format" />
+            </form>
+            </details>
+            if !main_state.custom_formats.is_empty() {
+                <details>
+                <summary>{"Edit custom formats"}</summary>
+                <p>{"A format's size can't change while a plate uses it, and it can only be deleted once no plate does."}</p>
+                { custom_format_editors }
+                </details>
+            }
             <form class="modal_close" method="dialog"><button /></form>
         </dialog>
     }
@@ -118,6 +290,11 @@ pub fn NewPlateDialog(props: &NewPlateDialogProps) -> Html {
 impl From<&PlateInstance> for String {
     fn from(value: &PlateInstance) -> Self {
         // Could have other formatting here
-        format!("{}, {}", value.name, value.plate.plate_format)
+        match get_labware(value.plate.plate_format, value.plate.chemistry) {
+            Some(labware) if value.plate.chemistry != PlateChemistry::Standard => {
+                format!("{}, {}", value.name, labware.name)
+            }
+            _ => format!("{}, {}", value.name, value.plate.plate_format),
+        }
     }
 }
@@ -0,0 +1,238 @@
+#![allow(non_snake_case)]
+
+use wasm_bindgen::closure::Closure;
+use wasm_bindgen::JsCast;
+use web_sys::HtmlDialogElement;
+use yew::prelude::*;
+use yewdux::prelude::*;
+
+// How long a toast stays up before it dismisses itself, in ms
+const TOAST_TIMEOUT: i32 = 6000;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Level {
+    Info,
+    Warning,
+    // Errors stay up until dismissed
+    Error,
+}
+
+impl Level {
+    fn class(&self) -> &'static str {
+        match self {
+            Level::Info => "info",
+            Level::Warning => "warning",
+            Level::Error => "error",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Toast {
+    pub id: u64,
+    pub level: Level,
+    pub message: String,
+}
+
+// Asks before doing something that can't be undone
+#[derive(Clone, PartialEq)]
+pub struct Confirmation {
+    pub title: String,
+    // What will be lost
+    pub message: String,
+    // Label of the button that goes ahead
+    pub action: String,
+    pub on_confirm: Callback<()>,
+}
+
+#[derive(Default, Clone, PartialEq, Store)]
+pub struct Notifications {
+    pub toasts: Vec<Toast>,
+    next_id: u64,
+    pub confirmation: Option<Confirmation>,
+}
+
+impl Notifications {
+    pub fn push(&mut self, level: Level, message: String) -> u64 {
+        self.next_id += 1;
+        self.toasts.push(Toast {
+            id: self.next_id,
+            level,
+            message,
+        });
+        self.next_id
+    }
+    pub fn dismiss(&mut self, id: u64) {
+        self.toasts.retain(|t| t.id != id);
+    }
+}
+
+/// Shows a toast without interrupting what the user is doing.
+pub fn notify(level: Level, message: impl Into<String>) {
+    let message = message.into();
+    Dispatch::<Notifications>::new().reduce_mut(|state| {
+        state.push(level, message);
+    });
+}
+
+/// Opens a confirmation dialog; `on_confirm` only runs if the user goes ahead.
+pub fn confirm(
+    title: impl Into<String>,
+    message: impl Into<String>,
+    action: impl Into<String>,
+    on_confirm: Callback<()>,
+) {
+    let confirmation = Confirmation {
+        title: title.into(),
+        message: message.into(),
+        action: action.into(),
+        on_confirm,
+    };
+    Dispatch::<Notifications>::new().reduce_mut(|state| {
+        state.confirmation = Some(confirmation);
+    });
+}
+
+#[function_component]
+pub fn NotificationArea() -> Html {
+    let (state, _) = use_store::<Notifications>();
+
+    let toasts = state
+        .toasts
+        .iter()
+        .map(|t| html! { <ToastView key={t.id} toast={t.clone()}/> })
+        .collect::<Html>();
+
+    html! {
+        <>
+        <div class="toasts">{ toasts }</div>
+        if let Some(confirmation) = &state.confirmation {
+            <ConfirmDialog confirmation={confirmation.clone()}/>
+        }
+        </>
+    }
+}
+
+#[derive(PartialEq, Properties)]
+struct ToastViewProps {
+    toast: Toast,
+}
+
+#[function_component]
+fn ToastView(props: &ToastViewProps) -> Html {
+    let dispatch = Dispatch::<Notifications>::new();
+    let id = props.toast.id;
+
+    {
+        let dispatch = dispatch.clone();
+        use_effect_with_deps(
+            move |level| {
+                if *level != Level::Error {
+                    let dismiss = Closure::once_into_js(move || {
+                        dispatch.reduce_mut(|state| state.dismiss(id));
+                    });
+                    web_sys::window()
+                        .unwrap()
+                        .set_timeout_with_callback_and_timeout_and_arguments_0(
+                            dismiss.unchecked_ref(),
+                            TOAST_TIMEOUT,
+                        )
+                        .ok();
+                }
+            },
+            props.toast.level,
+        );
+    }
+
+    let on_dismiss = Callback::from(move |_: MouseEvent| {
+        dispatch.reduce_mut(|state| state.dismiss(id));
+    });
+
+    html! {
+        <div class={classes!("toast", props.toast.level.class())} role="status">
+            <span>{props.toast.message.clone()}</span>
+            <button onclick={on_dismiss} title="Dismiss">{"×"}</button>
+        </div>
+    }
+}
+
+#[derive(PartialEq, Properties)]
+struct ConfirmDialogProps {
+    confirmation: Confirmation,
+}
+
+#[function_component]
+fn ConfirmDialog(props: &ConfirmDialogProps) -> Html {
+    let dialog_ref = use_node_ref();
+
+    let on_confirm = {
+        let on_confirm = props.confirmation.on_confirm.clone();
+        let dialog_ref = dialog_ref.clone();
+        Callback::from(move |_: MouseEvent| {
+            on_confirm.emit(());
+            if let Some(dialog) = dialog_ref.cast::<HtmlDialogElement>() {
+                dialog.close();
+            }
+        })
+    };
+    let on_cancel = {
+        let dialog_ref = dialog_ref.clone();
+        Callback::from(move |_: MouseEvent| {
+            if let Some(dialog) = dialog_ref.cast::<HtmlDialogElement>() {
+                dialog.close();
+            }
+        })
+    };
+    let onclose = Callback::from(move |_: Event| {
+        Dispatch::<Notifications>::new().reduce_mut(|state| state.confirmation = None);
+    });
+
+    {
+        let dialog_ref = dialog_ref.clone();
+
+        use_effect_with_deps(
+            |dialog_ref| {
+                dialog_ref
+                    .cast::<HtmlDialogElement>()
+                    .unwrap()
+                    .show_modal()
+                    .ok();
+            },
+            dialog_ref,
+        );
+    }
+
+    html! {
+        <dialog ref={dialog_ref} class="dialog confirm_dialog" onclose={onclose}>
+            <h2>{props.confirmation.title.clone()}</h2>
+            <p>{props.confirmation.message.clone()}</p>
+            <div>
+            <button onclick={on_cancel}>{"Cancel"}</button>
+            <button class="danger" onclick={on_confirm}>{props.confirmation.action.clone()}</button>
+            </div>
+        </dialog>
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use wasm_bindgen_test::*;
+
+    use super::*;
+
+    #[test]
+    #[wasm_bindgen_test]
+    fn test_push_and_dismiss() {
+        let mut state = Notifications::default();
+        let first = state.push(Level::Info, "Saved".to_string());
+        let second = state.push(Level::Error, "Failed".to_string());
+        assert_ne!(first, second);
+        assert_eq!(state.toasts.len(), 2);
+        state.dismiss(first);
+        assert_eq!(state.toasts.len(), 1);
+        assert_eq!(state.toasts[0].level, Level::Error);
+        // Dismissing twice is harmless
+        state.dismiss(first);
+        assert_eq!(state.toasts.len(), 1);
+    }
+}
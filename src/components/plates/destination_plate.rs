@@ -4,22 +4,30 @@ use std::collections::HashMap;
 use yew::prelude::*;
 use yewdux::prelude::*;
 
+use crate::components::notifications::confirm;
 use crate::components::states::{CurrentTransfer, MainState};
+use crate::data::conflicts::find_conflicts;
+use crate::data::heatmap::value_range;
 use crate::data::plate_instances::PlateInstance;
 use crate::data::transfer::Transfer;
 use crate::data::transfer_region::Region;
 
 // Color Palette for the Source Plates, can be changed here
-use crate::components::plates::util::Palettes;
+use crate::components::plates::util::{heat_color, Palettes};
 const PALETTE: super::util::ColorPalette = Palettes::RAINBOW;
 
 use super::super::transfer_menu::{num_to_letters, RegionDisplay};
+use super::source_plate::{
+    arrow_offset, in_rect, region_handle, region_origin, step_well, RegionHandle,
+};
 
 #[derive(Properties, PartialEq)]
 pub struct DestinationPlateProps {
     pub source_plate: PlateInstance,
     pub destination_plate: PlateInstance,
     pub cell_height: f64,
+    // Heatmap values to color wells by, instead of by transfer
+    pub heat: Option<HashMap<(u8, u8), f32>>,
 }
 
 #[function_component]
@@ -29,39 +37,114 @@ pub fn DestinationPlate(props: &DestinationPlateProps) -> Html {
     let m_start_handle: UseStateHandle<Option<(u8, u8)>> = use_state_eq(|| None);
     let m_end_handle: UseStateHandle<Option<(u8, u8)>> = use_state_eq(|| None);
     let m_stat_handle: UseStateHandle<bool> = use_state_eq(|| false);
+    // While moving the region: the well it was grabbed by, and how far it has moved
+    let m_grab_handle: UseStateHandle<Option<(u8, u8)>> = use_state_eq(|| None);
+    let m_offset_handle: UseStateHandle<(i16, i16)> = use_state_eq(|| (0, 0));
+    let plate_size = props.destination_plate.plate.size();
+    // When set, selecting wells toggles them in the plate's exclusion mask
+    let painting_mask: UseStateHandle<bool> = use_state_eq(|| false);
+    // Keyboard selection: the well under the cursor and where a Shift-selection started
+    let k_cursor_handle: UseStateHandle<Option<(u8, u8)>> = use_state_eq(|| None);
+    let k_anchor_handle: UseStateHandle<Option<(u8, u8)>> = use_state_eq(|| None);
 
     if !(*m_stat_handle) {
         let (pt1, pt2) = match ct_state.transfer.transfer_region.dest_region {
             Region::Point((x, y)) => ((x, y), (x, y)),
             Region::Rect(c1, c2) => (c1, c2),
-            Region::Custom(_) => ((0,0), (0,0)),
+            Region::Custom(_) => ((0, 0), (0, 0)),
+            Region::Multi(ref rects) => rects.last().copied().unwrap_or(((0, 0), (0, 0))),
         };
         m_start_handle.set(Some(pt1));
         m_end_handle.set(Some(pt2));
     }
-    let destination_wells = ct_state.transfer.transfer_region.get_destination_wells();
+    let destination_wells = ct_state
+        .transfer
+        .transfer_region
+        .get_destination_wells(&props.destination_plate.mask);
+
+    let heat_max = props
+        .heat
+        .as_ref()
+        .and_then(value_range)
+        .map(|(_, max)| max)
+        .unwrap_or(0.0);
+    let heat_label = |well: (u8, u8)| {
+        let unit = main_state.preferences.overlay.unit();
+        props
+            .heat
+            .as_ref()
+            .and_then(|heat| heat.get(&well))
+            .map(|v| format!("\n{:.1} {}", v, unit))
+            .unwrap_or_default()
+    };
 
     let ordered_ids: Vec<uuid::Uuid> = {
-        let mut ids: Vec<uuid::Uuid> = main_state.transfers.clone().iter()
-        .map(|x| x.id)
-        .collect();
+        let mut ids: Vec<uuid::Uuid> = main_state.transfers.clone().iter().map(|x| x.id).collect();
         ids.sort_unstable();
         ids
     };
 
+    // The region as it would be if dropped now, while it is being moved
+    let moved_region = m_grab_handle.and_then(|_| {
+        ct_state
+            .transfer
+            .transfer_region
+            .dest_region
+            .translate(*m_offset_handle, plate_size)
+    });
+    let moved_error = moved_region.as_ref().and_then(|region| {
+        let mut tr = ct_state.transfer.transfer_region.clone();
+        tr.dest_region = region.clone();
+        tr.validate()
+            .and_then(|_| tr.validate_mask(&props.destination_plate.mask))
+            .err()
+    });
+
     let mouse_callback = {
         let m_start_handle = m_start_handle.clone();
         let m_end_handle = m_end_handle.clone();
         let m_stat_handle = m_stat_handle.clone();
+        let m_grab_handle = m_grab_handle.clone();
+        let m_offset_handle = m_offset_handle.clone();
+        let ct_dispatch = ct_dispatch.clone();
+        let k_cursor_handle = k_cursor_handle.clone();
 
         Callback::from(move |(i, j, t)| match t {
+            MouseEventType::Mousedown | MouseEventType::Move | MouseEventType::Resize
+                if k_cursor_handle.is_some() =>
+            {
+                // The mouse takes over from the keyboard
+                k_cursor_handle.set(None);
+            }
             MouseEventType::Mousedown => {
                 m_start_handle.set(Some((i, j)));
                 m_end_handle.set(Some((i, j)));
                 m_stat_handle.set(true);
             }
+            MouseEventType::Move => {
+                m_grab_handle.set(Some((i, j)));
+                m_offset_handle.set((0, 0));
+                m_stat_handle.set(true);
+            }
+            MouseEventType::Resize => {
+                // Dragging a corner keeps the opposite corner in place
+                let region = &ct_dispatch.get().transfer.transfer_region.dest_region;
+                if let Some(opposite) = region.opposite_corner((i, j)) {
+                    m_start_handle.set(Some(opposite));
+                    m_end_handle.set(Some((i, j)));
+                    m_stat_handle.set(true);
+                }
+            }
+            MouseEventType::Mouseenter if !*m_stat_handle => (),
             MouseEventType::Mouseenter => {
-                if *m_stat_handle {
+                if let Some(grab) = *m_grab_handle {
+                    let offset = (i as i16 - grab.0 as i16, j as i16 - grab.1 as i16);
+                    let region = &ct_dispatch.get().transfer.transfer_region.dest_region;
+                    // Stops at the edge of the plate
+                    if region.translate(offset, plate_size).is_some() {
+                        m_offset_handle.set(offset);
+                    }
+                } else {
                     m_end_handle.set(Some((i, j)));
                 }
             }
@@ -75,7 +158,9 @@ pub fn DestinationPlate(props: &DestinationPlateProps) -> Html {
             .filter(|t| t.dest_id == props.destination_plate.get_uuid());
         let mut tooltip_map: HashMap<(u8, u8), Vec<&Transfer>> = HashMap::new();
         for t in ts {
-            let dws = t.transfer_region.get_destination_wells();
+            let dws = t
+                .transfer_region
+                .get_destination_wells(&props.destination_plate.mask);
             for dw in dws {
                 if let Some(val) = tooltip_map.get_mut(&dw) {
                     val.push(t);
@@ -87,15 +172,67 @@ pub fn DestinationPlate(props: &DestinationPlateProps) -> Html {
         tooltip_map
     };
 
+    // Wells written more than once; true where every transfer allows pooling
+    let conflict_map: HashMap<(u8, u8), bool> =
+        find_conflicts(&main_state.transfers, &main_state.destination_plates)
+            .into_iter()
+            .filter(|c| c.dest_plate == props.destination_plate.get_uuid())
+            .map(|c| (c.well, c.pooling_allowed))
+            .collect();
+
     let mouseup_callback = {
         let m_start_handle = m_start_handle.clone();
         let m_end_handle = m_end_handle.clone();
+        let m_stat_handle = m_stat_handle.clone();
+        let m_grab_handle = m_grab_handle.clone();
+        let ct_dispatch = ct_dispatch.clone();
 
-        Callback::from(move |_: MouseEvent| {
+        let painting_mask = painting_mask.clone();
+        let dest_id = props.destination_plate.get_uuid();
+        let mask = props.destination_plate.mask.clone();
+
+        Callback::from(move |e: MouseEvent| {
+            if !*m_stat_handle {
+                return; // Nothing was being selected
+            }
             m_stat_handle.set(false);
+            if m_grab_handle.is_some() {
+                m_grab_handle.set(None);
+                let offset = *m_offset_handle;
+                ct_dispatch.reduce_mut(|state| {
+                    let region = &mut state.transfer.transfer_region.dest_region;
+                    if let Some(moved) = region.translate(offset, plate_size) {
+                        *region = moved;
+                    }
+                });
+                return;
+            }
             if let Some(ul) = *m_start_handle {
                 if let Some(br) = *m_end_handle {
-                    if let Ok(rd) = RegionDisplay::try_from((ul.0, ul.1, br.0, br.1)) {
+                    if *painting_mask {
+                        let mask = toggled_mask(&mask, ul, br);
+                        set_plate_masks(vec![(dest_id, mask)]);
+                    } else if let Region::Custom(_) =
+                        ct_dispatch.get().transfer.transfer_region.dest_region
+                    {
+                        // Completes a pair started on the source plate
+                        if let Some(src) = ct_dispatch.get().pending_pair_source {
+                            if ul == br && ul.0 > 0 && ul.1 > 0 {
+                                ct_dispatch.reduce_mut(|state| {
+                                    state
+                                        .transfer
+                                        .transfer_region
+                                        .edit_custom(|c| c.push(src, ul));
+                                    state.pending_pair_source = None;
+                                });
+                            }
+                        }
+                    } else if e.ctrl_key() || e.shift_key() {
+                        // Ctrl/Shift adds another rectangle to the region
+                        ct_dispatch.reduce_mut(|state| {
+                            state.transfer.transfer_region.dest_region.add_rect(ul, br);
+                        });
+                    } else if let Ok(rd) = RegionDisplay::try_from((ul.0, ul.1, br.0, br.1)) {
                         ct_dispatch.reduce_mut(|state| {
                             state.transfer.transfer_region.dest_region = Region::from(&rd);
                         });
@@ -107,6 +244,84 @@ pub fn DestinationPlate(props: &DestinationPlateProps) -> Html {
 
     let mouseleave_callback = Callback::clone(&mouseup_callback);
 
+    let keydown_callback = {
+        let ct_dispatch = ct_dispatch.clone();
+        let k_cursor_handle = k_cursor_handle.clone();
+        let k_anchor_handle = k_anchor_handle.clone();
+        let painting_mask = painting_mask.clone();
+        let dest_id = props.destination_plate.get_uuid();
+        let mask = props.destination_plate.mask.clone();
+        Callback::from(move |e: KeyboardEvent| {
+            let region = ct_dispatch
+                .get()
+                .transfer
+                .transfer_region
+                .dest_region
+                .clone();
+            if let Some(offset) = arrow_offset(&e.key()) {
+                e.prevent_default();
+                if e.alt_key() {
+                    // Alt nudges the whole region
+                    if let Some(moved) = region.translate(offset, plate_size) {
+                        ct_dispatch.reduce_mut(|state| {
+                            state.transfer.transfer_region.dest_region = moved;
+                        });
+                    }
+                    return;
+                }
+                // The first press puts the cursor on the region, later ones move it
+                let (cursor, next) = match *k_cursor_handle {
+                    Some(cursor) => (cursor, step_well(cursor, offset, plate_size)),
+                    None => {
+                        let origin = region_origin(&region).unwrap_or((1, 1));
+                        (origin, origin)
+                    }
+                };
+                let anchor = match *k_anchor_handle {
+                    Some(anchor) if e.shift_key() => anchor,
+                    _ if e.shift_key() => cursor,
+                    _ => next,
+                };
+                k_cursor_handle.set(Some(next));
+                k_anchor_handle.set(Some(anchor));
+            } else if e.key() == "Enter" {
+                if let (Some(anchor), Some(cursor)) = (*k_anchor_handle, *k_cursor_handle) {
+                    e.prevent_default();
+                    if *painting_mask {
+                        let mask = toggled_mask(&mask, anchor, cursor);
+                        set_plate_masks(vec![(dest_id, mask)]);
+                    } else if let Region::Custom(_) = region {
+                        // Completes a pair started on the source plate
+                        if let Some(src) = ct_dispatch.get().pending_pair_source {
+                            ct_dispatch.reduce_mut(|state| {
+                                state
+                                    .transfer
+                                    .transfer_region
+                                    .edit_custom(|c| c.push(src, cursor));
+                                state.pending_pair_source = None;
+                            });
+                        }
+                    } else if let Ok(rd) =
+                        RegionDisplay::try_from((anchor.0, anchor.1, cursor.0, cursor.1))
+                    {
+                        ct_dispatch.reduce_mut(|state| {
+                            state.transfer.transfer_region.dest_region = Region::from(&rd);
+                        });
+                    }
+                }
+            } else if e.key() == "Escape" {
+                k_cursor_handle.set(None);
+                k_anchor_handle.set(None);
+            }
+        })
+    };
+
+    let toggle_painting_mask = {
+        let painting_mask = painting_mask.clone();
+        Callback::from(move |_: MouseEvent| {
+            painting_mask.set(!*painting_mask);
+        })
+    };
 
     let screenshot_callback = Callback::from(|_| {
         let _ = js_sys::eval("copy_screenshot_dest()");
@@ -126,16 +341,36 @@ pub fn DestinationPlate(props: &DestinationPlateProps) -> Html {
             let row = (1..=props.destination_plate.plate.size().1).map(|j| {
             html! {
                 <DestPlateCell i={i} j={j}
-                selected={super::source_plate::in_rect(*m_start_handle.clone(), *m_end_handle.clone(), (i,j))}
+                selected={match (&moved_region, *k_cursor_handle) {
+                    (Some(region), _) => region.contains((i,j)),
+                    (None, Some(cursor)) => in_rect(*k_anchor_handle, Some(cursor), (i,j)),
+                    (None, None) => in_rect(*m_start_handle.clone(), *m_end_handle.clone(), (i,j))
+                        || ct_state.transfer.transfer_region.dest_region.rects().iter()
+                            .any(|(c1, c2)| in_rect(Some(*c1), Some(*c2), (i,j))),
+                }}
+                cursor={*k_cursor_handle == Some((i,j))}
+                handle={if *m_stat_handle || *painting_mask { None } else {
+                    region_handle(&ct_state.transfer.transfer_region.dest_region, (i,j))
+                }}
                 mouse={mouse_callback.clone()}
                 in_transfer={destination_wells.contains(&(i,j)) && main_state.preferences.in_transfer_hashes}
-                color={transfer_map.get(&(i,j))
-                    .and_then(|t| t.last())
-                    .map(|t| PALETTE.get_ordered(t.get_uuid(), &ordered_ids))
-                }
+                color={match &props.heat {
+                    Some(heat) => heat.get(&(i,j)).map(|v| heat_color(*v, heat_max)),
+                    None => transfer_map.get(&(i,j))
+                        .and_then(|t| t.last())
+                        .map(|t| PALETTE.get_ordered(t.get_uuid(), &ordered_ids)),
+                }}
                 cell_height={props.cell_height}
-                title={transfer_map.get(&(i,j)).map(|transfers| format!("Used by: {}", transfers.iter().map(|t| t.name.clone())
-                                    .collect::<Vec<_>>().join(", ")))}
+                conflict={conflict_map.get(&(i,j)).copied()}
+                excluded={props.destination_plate.mask.contains(&(i,j))}
+                title={transfer_map.get(&(i,j)).map(|transfers| format!("Used by: {}{}", transfers.iter().map(|t| t.name.clone())
+                                    .collect::<Vec<_>>().join(", "),
+                                    match conflict_map.get(&(i,j)) {
+                                        _ if props.destination_plate.mask.contains(&(i,j)) => "\nExcluded well",
+                                        Some(true) => "\nPooled",
+                                        Some(false) => "\nConflict: written more than once",
+                                        None => "",
+                                    }) + &heat_label((i,j)))}
                 />
             }
         }).collect::<Html>();
@@ -148,7 +383,8 @@ pub fn DestinationPlate(props: &DestinationPlateProps) -> Html {
         .collect::<Html>();
 
     html! {
-        <div ondblclick={screenshot_callback}
+        <>
+        <div ondblclick={screenshot_callback} tabindex="0" onkeydown={keydown_callback}
         class={classes!{"dest_plate",
             "W".to_owned()+&props.source_plate.plate.plate_format.to_string()}}>
             <table
@@ -160,14 +396,76 @@ pub fn DestinationPlate(props: &DestinationPlateProps) -> Html {
             }}>
             { column_header }{ rows }
             </table>
+            if let Some(msg) = moved_error {
+                <p class="validation_error">{msg}</p>
+            }
         </div>
+        <button class={classes!("paint_mask", painting_mask.then_some("active"))}
+         onclick={toggle_painting_mask}>
+            {if *painting_mask { "Done Excluding Wells" } else { "Exclude Wells" }}
+        </button>
+        </>
+    }
+}
+
+/// Sets the excluded wells of each plate, asking first
+/// if saved transfers would be moved around the new masks.
+pub fn set_plate_masks(masks: Vec<(uuid::Uuid, Vec<(u8, u8)>)>) {
+    let main_dispatch = Dispatch::<MainState>::new();
+    let state = main_dispatch.get();
+    let names: Vec<String> = masks
+        .iter()
+        .flat_map(|(id, mask)| state.repacked_by_mask(*id, mask))
+        .map(|t| format!("\"{}\"", t.name))
+        .collect();
+    let apply = Callback::from(move |_| {
+        let masks = masks.clone();
+        main_dispatch.reduce_mut(|state| {
+            for (id, mask) in masks {
+                state.set_plate_mask(id, mask);
+            }
+        });
+    });
+    if names.is_empty() {
+        apply.emit(());
+    } else {
+        confirm(
+            "Change excluded wells?",
+            format!(
+                "Saved transfers will be repacked around the excluded wells: {}",
+                names.join(", ")
+            ),
+            "Repack",
+            apply,
+        );
     }
 }
 
+fn toggled_mask(mask: &[(u8, u8)], ul: (u8, u8), br: (u8, u8)) -> Vec<(u8, u8)> {
+    let wells: Vec<(u8, u8)> = (u8::min(ul.0, br.0)..=u8::max(ul.0, br.0))
+        .flat_map(|i| (u8::min(ul.1, br.1)..=u8::max(ul.1, br.1)).map(move |j| (i, j)))
+        .collect();
+    // Unmask if everything selected was masked, otherwise mask it all
+    let mut mask = mask.to_vec();
+    if wells.iter().all(|w| mask.contains(w)) {
+        mask.retain(|w| !wells.contains(w));
+    } else {
+        mask.extend(
+            wells
+                .into_iter()
+                .filter(|w| !mask.contains(w))
+                .collect::<Vec<_>>(),
+        );
+    }
+    mask
+}
+
 #[derive(Debug)]
 pub enum MouseEventType {
     Mousedown,
     Mouseenter,
+    Move,
+    Resize,
 }
 
 #[derive(Properties, PartialEq)]
@@ -180,6 +478,11 @@ pub struct DestPlateCellProps {
     color: Option<[f64; 3]>,
     cell_height: f64,
     title: Option<String>,
+    // Some(true) for allowed pooling, Some(false) for an unintended overlap
+    conflict: Option<bool>,
+    excluded: bool,
+    handle: Option<RegionHandle>,
+    cursor: bool,
 }
 
 #[function_component]
@@ -192,13 +495,21 @@ fn DestPlateCell(props: &DestPlateCellProps) -> Html {
         Some(true) => Some("in_transfer"),
         _ => None,
     };
+    let conflict_class = match props.conflict {
+        Some(true) => Some("pooled"),
+        Some(false) => Some("conflict"),
+        None => None,
+    };
+    let excluded_class = props.excluded.then_some("excluded");
     let color = props.color.unwrap_or([255.0, 255.0, 255.0]);
     let mouse = Callback::clone(&props.mouse);
     let mouse2 = Callback::clone(&props.mouse);
+    let mouse3 = Callback::clone(&props.mouse);
     let (i, j) = (props.i, props.j);
 
     html! {
-        <td class={classes!("plate_cell", selected_class, in_transfer_class)}
+        <td class={classes!("plate_cell", selected_class, in_transfer_class, conflict_class, excluded_class,
+            props.cursor.then_some("cursor"))}
             style={format!("height: {}px;", props.cell_height)}
             onmousedown={move |_| {
                 mouse.emit((i,j, MouseEventType::Mousedown))
@@ -211,6 +522,16 @@ fn DestPlateCell(props: &DestPlateCellProps) -> Html {
             title={if let Some(text) = &props.title {
                 text.clone()
             } else { "".to_string() }}/>
+            if let Some(handle) = props.handle {
+                <div class={classes!("region_handle", handle.class())}
+                onmousedown={move |e: MouseEvent| {
+                    e.stop_propagation();
+                    mouse3.emit((i,j, match handle {
+                        RegionHandle::Move => MouseEventType::Move,
+                        RegionHandle::Resize => MouseEventType::Resize,
+                    }))
+                }}/>
+            }
         </td>
     }
 }
@@ -1,4 +1,5 @@
 pub mod destination_plate;
+pub mod overview;
 pub mod plate_container;
 pub mod source_plate;
 mod util;
@@ -0,0 +1,159 @@
+#![allow(non_snake_case)]
+
+use std::collections::HashMap;
+use uuid::Uuid;
+use yew::prelude::*;
+use yewdux::prelude::*;
+
+use crate::components::states::{CurrentTransfer, MainState};
+use crate::data::plate_instances::PlateInstance;
+use crate::data::transfer::Transfer;
+use crate::data::transfer_region::Region;
+
+// Same palette as the destination plate, so colors match between views
+use crate::components::plates::util::Palettes;
+const PALETTE: super::util::ColorPalette = Palettes::RAINBOW;
+
+#[derive(PartialEq, Properties)]
+pub struct OverviewProps {
+    // Called after a plate is picked to be edited
+    pub close_callback: Callback<()>,
+}
+
+#[function_component]
+pub fn Overview(props: &OverviewProps) -> Html {
+    let (main_state, main_dispatch) = use_store::<MainState>();
+    let (_, ct_dispatch) = use_store::<CurrentTransfer>();
+
+    let ordered_ids: Vec<Uuid> = {
+        let mut ids: Vec<Uuid> = main_state.transfers.iter().map(|x| x.id).collect();
+        ids.sort_unstable();
+        ids
+    };
+
+    let open_plate = {
+        let close_callback = props.close_callback.clone();
+        let main_state = main_state.clone();
+        move |dest_id: Uuid| {
+            let close_callback = close_callback.clone();
+            let main_state = main_state.clone();
+            let main_dispatch = main_dispatch.clone();
+            let ct_dispatch = ct_dispatch.clone();
+            Callback::from(move |_: MouseEvent| {
+                // Keep the source plate if it feeds this plate, otherwise pick one that does
+                let feeds = |source_id: Uuid| {
+                    main_state
+                        .transfers
+                        .iter()
+                        .any(|t| t.source_id == source_id && t.dest_id == dest_id)
+                };
+                let source_id = if feeds(main_state.selected_source_plate) {
+                    main_state.selected_source_plate
+                } else {
+                    main_state
+                        .transfers
+                        .iter()
+                        .find(|t| t.dest_id == dest_id)
+                        .map(|t| t.source_id)
+                        .unwrap_or(main_state.selected_source_plate)
+                };
+                ct_dispatch.reduce_mut(|state| {
+                    state.transfer.transfer_region.source_region = Region::default();
+                    state.transfer.transfer_region.dest_region = Region::default();
+                });
+                main_dispatch.reduce_mut(|state| {
+                    state.selected_source_plate = source_id;
+                    state.selected_dest_plate = dest_id;
+                    state.selected_transfer = Uuid::nil();
+                });
+                close_callback.emit(());
+            })
+        }
+    };
+
+    let plates = main_state
+        .destination_plates
+        .iter()
+        .map(|dpi| {
+            html! {
+                <PlateThumbnail plate={dpi.clone()}
+                transfers={main_state.transfers.iter()
+                    .filter(|t| t.dest_id == dpi.get_uuid())
+                    .cloned().collect::<Vec<_>>()}
+                ordered_ids={ordered_ids.clone()}
+                onclick={open_plate(dpi.get_uuid())}/>
+            }
+        })
+        .collect::<Html>();
+
+    html! {
+        <div class="overview">
+            if main_state.destination_plates.is_empty() {
+                <h2>{"No Destination Plates"}</h2>
+            } else {
+                { plates }
+            }
+        </div>
+    }
+}
+
+#[derive(PartialEq, Properties)]
+struct PlateThumbnailProps {
+    plate: PlateInstance,
+    transfers: Vec<Transfer>,
+    ordered_ids: Vec<Uuid>,
+    onclick: Callback<MouseEvent>,
+}
+
+#[function_component]
+fn PlateThumbnail(props: &PlateThumbnailProps) -> Html {
+    let size = props.plate.plate.size();
+    let mut hits: HashMap<(u8, u8), Vec<&Transfer>> = HashMap::new();
+    for t in props.transfers.iter() {
+        for well in t.transfer_region.get_destination_wells(&props.plate.mask) {
+            hits.entry(well).or_default().push(t);
+        }
+    }
+    let excluded = props.plate.mask.len();
+    let filled = hits.len();
+    let empty = (1..=size.0)
+        .flat_map(|i| (1..=size.1).map(move |j| (i, j)))
+        .filter(|w| !hits.contains_key(w) && !props.plate.mask.contains(w))
+        .count();
+
+    let rows = (1..=size.0)
+        .map(|i| {
+            let row = (1..=size.1)
+                .map(|j| {
+                    let color = hits
+                        .get(&(i, j))
+                        .and_then(|t| t.last())
+                        .map(|t| PALETTE.get_ordered(t.get_uuid(), &props.ordered_ids));
+                    let style = match color {
+                        Some(c) => format!("background: rgba({},{},{},1);", c[0], c[1], c[2]),
+                        None => String::new(),
+                    };
+                    html! {
+                        <td class={classes!(props.plate.mask.contains(&(i, j)).then_some("excluded"))}
+                        style={style}/>
+                    }
+                })
+                .collect::<Html>();
+            html! { <tr>{ row }</tr> }
+        })
+        .collect::<Html>();
+
+    html! {
+        <div class="overview_plate" onclick={props.onclick.clone()}
+        title="Click to edit this plate">
+            <h3>{props.plate.name.clone()}</h3>
+            <table>{ rows }</table>
+            <p>{format!("{} filled, {} empty", filled, empty)}
+            if excluded > 0 {
+                {format!(", {} excluded", excluded)}
+            }
+            {format!(" ({} transfers)", props.transfers.len())}
+            </p>
+        </div>
+    }
+}
@@ -1,12 +1,19 @@
 #![allow(non_snake_case)]
 use wasm_bindgen::prelude::Closure;
 use wasm_bindgen::JsCast;
+use web_sys::HtmlSelectElement;
 use yew::prelude::*;
+use yewdux::prelude::*;
 
+use crate::components::states::MainState;
+use crate::data::heatmap::{
+    destination_concentrations, destination_volumes, source_usage, value_range, Overlay,
+};
 use crate::data::plate_instances::PlateInstance;
 
 use super::destination_plate::DestinationPlate;
 use super::source_plate::SourcePlate;
+use super::util::heat_color;
 
 #[derive(Properties, PartialEq)]
 pub struct PlateContainerProps {
@@ -38,6 +45,87 @@ pub fn PlateContainer(props: &PlateContainerProps) -> Html {
         }
     };
 
+    let (main_state, main_dispatch) = use_store::<MainState>();
+    let overlay = main_state.preferences.overlay;
+    // Values for the heatmap, computed from the saved transfers like the export is
+    let (source_heat, dest_heat) = match (&props.source_dims, &props.destination_dims) {
+        (Some(spi), Some(dpi)) => match overlay {
+            Overlay::Transfers => (None, None),
+            Overlay::SourceUsage => (
+                Some(source_usage(
+                    &main_state.transfers,
+                    &main_state.destination_plates,
+                    spi.get_uuid(),
+                )),
+                None,
+            ),
+            Overlay::DestinationVolume => (
+                None,
+                Some(destination_volumes(
+                    &main_state.transfers,
+                    &main_state.destination_plates,
+                    dpi.get_uuid(),
+                )),
+            ),
+            Overlay::Concentration => (
+                None,
+                Some(destination_concentrations(
+                    &main_state.transfers,
+                    &main_state.source_plates,
+                    dpi,
+                )),
+            ),
+        },
+        _ => (None, None),
+    };
+    let legend =
+        source_heat
+            .as_ref()
+            .or(dest_heat.as_ref())
+            .map(|values| match value_range(values) {
+                Some((_, max)) => {
+                    let stops = (0..=4)
+                        .map(|k| {
+                            let c = heat_color(max * k as f32 / 4.0, max);
+                            format!("rgb({},{},{})", c[0], c[1], c[2])
+                        })
+                        .collect::<Vec<_>>()
+                        .join(", ");
+                    html! {
+                        <div class="legend">
+                            <span>{"0"}</span>
+                            <div class="legend_gradient"
+                            style={format!("background: linear-gradient(to right, {});", stops)}/>
+                            <span>{format!("{:.1} {}", max, overlay.unit())}</span>
+                        </div>
+                    }
+                }
+                None if overlay == Overlay::Concentration => html! {
+                    <p>{"Set source plate concentrations in their Plate Info to see this."}</p>
+                },
+                None => html! { <p>{"Nothing to show yet."}</p> },
+            });
+    let on_overlay_change = Callback::from(move |e: Event| {
+        let select = e
+            .target()
+            .and_then(|t| t.dyn_into::<HtmlSelectElement>().ok());
+        if let Some(overlay) = select
+            .and_then(|s| s.value().parse::<usize>().ok())
+            .and_then(|i| Overlay::ALL.get(i))
+        {
+            main_dispatch.reduce_mut(|state| state.preferences.overlay = *overlay);
+        }
+    });
+    let overlay_options = Overlay::ALL
+        .iter()
+        .enumerate()
+        .map(|(i, o)| {
+            html! {
+                <option value={i.to_string()} selected={*o == overlay}>{o.label()}</option>
+            }
+        })
+        .collect::<Html>();
+
     let resize_trigger = use_force_update();
     let onresize = Closure::<dyn FnMut(_)>::new(move |_: Event| {
         resize_trigger.force_update();
@@ -49,17 +137,25 @@ pub fn PlateContainer(props: &PlateContainerProps) -> Html {
 
     html! {
         <div class="plate_container">
+            <div class="overlay_select">
+                <label>{"Color wells by: "}
+                <select onchange={on_overlay_change}>{ overlay_options }</select>
+                </label>
+                if let Some(legend) = legend {
+                    { legend }
+                }
+            </div>
             if let Some(spi) = props.source_dims.clone() {
             if let Some(dpi) = props.destination_dims.clone() {
             <div class="plate_container--source">
                 <h2>{spi.name.clone()}</h2>
                 <SourcePlate source_plate={spi.clone()} destination_plate={dpi.clone()}
-                cell_height={cell_height}/>
+                cell_height={cell_height} heat={source_heat}/>
             </div>
             <div class="plate_container--destination">
                 <h2>{dpi.name.clone()}</h2>
                 <DestinationPlate source_plate={spi.clone()} destination_plate={dpi.clone()}
-                cell_height={cell_height}/>
+                cell_height={cell_height} heat={dest_heat}/>
             </div>
             } else {
                 <h2>{"No Destination Plate Selected"}</h2>
@@ -5,12 +5,13 @@ use yew::prelude::*;
 use yewdux::prelude::*;
 
 use crate::components::states::{CurrentTransfer, MainState};
+use crate::data::heatmap::value_range;
 use crate::data::plate_instances::PlateInstance;
 use crate::data::transfer::Transfer;
 use crate::data::transfer_region::Region;
 
 // Color Palette for the Source Plates, can be changed here
-use crate::components::plates::util::Palettes;
+use crate::components::plates::util::{heat_color, Palettes};
 const PALETTE: super::util::ColorPalette = Palettes::RAINBOW;
 
 use super::super::transfer_menu::{num_to_letters, RegionDisplay};
@@ -20,6 +21,8 @@ pub struct SourcePlateProps {
     pub source_plate: PlateInstance,
     pub destination_plate: PlateInstance,
     pub cell_height: f64,
+    // Heatmap values to color wells by, instead of by transfer
+    pub heat: Option<HashMap<(u8, u8), f32>>,
 }
 
 #[function_component]
@@ -29,12 +32,23 @@ pub fn SourcePlate(props: &SourcePlateProps) -> Html {
     let m_start_handle: UseStateHandle<Option<(u8, u8)>> = use_state_eq(|| None);
     let m_end_handle: UseStateHandle<Option<(u8, u8)>> = use_state_eq(|| None);
     let m_stat_handle: UseStateHandle<bool> = use_state_eq(|| false);
+    // While moving the region: the well it was grabbed by, and how far it has moved
+    let m_grab_handle: UseStateHandle<Option<(u8, u8)>> = use_state_eq(|| None);
+    let m_offset_handle: UseStateHandle<(i16, i16)> = use_state_eq(|| (0, 0));
+    let plate_size = props.source_plate.plate.size();
+    // Keyboard selection: the well under the cursor and where a Shift-selection started
+    let k_cursor_handle: UseStateHandle<Option<(u8, u8)>> = use_state_eq(|| None);
+    let k_anchor_handle: UseStateHandle<Option<(u8, u8)>> = use_state_eq(|| None);
 
     if !(*m_stat_handle) {
         let (pt1, pt2) = match ct_state.transfer.transfer_region.source_region {
             Region::Point((x, y)) => ((x, y), (x, y)),
             Region::Rect(c1, c2) => (c1, c2),
-            Region::Custom(_) => ((0,0), (0,0)),
+            Region::Custom(_) => ct_state
+                .pending_pair_source
+                .map(|w| (w, w))
+                .unwrap_or(((0, 0), (0, 0))),
+            Region::Multi(ref rects) => rects.last().copied().unwrap_or(((0, 0), (0, 0))),
         };
         m_start_handle.set(Some(pt1));
         m_end_handle.set(Some(pt2));
@@ -61,27 +75,88 @@ pub fn SourcePlate(props: &SourcePlateProps) -> Html {
 
     let source_wells = ct_state.transfer.transfer_region.get_source_wells();
 
+    let heat_max = props
+        .heat
+        .as_ref()
+        .and_then(value_range)
+        .map(|(_, max)| max)
+        .unwrap_or(0.0);
+    let heat_label = |well: (u8, u8)| {
+        let unit = main_state.preferences.overlay.unit();
+        props
+            .heat
+            .as_ref()
+            .and_then(|heat| heat.get(&well))
+            .map(|v| format!("\n{:.1} {}", v, unit))
+            .unwrap_or_default()
+    };
+
     let ordered_ids: Vec<uuid::Uuid> = {
-        let mut ids: Vec<uuid::Uuid> = main_state.transfers.clone().iter()
-        .map(|x| x.id)
-        .collect();
+        let mut ids: Vec<uuid::Uuid> = main_state.transfers.clone().iter().map(|x| x.id).collect();
         ids.sort_unstable();
         ids
     };
 
+    // The region as it would be if dropped now, while it is being moved
+    let moved_region = m_grab_handle.and_then(|_| {
+        ct_state
+            .transfer
+            .transfer_region
+            .source_region
+            .translate(*m_offset_handle, plate_size)
+    });
+    let moved_error = moved_region.as_ref().and_then(|region| {
+        let mut tr = ct_state.transfer.transfer_region.clone();
+        tr.source_region = region.clone();
+        let mask = main_state.plate_mask(ct_state.transfer.dest_id);
+        tr.validate().and_then(|_| tr.validate_mask(mask)).err()
+    });
+
     let mouse_callback = {
         let m_start_handle = m_start_handle.clone();
         let m_end_handle = m_end_handle.clone();
         let m_stat_handle = m_stat_handle.clone();
+        let m_grab_handle = m_grab_handle.clone();
+        let m_offset_handle = m_offset_handle.clone();
+        let ct_dispatch = ct_dispatch.clone();
+        let k_cursor_handle = k_cursor_handle.clone();
 
         Callback::from(move |(i, j, t)| match t {
+            MouseEventType::Mousedown | MouseEventType::Move | MouseEventType::Resize
+                if k_cursor_handle.is_some() =>
+            {
+                // The mouse takes over from the keyboard
+                k_cursor_handle.set(None);
+            }
             MouseEventType::Mousedown => {
                 m_start_handle.set(Some((i, j)));
                 m_end_handle.set(Some((i, j)));
                 m_stat_handle.set(true);
             }
+            MouseEventType::Move => {
+                m_grab_handle.set(Some((i, j)));
+                m_offset_handle.set((0, 0));
+                m_stat_handle.set(true);
+            }
+            MouseEventType::Resize => {
+                // Dragging a corner keeps the opposite corner in place
+                let region = &ct_dispatch.get().transfer.transfer_region.source_region;
+                if let Some(opposite) = region.opposite_corner((i, j)) {
+                    m_start_handle.set(Some(opposite));
+                    m_end_handle.set(Some((i, j)));
+                    m_stat_handle.set(true);
+                }
+            }
+            MouseEventType::Mouseenter if !*m_stat_handle => (),
             MouseEventType::Mouseenter => {
-                if *m_stat_handle {
+                if let Some(grab) = *m_grab_handle {
+                    let offset = (i as i16 - grab.0 as i16, j as i16 - grab.1 as i16);
+                    let region = &ct_dispatch.get().transfer.transfer_region.source_region;
+                    // Stops at the edge of the plate
+                    if region.translate(offset, plate_size).is_some() {
+                        m_offset_handle.set(offset);
+                    }
+                } else {
                     m_end_handle.set(Some((i, j)));
                 }
             }
@@ -91,12 +166,48 @@ pub fn SourcePlate(props: &SourcePlateProps) -> Html {
     let mouseup_callback = {
         let m_start_handle = m_start_handle.clone();
         let m_end_handle = m_end_handle.clone();
+        let m_stat_handle = m_stat_handle.clone();
+        let m_grab_handle = m_grab_handle.clone();
+        let ct_dispatch = ct_dispatch.clone();
 
-        Callback::from(move |_: MouseEvent| {
+        Callback::from(move |e: MouseEvent| {
+            if !*m_stat_handle {
+                return; // Nothing was being selected
+            }
             m_stat_handle.set(false);
+            if m_grab_handle.is_some() {
+                m_grab_handle.set(None);
+                let offset = *m_offset_handle;
+                ct_dispatch.reduce_mut(|state| {
+                    let region = &mut state.transfer.transfer_region.source_region;
+                    if let Some(moved) = region.translate(offset, plate_size) {
+                        *region = moved;
+                    }
+                });
+                return;
+            }
             if let Some(ul) = *m_start_handle {
                 if let Some(br) = *m_end_handle {
-                    if let Ok(rd) = RegionDisplay::try_from((ul.0, ul.1, br.0, br.1)) {
+                    if let Region::Custom(_) =
+                        ct_dispatch.get().transfer.transfer_region.source_region
+                    {
+                        // Clicking a single well starts a new pair,
+                        // finished by clicking on the destination plate
+                        if ul == br && ul.0 > 0 && ul.1 > 0 {
+                            ct_dispatch.reduce_mut(|state| {
+                                state.pending_pair_source = Some(ul);
+                            });
+                        }
+                    } else if e.ctrl_key() || e.shift_key() {
+                        // Ctrl/Shift adds another rectangle to the region
+                        ct_dispatch.reduce_mut(|state| {
+                            state
+                                .transfer
+                                .transfer_region
+                                .source_region
+                                .add_rect(ul, br);
+                        });
+                    } else if let Ok(rd) = RegionDisplay::try_from((ul.0, ul.1, br.0, br.1)) {
                         ct_dispatch.reduce_mut(|state| {
                             state.transfer.transfer_region.source_region = Region::from(&rd);
                         });
@@ -108,6 +219,64 @@ pub fn SourcePlate(props: &SourcePlateProps) -> Html {
 
     let mouseleave_callback = Callback::clone(&mouseup_callback);
 
+    let keydown_callback = {
+        let ct_dispatch = ct_dispatch.clone();
+        let k_cursor_handle = k_cursor_handle.clone();
+        let k_anchor_handle = k_anchor_handle.clone();
+        Callback::from(move |e: KeyboardEvent| {
+            let region = ct_dispatch
+                .get()
+                .transfer
+                .transfer_region
+                .source_region
+                .clone();
+            if let Some(offset) = arrow_offset(&e.key()) {
+                e.prevent_default();
+                if e.alt_key() {
+                    // Alt nudges the whole region
+                    if let Some(moved) = region.translate(offset, plate_size) {
+                        ct_dispatch.reduce_mut(|state| {
+                            state.transfer.transfer_region.source_region = moved;
+                        });
+                    }
+                    return;
+                }
+                // The first press puts the cursor on the region, later ones move it
+                let (cursor, next) = match *k_cursor_handle {
+                    Some(cursor) => (cursor, step_well(cursor, offset, plate_size)),
+                    None => {
+                        let origin = region_origin(&region).unwrap_or((1, 1));
+                        (origin, origin)
+                    }
+                };
+                let anchor = match *k_anchor_handle {
+                    Some(anchor) if e.shift_key() => anchor,
+                    _ if e.shift_key() => cursor,
+                    _ => next,
+                };
+                k_cursor_handle.set(Some(next));
+                k_anchor_handle.set(Some(anchor));
+            } else if e.key() == "Enter" {
+                if let (Some(anchor), Some(cursor)) = (*k_anchor_handle, *k_cursor_handle) {
+                    e.prevent_default();
+                    if let Region::Custom(_) = region {
+                        // Starts a pair, as clicking a single well does
+                        ct_dispatch.reduce_mut(|state| state.pending_pair_source = Some(cursor));
+                    } else if let Ok(rd) =
+                        RegionDisplay::try_from((anchor.0, anchor.1, cursor.0, cursor.1))
+                    {
+                        ct_dispatch.reduce_mut(|state| {
+                            state.transfer.transfer_region.source_region = Region::from(&rd);
+                        });
+                    }
+                }
+            } else if e.key() == "Escape" {
+                k_cursor_handle.set(None);
+                k_anchor_handle.set(None);
+            }
+        })
+    };
+
     let screenshot_callback = Callback::from(|_| {
         let _ = js_sys::eval("copy_screenshot_src()");
     });
@@ -129,16 +298,28 @@ pub fn SourcePlate(props: &SourcePlateProps) -> Html {
                 .map(|j| {
                     html! {
                         <SourcePlateCell i={i} j={j}
-                        selected={in_rect(*m_start_handle.clone(), *m_end_handle.clone(), (i,j))}
+                        selected={match (&moved_region, *k_cursor_handle) {
+                            (Some(region), _) => region.contains((i,j)),
+                            (None, Some(cursor)) => in_rect(*k_anchor_handle, Some(cursor), (i,j)),
+                            (None, None) => in_rect(*m_start_handle.clone(), *m_end_handle.clone(), (i,j))
+                                || ct_state.transfer.transfer_region.source_region.rects().iter()
+                                    .any(|(c1, c2)| in_rect(Some(*c1), Some(*c2), (i,j))),
+                        }}
+                        cursor={*k_cursor_handle == Some((i,j))}
+                        handle={if *m_stat_handle { None } else {
+                            region_handle(&ct_state.transfer.transfer_region.source_region, (i,j))
+                        }}
                         mouse={mouse_callback.clone()}
                         in_transfer={source_wells.contains(&(i,j)) && main_state.preferences.in_transfer_hashes}
-                        color={transfer_map.get(&(i,j))
-                            .and_then(|t| t.last())
-                            .map(|t| PALETTE.get_ordered(t.get_uuid(), &ordered_ids))
-                        }
+                        color={match &props.heat {
+                            Some(heat) => heat.get(&(i,j)).map(|v| heat_color(*v, heat_max)),
+                            None => transfer_map.get(&(i,j))
+                                .and_then(|t| t.last())
+                                .map(|t| PALETTE.get_ordered(t.get_uuid(), &ordered_ids)),
+                        }}
                         cell_height={props.cell_height}
                         title={transfer_map.get(&(i,j)).map(|transfers| format!("Used by: {}", transfers.iter().map(|t| t.name.clone())
-                                    .collect::<Vec<_>>().join(", ")))}
+                                    .collect::<Vec<_>>().join(", ")) + &heat_label((i,j)))}
                         />
                     }
                 })
@@ -152,7 +333,7 @@ pub fn SourcePlate(props: &SourcePlateProps) -> Html {
         .collect::<Html>();
 
     html! {
-        <div ondblclick={screenshot_callback}
+        <div ondblclick={screenshot_callback} tabindex="0" onkeydown={keydown_callback}
         class={classes!{"source_plate",
         "W".to_owned()+&props.source_plate.plate.plate_format.to_string()}}>
             <table
@@ -165,6 +346,9 @@ pub fn SourcePlate(props: &SourcePlateProps) -> Html {
                 { column_header }
                 { rows }
             </table>
+            if let Some(msg) = moved_error {
+                <p class="validation_error">{msg}</p>
+            }
         </div>
     }
 }
@@ -179,11 +363,15 @@ pub struct SourcePlateCellProps {
     color: Option<[f64; 3]>,
     cell_height: f64,
     title: Option<String>,
+    handle: Option<RegionHandle>,
+    cursor: bool,
 }
 #[derive(Debug)]
 pub enum MouseEventType {
     Mousedown,
     Mouseenter,
+    Move,
+    Resize,
 }
 
 #[function_component]
@@ -199,10 +387,11 @@ fn SourcePlateCell(props: &SourcePlateCellProps) -> Html {
     let color = props.color.unwrap_or([255.0, 255.0, 255.0]);
     let mouse = Callback::clone(&props.mouse);
     let mouse2 = Callback::clone(&props.mouse);
+    let mouse3 = Callback::clone(&props.mouse);
     let (i, j) = (props.i, props.j);
 
     html! {
-        <td class={classes!("plate_cell", selected_class, in_transfer_class)}
+        <td class={classes!("plate_cell", selected_class, in_transfer_class, props.cursor.then_some("cursor"))}
             style={format!("height: {}px;", props.cell_height)}
             id={format!("color={:?}", props.color)}
             onmousedown={move |_| {
@@ -216,10 +405,77 @@ fn SourcePlateCell(props: &SourcePlateCellProps) -> Html {
             title={if let Some(text) = &props.title {
                 text.clone()
             } else {"".to_string()}}/>
+            if let Some(handle) = props.handle {
+                <div class={classes!("region_handle", handle.class())}
+                onmousedown={move |e: MouseEvent| {
+                    e.stop_propagation();
+                    mouse3.emit((i,j, match handle {
+                        RegionHandle::Move => MouseEventType::Move,
+                        RegionHandle::Resize => MouseEventType::Resize,
+                    }))
+                }}/>
+            }
         </td>
     }
 }
 
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum RegionHandle {
+    Move,
+    Resize,
+}
+impl RegionHandle {
+    pub fn class(&self) -> &'static str {
+        match self {
+            RegionHandle::Move => "move",
+            RegionHandle::Resize => "resize",
+        }
+    }
+}
+
+pub fn region_origin(region: &Region) -> Option<(u8, u8)> {
+    // The upper-left well of a region (of its first rectangle, if several)
+    match region {
+        Region::Point(p) => Some(*p),
+        Region::Rect(c1, c2) => Some((u8::min(c1.0, c2.0), u8::min(c1.1, c2.1))),
+        Region::Multi(rects) => rects
+            .first()
+            .map(|(c1, c2)| (u8::min(c1.0, c2.0), u8::min(c1.1, c2.1))),
+        Region::Custom(_) => None,
+    }
+}
+
+pub fn region_handle(region: &Region, well: (u8, u8)) -> Option<RegionHandle> {
+    // The upper-left well of a region moves it,
+    // the other corners of a rectangle resize it
+    if well == region_origin(region)? {
+        Some(RegionHandle::Move)
+    } else if region.opposite_corner(well).is_some() {
+        Some(RegionHandle::Resize)
+    } else {
+        None
+    }
+}
+
+pub fn arrow_offset(key: &str) -> Option<(i16, i16)> {
+    // (Rows, Columns) to nudge a region by
+    match key {
+        "ArrowUp" => Some((-1, 0)),
+        "ArrowDown" => Some((1, 0)),
+        "ArrowLeft" => Some((0, -1)),
+        "ArrowRight" => Some((0, 1)),
+        _ => None,
+    }
+}
+
+pub fn step_well(well: (u8, u8), offset: (i16, i16), size: (u8, u8)) -> (u8, u8) {
+    // Moves a well, stopping at the edges of the plate
+    (
+        (well.0 as i16 + offset.0).clamp(1, size.0 as i16) as u8,
+        (well.1 as i16 + offset.1).clamp(1, size.1 as i16) as u8,
+    )
+}
+
 pub fn in_rect(corner1: Option<(u8, u8)>, corner2: Option<(u8, u8)>, pt: (u8, u8)) -> bool {
     if let (Some(c1), Some(c2)) = (corner1, corner2) {
         pt.0 <= u8::max(c1.0, c2.0)
@@ -235,7 +491,7 @@ pub fn in_rect(corner1: Option<(u8, u8)>, corner2: Option<(u8, u8)>, pt: (u8, u8
 mod tests {
     use wasm_bindgen_test::*;
 
-    use super::in_rect;
+    use super::{in_rect, step_well};
 
     // in_rect tests
     #[test]
@@ -291,4 +547,15 @@ mod tests {
         assert!(!in_rect(Some(c1), Some(c2), pt1));
         assert!(!in_rect(Some(c1), Some(c2), pt2));
     }
+
+    #[test]
+    #[wasm_bindgen_test]
+    fn test_step_well() {
+        let size = (8, 12);
+        assert_eq!(step_well((4, 6), (1, 0), size), (5, 6));
+        assert_eq!(step_well((4, 6), (0, -1), size), (4, 5));
+        // Stops at the edges of the plate
+        assert_eq!(step_well((1, 1), (-1, 0), size), (1, 1));
+        assert_eq!(step_well((8, 12), (0, 1), size), (8, 12));
+    }
 }
@@ -72,5 +72,18 @@ impl Palettes {
         c: [0.100, 0.500, 0.360],
         d: [0.000, 0.000, 0.650],
     };
+    // Runs from white at 0 to dark blue at 1, for heatmaps
+    pub const HEAT: ColorPalette = ColorPalette {
+        a: [0.500, 0.550, 0.750],
+        b: [0.500, 0.450, 0.250],
+        c: [0.500, 0.500, 0.500],
+        d: [0.000, 0.000, 0.000],
+    };
+
+}
 
+pub fn heat_color(value: f32, max: f32) -> [f64; 3] {
+    // Heatmaps run from zero up to the largest value shown
+    let t = if max > 0.0 { value / max } else { 1.0 };
+    Palettes::HEAT.get(t.clamp(0.0, 1.0) as f64)
 }
@@ -0,0 +1,468 @@
+#![allow(non_snake_case)]
+
+use std::any::type_name;
+
+use uuid::Uuid;
+use wasm_bindgen::closure::Closure;
+use wasm_bindgen::{JsCast, JsValue};
+use web_sys::{HtmlDialogElement, HtmlInputElement, Storage, StorageEvent};
+use yew::prelude::*;
+use yewdux::prelude::*;
+
+use super::notifications::{confirm, notify, Level};
+use super::states::{CurrentTransfer, MainState, Projects};
+use crate::data::history::{push_snapshot, Snapshot};
+use crate::data::schema::{from_json, to_json};
+
+// Each project's state and snapshots live under their own keys in local storage
+const PROJECT_KEY_PREFIX: &str = "plate-tool-project-";
+const HISTORY_KEY_PREFIX: &str = "plate-tool-history-";
+// Where the open state was kept before there were projects
+const LEGACY_STATE_KEY: &str = "plate_tool::components::states::MainState";
+// Each tab has its own open project, kept in session storage
+const OPEN_PROJECT_KEY: &str = "plate-tool-open-project";
+// How often the open project is snapshotted, in ms (only if it changed)
+const AUTOSAVE_INTERVAL: i32 = 5 * 60 * 1000;
+
+fn local_storage() -> Option<Storage> {
+    web_sys::window()?.local_storage().ok()?
+}
+
+fn session_storage() -> Option<Storage> {
+    web_sys::window()?.session_storage().ok()?
+}
+
+fn project_key(id: Uuid) -> String {
+    format!("{}{}", PROJECT_KEY_PREFIX, id)
+}
+
+pub fn now() -> f64 {
+    js_sys::Date::now()
+}
+
+pub fn format_time(ms: f64) -> String {
+    js_sys::Date::new(&JsValue::from_f64(ms))
+        .to_locale_string("default", &JsValue::UNDEFINED)
+        .into()
+}
+
+/// A project's stored state; one that was never saved is empty.
+pub fn load_project(id: Uuid) -> Result<MainState, String> {
+    let storage = local_storage().ok_or("Local storage is unavailable")?;
+    match storage.get_item(&project_key(id)) {
+        Ok(Some(json)) => from_json(&json),
+        Ok(None) => Ok(MainState::default()),
+        Err(_) => Err("Unable to read local storage".to_string()),
+    }
+}
+
+/// Stores a project's state, returning whether it differed from what was stored.
+pub fn save_project(id: Uuid, state: &MainState) -> bool {
+    let (Some(storage), Ok(json)) = (local_storage(), to_json(state)) else {
+        return false;
+    };
+    let key = project_key(id);
+    if storage.get_item(&key).ok().flatten().as_ref() == Some(&json) {
+        return false;
+    }
+    storage.set_item(&key, &json).is_ok()
+}
+
+fn delete_project(id: Uuid) {
+    if let Some(storage) = local_storage() {
+        storage.remove_item(&project_key(id)).ok();
+        storage.remove_item(&history_key(id)).ok();
+    }
+}
+
+// The list as last stored by any tab (under yewdux's key for the store)
+fn load_projects() -> Option<Projects> {
+    let json = local_storage()?.get_item(type_name::<Projects>()).ok()??;
+    serde_json::from_str(&json).ok()
+}
+
+/// Changes the list of projects, starting from what other tabs last stored
+/// so their changes aren't written over.
+pub fn update_projects(f: impl FnOnce(&mut Projects)) {
+    let stored = load_projects();
+    Dispatch::<Projects>::new().reduce_mut(|projects| {
+        if let Some(stored) = stored {
+            projects.merge(stored);
+        }
+        f(projects);
+    });
+}
+
+fn set_open_project(id: Uuid) {
+    update_projects(|projects| projects.current = id);
+    if let Some(storage) = session_storage() {
+        storage.set_item(OPEN_PROJECT_KEY, &id.to_string()).ok();
+    }
+}
+
+fn history_key(id: Uuid) -> String {
+    format!("{}{}", HISTORY_KEY_PREFIX, id)
+}
+
+/// A project's snapshots, oldest first.
+pub fn load_history(id: Uuid) -> Result<Vec<Snapshot>, String> {
+    let storage = local_storage().ok_or("Local storage is unavailable")?;
+    match storage.get_item(&history_key(id)) {
+        Ok(Some(json)) => serde_json::from_str(&json).map_err(|e| e.to_string()),
+        Ok(None) => Ok(Vec::new()),
+        Err(_) => Err("Unable to read local storage".to_string()),
+    }
+}
+
+fn save_history(id: Uuid, mut history: Vec<Snapshot>) {
+    let Some(storage) = local_storage() else {
+        return;
+    };
+    // Makes room by dropping the oldest snapshots if storage is full
+    while !history.is_empty() {
+        let stored = serde_json::to_string(&history)
+            .map(|json| storage.set_item(&history_key(id), &json).is_ok())
+            .unwrap_or(false);
+        if stored {
+            return;
+        }
+        history.remove(0);
+    }
+}
+
+/// Keeps a copy of the open project that can be restored from the history.
+pub fn take_snapshot(reason: &str) {
+    let id = Dispatch::<Projects>::new().get().current;
+    let snapshot = Snapshot {
+        time: now(),
+        reason: reason.to_string(),
+        state: (*Dispatch::<MainState>::new().get()).clone(),
+    };
+    // Saving would replace the snapshots that couldn't be read
+    let mut history = match load_history(id) {
+        Ok(history) => history,
+        Err(e) => {
+            notify(
+                Level::Error,
+                format!(
+                    "The history couldn't be read, so no snapshot was taken: {}",
+                    e
+                ),
+            );
+            return;
+        }
+    };
+    if push_snapshot(&mut history, snapshot) {
+        save_history(id, history);
+    }
+}
+
+/// Switches to another project, keeping the open one as it is.
+/// Nothing changes if the project can't be read.
+pub fn open_project(id: Uuid) -> Result<(), String> {
+    let state = load_project(id)?;
+    let main_dispatch = Dispatch::<MainState>::new();
+    let current = Dispatch::<Projects>::new().get().current;
+    save_project(current, &main_dispatch.get());
+
+    set_open_project(id);
+    main_dispatch.set(state);
+    Dispatch::<CurrentTransfer>::new().set(CurrentTransfer::default());
+    Ok(())
+}
+
+fn new_project(name: &str) -> Uuid {
+    let mut id = Uuid::nil();
+    update_projects(|projects| id = projects.add(name, now()));
+    save_project(id, &MainState::default());
+    id
+}
+
+/// Opens this tab's project at startup (or the latest one in a new tab),
+/// creating the first one if needed.
+fn open_current_project() {
+    let projects = Dispatch::<Projects>::new().get();
+    let open = session_storage()
+        .and_then(|s| s.get_item(OPEN_PROJECT_KEY).ok().flatten())
+        .and_then(|id| Uuid::parse_str(&id).ok())
+        .filter(|id| projects.get(*id).is_some())
+        .or(projects.latest());
+    if let Some(id) = open {
+        set_open_project(id);
+    } else {
+        let id = new_project("Untitled Project");
+        // The state from before there were projects becomes the first one
+        let legacy = local_storage().and_then(|s| s.get_item(LEGACY_STATE_KEY).ok().flatten());
+        if let Some(json) = legacy {
+            match from_json(&json) {
+                Ok(state) => {
+                    if save_project(id, &state) {
+                        if let Some(storage) = local_storage() {
+                            storage.remove_item(LEGACY_STATE_KEY).ok();
+                        }
+                    }
+                }
+                Err(e) => notify(
+                    Level::Error,
+                    format!(
+                        "Your previous work couldn't be read and was left in storage: {}",
+                        e
+                    ),
+                ),
+            }
+        }
+        set_open_project(id);
+    }
+
+    let current = Dispatch::<Projects>::new().get().current;
+    if let Err(e) = load_project(current).map(|state| Dispatch::<MainState>::new().set(state)) {
+        // The project is left as it was stored; work goes into a new one instead
+        let name = projects
+            .get(current)
+            .map(|p| p.name.clone())
+            .unwrap_or_default();
+        let id = new_project("Untitled Project");
+        set_open_project(id);
+        Dispatch::<MainState>::new().set(MainState::default());
+        notify(
+            Level::Error,
+            format!("{} couldn't be opened and was left as it was: {}", name, e),
+        );
+    }
+}
+
+/// Keeps the open project's stored copy and modified time up to date.
+#[hook]
+pub fn use_project_sync() {
+    let (main_state, _) = use_store::<MainState>();
+
+    // Must run before the effect below, which would otherwise save
+    // the empty state over the project before it is loaded
+    use_effect_with_deps(|_| open_current_project(), ());
+
+    use_effect_with_deps(
+        |_| {
+            let id = Dispatch::<Projects>::new().get().current;
+            if save_project(id, &Dispatch::<MainState>::new().get()) {
+                update_projects(|projects| projects.touch(id, now()));
+            }
+        },
+        main_state,
+    );
+
+    // Keeps the list of projects, and other tabs showing the same project, up to date
+    use_effect_with_deps(
+        |_| {
+            let listener = Closure::<dyn Fn(StorageEvent)>::new(|e: StorageEvent| {
+                if e.key().as_deref() == Some(type_name::<Projects>()) {
+                    update_projects(|_| ());
+                    return;
+                }
+                let current = Dispatch::<Projects>::new().get().current;
+                if e.key() != Some(project_key(current)) {
+                    return;
+                }
+                match e.new_value() {
+                    Some(json) => {
+                        if let Ok(state) = from_json(&json) {
+                            Dispatch::<MainState>::new().set(state);
+                        }
+                    }
+                    // Deleted in another tab, but still open in this one
+                    None => {
+                        save_project(current, &Dispatch::<MainState>::new().get());
+                    }
+                }
+            });
+            let window = web_sys::window().unwrap();
+            window
+                .add_event_listener_with_callback("storage", listener.as_ref().unchecked_ref())
+                .ok();
+            move || {
+                window
+                    .remove_event_listener_with_callback(
+                        "storage",
+                        listener.as_ref().unchecked_ref(),
+                    )
+                    .ok();
+            }
+        },
+        (),
+    );
+
+    use_effect_with_deps(
+        |_| {
+            let autosave = Closure::<dyn Fn()>::new(|| take_snapshot("Autosave"));
+            let window = web_sys::window().unwrap();
+            let handle = window
+                .set_interval_with_callback_and_timeout_and_arguments_0(
+                    autosave.as_ref().unchecked_ref(),
+                    AUTOSAVE_INTERVAL,
+                )
+                .ok();
+            move || {
+                if let Some(handle) = handle {
+                    window.clear_interval_with_handle(handle);
+                }
+                drop(autosave);
+            }
+        },
+        (),
+    );
+}
+
+#[derive(PartialEq, Properties)]
+pub struct ProjectManagerDialogProps {
+    pub close_callback: Callback<()>,
+}
+
+#[function_component]
+pub fn ProjectManagerDialog(props: &ProjectManagerDialogProps) -> Html {
+    let (projects, projects_dispatch) = use_store::<Projects>();
+    let (main_state, _) = use_store::<MainState>();
+
+    let on_new = Callback::from(|_: MouseEvent| {
+        let id = new_project("Untitled Project");
+        if let Err(e) = open_project(id) {
+            notify(Level::Error, e);
+        }
+    });
+    let on_rename = |id: Uuid| {
+        Callback::from(move |e: Event| {
+            let input = e
+                .target()
+                .and_then(|t| t.dyn_into::<HtmlInputElement>().ok());
+            if let Some(input) = input {
+                update_projects(|projects| {
+                    projects.rename(id, &input.value());
+                    projects.touch(id, now());
+                });
+            }
+        })
+    };
+    let on_open = |id: Uuid| {
+        Callback::from(move |_: MouseEvent| {
+            if let Err(e) = open_project(id) {
+                notify(Level::Error, format!("Couldn't open the project: {}", e));
+            }
+        })
+    };
+    let on_duplicate = |id: Uuid| {
+        let projects_dispatch = projects_dispatch.clone();
+        let main_state = main_state.clone();
+        Callback::from(move |_: MouseEvent| {
+            let projects = projects_dispatch.get();
+            let state = if id == projects.current {
+                Ok((*main_state).clone())
+            } else {
+                load_project(id)
+            };
+            let state = match state {
+                Ok(state) => state,
+                Err(e) => {
+                    notify(
+                        Level::Error,
+                        format!("Couldn't duplicate the project: {}", e),
+                    );
+                    return;
+                }
+            };
+            if let Some(info) = projects.get(id) {
+                let name = format!("{} (copy)", info.name);
+                let mut copy = Uuid::nil();
+                update_projects(|projects| copy = projects.add(&name, now()));
+                save_project(copy, &state);
+            }
+        })
+    };
+    let on_delete = |id: Uuid| {
+        let projects_dispatch = projects_dispatch.clone();
+        Callback::from(move |_: MouseEvent| {
+            let projects = projects_dispatch.get();
+            let Some(info) = projects.get(id) else {
+                return;
+            };
+            let (plates, transfers) = load_project(id)
+                .ok()
+                .map(|s| {
+                    (
+                        s.source_plates.len() + s.destination_plates.len(),
+                        s.transfers.len(),
+                    )
+                })
+                .unwrap_or_default();
+            confirm(
+                format!("Delete {}?", info.name),
+                format!(
+                    "This will delete the project with its {} plates and {} transfers.",
+                    plates, transfers
+                ),
+                "Delete Project",
+                Callback::from(move |_| {
+                    update_projects(|projects| projects.remove(id));
+                    delete_project(id);
+                }),
+            );
+        })
+    };
+
+    let dialog_ref = use_node_ref();
+    let onclose = {
+        let close_callback = props.close_callback.clone();
+        Callback::from(move |_: Event| {
+            close_callback.emit(());
+        })
+    };
+
+    {
+        let dialog_ref = dialog_ref.clone();
+
+        use_effect_with_deps(
+            |dialog_ref| {
+                dialog_ref
+                    .cast::<HtmlDialogElement>()
+                    .unwrap()
+                    .show_modal()
+                    .ok();
+            },
+            dialog_ref,
+        );
+    }
+
+    // Most recently changed first
+    let mut list = projects.projects.clone();
+    list.sort_by(|a, b| b.modified.total_cmp(&a.modified));
+    let rows = list
+        .iter()
+        .map(|p| {
+            let is_current = p.id == projects.current;
+            html! {
+                <tr class={classes!(is_current.then_some("current"))}>
+                    <td><input type="text" value={p.name.clone()} onchange={on_rename(p.id)}/></td>
+                    <td>{format_time(p.modified)}</td>
+                    <td>
+                    <button onclick={on_open(p.id)} disabled={is_current}>
+                        {if is_current { "Open Now" } else { "Open" }}</button>
+                    <button onclick={on_duplicate(p.id)}>{"Duplicate"}</button>
+                    <button onclick={on_delete(p.id)} disabled={is_current}
+                     title={if is_current { "Open another project to delete this one" } else { "" }}>
+                        {"Delete"}</button>
+                    </td>
+                </tr>
+            }
+        })
+        .collect::<Html>();
+
+    html! {
+        <dialog ref={dialog_ref} class="dialog project_manager_dialog" onclose={onclose}>
+            <h2>{"Projects"}</h2>
+            <p>{"Projects are kept in this browser. Export as JSON to move one elsewhere."}</p>
+            <table>
+                <tr><th>{"Name"}</th><th>{"Last Modified"}</th><th /></tr>
+                { rows }
+            </table>
+            <button onclick={on_new}>{"New Project"}</button>
+            <form class="modal_close" method="dialog"><button /></form>
+        </dialog>
+    }
+}
@@ -0,0 +1,193 @@
+#![allow(non_snake_case)]
+
+use wasm_bindgen::JsCast;
+use web_sys::{HtmlDialogElement, HtmlInputElement};
+use yew::prelude::*;
+use yewdux::prelude::*;
+
+use crate::components::states::MainState;
+use crate::data::randomize::{new_seed, randomized_pairs};
+use crate::data::transfer::Transfer;
+use crate::data::transfer_region::{Region, TransferRegion};
+use crate::data::well_name::parse_well_list;
+
+#[derive(PartialEq, Properties)]
+pub struct RandomizeDialogProps {
+    pub close_callback: Callback<()>,
+}
+
+#[function_component]
+pub fn RandomizeDialog(props: &RandomizeDialogProps) -> Html {
+    let (main_state, main_dispatch) = use_store::<MainState>();
+    let source_text = use_state(String::new);
+    let dest_text = use_state(String::new);
+    let excluded_text = use_state(String::new);
+    let seed = use_state(new_seed);
+
+    let spi = main_state
+        .source_plates
+        .iter()
+        .find(|spi| spi.get_uuid() == main_state.selected_source_plate)
+        .cloned();
+    let dpi = main_state
+        .destination_plates
+        .iter()
+        .find(|dpi| dpi.get_uuid() == main_state.selected_dest_plate)
+        .cloned();
+
+    let pairs = match (&spi, &dpi) {
+        (Some(spi), Some(dpi)) => Some(
+            parse_well_list(&source_text, spi.plate.size())
+                .map_err(|e| format!("Source: {}", e))
+                .and_then(|sources| {
+                    let dests = parse_well_list(&dest_text, dpi.plate.size())
+                        .map_err(|e| format!("Destination: {}", e))?;
+                    let mut excluded = parse_well_list(&excluded_text, dpi.plate.size())
+                        .map_err(|e| format!("Excluded: {}", e))?;
+                    // Wells excluded on the plate itself are never used
+                    excluded.extend_from_slice(&dpi.mask);
+                    randomized_pairs(&sources, &dests, &excluded, *seed)
+                }),
+        ),
+        _ => None,
+    };
+
+    let on_text_change = |handle: &UseStateHandle<String>| {
+        let handle = handle.clone();
+        Callback::from(move |e: Event| {
+            if let Some(input) = e
+                .target()
+                .and_then(|t| t.dyn_into::<HtmlInputElement>().ok())
+            {
+                handle.set(input.value());
+            }
+        })
+    };
+    let on_seed_change = {
+        let seed = seed.clone();
+        Callback::from(move |e: Event| {
+            let input = e
+                .target()
+                .and_then(|t| t.dyn_into::<HtmlInputElement>().ok());
+            if let Some(value) = input.and_then(|i| i.value().parse::<u64>().ok()) {
+                seed.set(value);
+            }
+        })
+    };
+    let on_new_seed = {
+        let seed = seed.clone();
+        Callback::from(move |_: MouseEvent| {
+            seed.set(new_seed());
+        })
+    };
+
+    let dialog_ref = use_node_ref();
+    let on_create = {
+        let pairs = pairs.clone();
+        let seed = *seed;
+        let (spi, dpi) = (spi.clone(), dpi.clone());
+        let dialog_ref = dialog_ref.clone();
+        Callback::from(move |_: MouseEvent| {
+            if let (Some(spi), Some(dpi), Some(Ok(pairs))) = (spi.clone(), dpi.clone(), &pairs) {
+                if pairs.is_empty() {
+                    return;
+                }
+                let custom_region = Region::new_custom(pairs);
+                let transfer_region = TransferRegion {
+                    source_region: custom_region.clone(),
+                    dest_region: custom_region,
+                    interleave_source: (1, 1),
+                    interleave_dest: (1, 1),
+                    fill_order: Default::default(),
+                    source_plate: spi.plate,
+                    dest_plate: dpi.plate,
+                };
+                let mut transfer =
+                    Transfer::new(spi, dpi, transfer_region, format!("Randomized ({})", seed));
+                transfer.seed = Some(seed);
+                main_dispatch.reduce_mut(|state| {
+                    state.transfers.push(transfer);
+                    state.selected_transfer = state
+                        .transfers
+                        .last()
+                        .expect("An element should have just been added")
+                        .get_uuid();
+                });
+                if let Some(dialog) = dialog_ref.cast::<HtmlDialogElement>() {
+                    dialog.close();
+                }
+            }
+        })
+    };
+    let on_cancel = {
+        let dialog_ref = dialog_ref.clone();
+        Callback::from(move |_: MouseEvent| {
+            if let Some(dialog) = dialog_ref.cast::<HtmlDialogElement>() {
+                dialog.close();
+            }
+        })
+    };
+    let onclose = {
+        let close_callback = props.close_callback.clone();
+        Callback::from(move |_: Event| {
+            close_callback.emit(());
+        })
+    };
+
+    {
+        let dialog_ref = dialog_ref.clone();
+
+        use_effect_with_deps(
+            |dialog_ref| {
+                dialog_ref
+                    .cast::<HtmlDialogElement>()
+                    .unwrap()
+                    .show_modal()
+                    .ok();
+            },
+            dialog_ref,
+        );
+    }
+
+    let can_create = matches!(&pairs, Some(Ok(pairs)) if !pairs.is_empty());
+
+    html! {
+        <dialog ref={dialog_ref} class="dialog randomize_dialog" onclose={onclose}>
+            <h2>{"Randomized Layout"}</h2>
+            if let (Some(spi), Some(dpi)) = (&spi, &dpi) {
+                <p>{format!("Scatter wells from {} across {}", spi.name, dpi.name)}</p>
+                <form>
+                    <label>{"Source wells:"}
+                    <input type="text" placeholder="A1:H6" value={(*source_text).clone()}
+                     onchange={on_text_change(&source_text)}/>
+                    </label>
+                    <label>{"Destination wells:"}
+                    <input type="text" placeholder="A1:P24" value={(*dest_text).clone()}
+                     onchange={on_text_change(&dest_text)}/>
+                    </label>
+                    <label>{"Excluded wells (controls):"}
+                    <input type="text" placeholder="A1, P24" value={(*excluded_text).clone()}
+                     onchange={on_text_change(&excluded_text)}/>
+                    </label>
+                    <label>{"Seed:"}
+                    <input type="text" inputmode="numeric" value={seed.to_string()}
+                     onchange={on_seed_change}/>
+                    </label>
+                    <input type="button" value="New Seed" onclick={on_new_seed}/>
+                </form>
+                if let Some(Err(e)) = &pairs {
+                    <p class="validation_error">{e}</p>
+                } else if let Some(Ok(pairs)) = &pairs {
+                    <p>{format!("{} wells will be placed", pairs.len())}</p>
+                }
+                <div>
+                <button onclick={on_create} disabled={!can_create}>{"Create Transfer"}</button>
+                <button onclick={on_cancel}>{"Cancel"}</button>
+                </div>
+            } else {
+                <p>{"Select a source and destination plate first."}</p>
+            }
+            <form class="modal_close" method="dialog"><button /></form>
+        </dialog>
+    }
+}
@@ -0,0 +1,112 @@
+#![allow(non_snake_case)]
+
+use wasm_bindgen::JsCast;
+use web_sys::{HtmlDialogElement, HtmlInputElement};
+use yew::prelude::*;
+use yewdux::prelude::*;
+
+use crate::components::states::MainState;
+use crate::data::estimate::{estimate_state, format_duration, TimingParameters};
+
+#[derive(PartialEq, Properties)]
+pub struct RunEstimateDialogProps {
+    pub close_callback: Callback<()>,
+}
+
+#[function_component]
+pub fn RunEstimateDialog(props: &RunEstimateDialogProps) -> Html {
+    let (main_state, main_dispatch) = use_store::<MainState>();
+    let params = main_state.preferences.timing;
+
+    // Estimate the picklist as it would be exported right now
+    let estimate = estimate_state(&main_state, &params);
+
+    let on_param_change = |set: fn(&mut TimingParameters, f32)| {
+        let main_dispatch = main_dispatch.clone();
+        Callback::from(move |e: Event| {
+            let input = e
+                .target()
+                .and_then(|t| t.dyn_into::<HtmlInputElement>().ok());
+            if let Some(value) = input.and_then(|i| i.value().parse::<f32>().ok()) {
+                if value >= 0.0 {
+                    main_dispatch.reduce_mut(|state| set(&mut state.preferences.timing, value));
+                }
+            }
+        })
+    };
+
+    let dialog_ref = use_node_ref();
+    let onclose = {
+        let close_callback = props.close_callback.clone();
+        Callback::from(move |_: Event| {
+            close_callback.emit(());
+        })
+    };
+
+    {
+        let dialog_ref = dialog_ref.clone();
+
+        use_effect_with_deps(
+            |dialog_ref| {
+                dialog_ref
+                    .cast::<HtmlDialogElement>()
+                    .unwrap()
+                    .show_modal()
+                    .ok();
+            },
+            dialog_ref,
+        );
+    }
+
+    let per_plate = |counts: &std::collections::BTreeMap<String, u64>| {
+        counts
+            .iter()
+            .map(|(name, droplets)| html! { <tr><td>{name}</td><td>{droplets}</td></tr> })
+            .collect::<Html>()
+    };
+    let summary = match &estimate {
+        Ok(estimate) => html! {
+            <>
+            <p>{format!("Estimated run time: {}", format_duration(estimate.total_seconds))}</p>
+            <p>{format!("Plate swaps: {}", estimate.plate_swaps)}</p>
+            <p>{format!("Wells visited: {}", estimate.well_moves)}</p>
+            <p>{format!("Droplets: {}", estimate.droplets)}</p>
+            <table>
+                <tr><th>{"Source Plate"}</th><th>{"Droplets"}</th></tr>
+                { per_plate(&estimate.droplets_per_source) }
+                <tr><th>{"Destination Plate"}</th><th>{"Droplets"}</th></tr>
+                { per_plate(&estimate.droplets_per_destination) }
+            </table>
+            </>
+        },
+        Err(e) => html! { <p class="validation_error">{e}</p> },
+    };
+
+    html! {
+        <dialog ref={dialog_ref} class="dialog run_estimate_dialog" onclose={onclose}>
+            <h2>{"Run Estimate"}</h2>
+            <p>{format!("Using the row order from CSV export ({}).",
+                main_state.preferences.export_ordering.label())}</p>
+            <form>
+                <label>{"Droplet volume (nL):"}
+                <input type="number" min="0" step="any" value={params.droplet_volume.to_string()}
+                 onchange={on_param_change(|p, v| p.droplet_volume = v)}/>
+                </label>
+                <label>{"Seconds per droplet:"}
+                <input type="number" min="0" step="any" value={params.seconds_per_droplet.to_string()}
+                 onchange={on_param_change(|p, v| p.seconds_per_droplet = v)}/>
+                </label>
+                <label>{"Seconds per well move:"}
+                <input type="number" min="0" step="any" value={params.seconds_per_well.to_string()}
+                 onchange={on_param_change(|p, v| p.seconds_per_well = v)}/>
+                </label>
+                <label>{"Seconds per plate swap:"}
+                <input type="number" min="0" step="any" value={params.seconds_per_plate_swap.to_string()}
+                 onchange={on_param_change(|p, v| p.seconds_per_plate_swap = v)}/>
+                </label>
+            </form>
+            { summary }
+            <form class="modal_close" method="dialog"><button /></form>
+        </dialog>
+    }
+}
@@ -0,0 +1,50 @@
+use wasm_bindgen::closure::Closure;
+use wasm_bindgen::JsCast;
+use web_sys::Element;
+use yew::prelude::*;
+
+// Keys typed into a form field belong to the field, not to a shortcut
+fn is_typing(e: &KeyboardEvent) -> bool {
+    e.target()
+        .and_then(|t| t.dyn_into::<Element>().ok())
+        .map(|el| matches!(el.tag_name().as_str(), "INPUT" | "SELECT" | "TEXTAREA"))
+        .unwrap_or(false)
+}
+
+// Shortcuts are off while a dialog is showing
+fn dialog_open() -> bool {
+    web_sys::window()
+        .and_then(|w| w.document())
+        .and_then(|d| d.query_selector("dialog[open]").ok().flatten())
+        .is_some()
+}
+
+/// Calls `handler` for keys pressed anywhere on the page,
+/// except while typing in a field, with a dialog open or with a modifier held.
+#[hook]
+pub fn use_shortcuts(handler: Callback<KeyboardEvent>) {
+    use_effect_with_deps(
+        |handler| {
+            let handler = handler.clone();
+            let listener = Closure::<dyn Fn(KeyboardEvent)>::new(move |e: KeyboardEvent| {
+                if e.ctrl_key() || e.meta_key() || e.alt_key() || is_typing(&e) || dialog_open() {
+                    return;
+                }
+                handler.emit(e);
+            });
+            let window = web_sys::window().unwrap();
+            window
+                .add_event_listener_with_callback("keydown", listener.as_ref().unchecked_ref())
+                .ok();
+            move || {
+                window
+                    .remove_event_listener_with_callback(
+                        "keydown",
+                        listener.as_ref().unchecked_ref(),
+                    )
+                    .ok();
+            }
+        },
+        handler,
+    );
+}
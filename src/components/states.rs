@@ -1,30 +1,58 @@
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use uuid::Uuid;
-use yewdux::{prelude::*, storage};
+use yewdux::prelude::*;
+#[cfg(target_arch = "wasm32")]
+use yewdux::storage;
 
+use crate::data::csv::TransferOrdering;
+use crate::data::estimate::TimingParameters;
+use crate::data::heatmap::{destination_volumes, Overlay};
 use crate::data::plate::*;
-use crate::data::plate_instances::PlateInstance;
+use crate::data::plate_instances::{plate_mask, PlateInstance};
 use crate::data::transfer::Transfer;
+use crate::data::well_name::WellNameStyle;
 
 #[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize, Store)]
 #[store(storage = "session")]
 #[non_exhaustive]
 pub struct CurrentTransfer {
     pub transfer: Transfer,
+    // Source well waiting for a destination when pairing wells by clicking
+    #[serde(default)]
+    pub pending_pair_source: Option<(u8, u8)>,
 }
 
-#[derive(PartialEq, Clone, Copy, Serialize, Deserialize)]
+#[derive(PartialEq, Clone, Copy, Serialize, Deserialize, JsonSchema)]
 pub struct Preferences {
     pub in_transfer_hashes: bool,
+    // Well naming and row ordering used for the last CSV export
+    #[serde(default)]
+    pub export_well_style: WellNameStyle,
+    #[serde(default)]
+    pub export_ordering: TransferOrdering,
+    #[serde(default)]
+    pub timing: TimingParameters,
+    // What the plate wells are colored by
+    #[serde(default)]
+    pub overlay: Overlay,
 }
 
 impl Default for Preferences {
     fn default() -> Self {
-        Self { in_transfer_hashes: true }
+        Self {
+            in_transfer_hashes: true,
+            export_well_style: WellNameStyle::default(),
+            export_ordering: TransferOrdering::default(),
+            timing: TimingParameters::default(),
+            overlay: Overlay::default(),
+        }
     }
 }
 
-#[derive(Default, PartialEq, Clone, Serialize, Deserialize)]
+// Stored per project, see project_manager
+#[derive(Default, PartialEq, Clone, Serialize, Deserialize, JsonSchema, Store)]
 #[non_exhaustive]
 pub struct MainState {
     pub source_plates: Vec<PlateInstance>,
@@ -36,20 +64,8 @@ pub struct MainState {
 
     #[serde(default)]
     pub preferences: Preferences,
-}
-
-impl Store for MainState {
-    fn new() -> Self {
-        init_listener(storage::StorageListener::<Self>::new(storage::Area::Local));
-
-        storage::load(storage::Area::Local)
-            .expect("Unable to load state")
-            .unwrap_or_default()
-    }
-
-    fn should_notify(&self, old: &Self) -> bool {
-        self != old
-    }
+    #[serde(default)]
+    pub custom_formats: Vec<CustomFormat>,
 }
 
 impl MainState {
@@ -74,6 +90,147 @@ impl MainState {
         assert!(plate.plate.plate_type == PlateType::Destination);
         self.destination_plates.push(plate);
     }
+    pub fn add_custom_format(&mut self, format: CustomFormat) {
+        self.custom_formats.push(format);
+    }
+    pub fn get_custom_format(&self, id: Uuid) -> Option<&CustomFormat> {
+        self.custom_formats.iter().find(|cf| cf.get_uuid() == id)
+    }
+    fn custom_format_in_use(&self, id: Uuid) -> bool {
+        self.source_plates
+            .iter()
+            .chain(self.destination_plates.iter())
+            .any(|pi| match pi.plate.plate_format {
+                PlateFormat::Custom { id: cf_id, .. } => cf_id == id,
+                _ => false,
+            })
+    }
+    pub fn update_custom_format(&mut self, format: CustomFormat) -> Result<(), String> {
+        // Plates only carry the format's size, so it can't change under them
+        let in_use = self.custom_format_in_use(format.get_uuid());
+        let cf = self
+            .custom_formats
+            .iter_mut()
+            .find(|cf| cf.get_uuid() == format.get_uuid())
+            .ok_or("Custom format not found")?;
+        if in_use && (cf.rows, cf.columns) != (format.rows, format.columns) {
+            return Err(format!(
+                "\"{}\" is used by a plate, so its size can't be changed",
+                cf.name
+            ));
+        }
+        *cf = format;
+        Ok(())
+    }
+    pub fn del_custom_format(&mut self, id: Uuid) -> Result<(), String> {
+        let index = self
+            .custom_formats
+            .iter()
+            .position(|cf| cf.get_uuid() == id)
+            .ok_or("Custom format not found")?;
+        if self.custom_format_in_use(id) {
+            return Err(format!(
+                "\"{}\" is used by a plate, delete the plate first",
+                self.custom_formats[index].name
+            ));
+        }
+        self.custom_formats.remove(index);
+        Ok(())
+    }
+    fn validate_volume_with(
+        &self,
+        transfer: &Transfer,
+        existing: &HashMap<(u8, u8), f32>,
+    ) -> Result<(), String> {
+        let region = &transfer.transfer_region;
+        let source = region.source_plate.labware(&self.custom_formats)?;
+        let dest = region.dest_plate.labware(&self.custom_formats)?;
+        let mask = self.plate_mask(transfer.dest_id);
+        region.validate_volume(transfer.volume, &source, &dest, existing, mask)?;
+        Ok(())
+    }
+    pub fn validate_volume(&self, transfer: &Transfer) -> Result<(), String> {
+        // Checks against both plates' labware, counting what the other
+        // transfers already put into each destination well
+        let others = self
+            .transfers
+            .iter()
+            .filter(|t| t.get_uuid() != transfer.get_uuid());
+        let existing = destination_volumes(others, &self.destination_plates, transfer.dest_id);
+        self.validate_volume_with(transfer, &existing)
+    }
+    fn validate_transfer_with(
+        &self,
+        transfer: &Transfer,
+        existing: &HashMap<(u8, u8), f32>,
+    ) -> Result<(), String> {
+        // Every check a saved transfer should pass
+        transfer.transfer_region.validate()?;
+        self.validate_volume_with(transfer, existing)?;
+        transfer
+            .transfer_region
+            .validate_mask(self.plate_mask(transfer.dest_id))?;
+        Ok(())
+    }
+    pub fn validate_transfers(&self) -> HashMap<Uuid, Result<(), String>> {
+        // Checks every saved transfer, with each destination plate's
+        // well totals summed once instead of once per transfer
+        let pairs: Vec<_> = self
+            .transfers
+            .iter()
+            .map(|t| {
+                t.transfer_region
+                    .get_transfer_pairs(t.volume, self.plate_mask(t.dest_id))
+            })
+            .collect();
+        let mut totals: HashMap<Uuid, HashMap<(u8, u8), f32>> = HashMap::new();
+        for (transfer, pairs) in self.transfers.iter().zip(pairs.iter()) {
+            let plate = totals.entry(transfer.dest_id).or_default();
+            for (_, d_well, volume) in pairs {
+                *plate.entry(*d_well).or_default() += volume;
+            }
+        }
+        self.transfers
+            .iter()
+            .zip(pairs.iter())
+            .map(|(transfer, pairs)| {
+                // Everything but this transfer's own share
+                let mut existing = totals[&transfer.dest_id].clone();
+                for (_, d_well, volume) in pairs {
+                    if let Some(total) = existing.get_mut(d_well) {
+                        *total -= volume;
+                    }
+                }
+                let status = self.validate_transfer_with(transfer, &existing);
+                (transfer.get_uuid(), status)
+            })
+            .collect()
+    }
+    pub fn plate_mask(&self, id: Uuid) -> &[(u8, u8)] {
+        plate_mask(&self.destination_plates, id)
+    }
+    pub fn repacked_by_mask(&self, id: Uuid, mask: &[(u8, u8)]) -> Vec<&Transfer> {
+        // Saved transfers into the plate whose wells would change with this mask
+        let current = self.plate_mask(id);
+        self.transfers
+            .iter()
+            .filter(|t| t.dest_id == id)
+            .filter(|t| {
+                t.transfer_region.get_transfer_pairs(t.volume, current)
+                    != t.transfer_region.get_transfer_pairs(t.volume, mask)
+            })
+            .collect()
+    }
+    pub fn set_plate_mask(&mut self, id: Uuid, mask: Vec<(u8, u8)>) {
+        // Transfers into the plate are packed around the new mask
+        if let Some(dpi) = self
+            .destination_plates
+            .iter_mut()
+            .find(|dpi| dpi.get_uuid() == id)
+        {
+            dpi.mask = mask;
+        }
+    }
     pub fn del_plate(&mut self, id: Uuid) {
         if let Some(index) = self
             .source_plates
@@ -92,6 +249,81 @@ impl MainState {
             self.purge_transfers();
         }
     }
+    pub fn move_transfer(&mut self, id: Uuid, before: Uuid) {
+        // Transfers are exported in this order
+        if id == before {
+            return;
+        }
+        if let Some(from) = self.transfers.iter().position(|t| t.get_uuid() == id) {
+            let transfer = self.transfers.remove(from);
+            let to = self
+                .transfers
+                .iter()
+                .position(|t| t.get_uuid() == before)
+                .unwrap_or(self.transfers.len());
+            self.transfers.insert(to, transfer);
+        }
+    }
+    pub fn duplicate_transfer(&mut self, id: Uuid) -> Option<Uuid> {
+        // The copy goes right after the original
+        let index = self.transfers.iter().position(|t| t.get_uuid() == id)?;
+        let mut copy = self.transfers[index].clone();
+        copy.id = Uuid::now_v7();
+        copy.name = format!("{} (copy)", copy.name);
+        let new_id = copy.get_uuid();
+        self.transfers.insert(index + 1, copy);
+        Some(new_id)
+    }
+    pub fn move_transfer_to_plates(
+        &mut self,
+        id: Uuid,
+        source_id: Uuid,
+        dest_id: Uuid,
+    ) -> Result<(), String> {
+        // The transfer is left where it was if its regions don't fit the new plates
+        let spi = self
+            .source_plates
+            .iter()
+            .find(|spi| spi.get_uuid() == source_id)
+            .ok_or("The source plate no longer exists")?;
+        let dpi = self
+            .destination_plates
+            .iter()
+            .find(|dpi| dpi.get_uuid() == dest_id)
+            .ok_or("The destination plate no longer exists")?;
+        let transfer = self
+            .transfers
+            .iter_mut()
+            .find(|t| t.get_uuid() == id)
+            .ok_or("The transfer no longer exists")?;
+        let mut region = transfer.transfer_region.clone();
+        region.source_plate = spi.plate;
+        region.dest_plate = dpi.plate;
+        // validate() leaves the destination's bounds to us
+        let dest_size = dpi.plate.size();
+        let check = region.validate().and_then(|_| {
+            let outside = region
+                .get_destination_wells(&[])
+                .iter()
+                .any(|w| w.0 > dest_size.0 || w.1 > dest_size.1);
+            if outside {
+                Err("Destination region is out-of-bounds!")
+            } else {
+                Ok(())
+            }
+        });
+        check.map_err(|e| format!("\"{}\" was not moved: {}", transfer.name, e))?;
+        transfer.source_id = source_id;
+        transfer.dest_id = dest_id;
+        transfer.transfer_region = region;
+        Ok(())
+    }
+    pub fn del_transfers(&mut self, ids: &[Uuid]) {
+        self.transfers.retain(|t| !ids.contains(&t.get_uuid()));
+        if ids.contains(&self.selected_transfer) {
+            self.selected_transfer = Uuid::nil();
+        }
+    }
     pub fn rename_plate(&mut self, id: Uuid, new_name: &str) {
         if let Some(index) = self
             .source_plates
@@ -109,3 +341,388 @@ impl MainState {
         }
     }
 }
+
+#[derive(PartialEq, Clone, Serialize, Deserialize, Debug)]
+pub struct ProjectInfo {
+    pub id: Uuid,
+    pub name: String,
+    // Milliseconds since the epoch, as from Date.now()
+    pub modified: f64,
+}
+
+// The list of projects; each project's MainState is stored separately.
+// Every tab shares the list, but has its own open project.
+#[derive(Default, PartialEq, Clone, Serialize, Deserialize)]
+pub struct Projects {
+    pub projects: Vec<ProjectInfo>,
+    #[serde(skip)]
+    pub current: Uuid,
+}
+
+impl Store for Projects {
+    // Storage only exists in the browser (not for the command line tool)
+    #[cfg(target_arch = "wasm32")]
+    fn new() -> Self {
+        init_listener(storage::StorageListener::<Self>::new(storage::Area::Local));
+
+        storage::load(storage::Area::Local)
+            .expect("Unable to load projects")
+            .unwrap_or_default()
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn new() -> Self {
+        Self::default()
+    }
+
+    fn should_notify(&self, old: &Self) -> bool {
+        self != old
+    }
+}
+
+impl Projects {
+    pub fn add(&mut self, name: &str, now: f64) -> Uuid {
+        let id = Uuid::now_v7();
+        self.projects.push(ProjectInfo {
+            id,
+            name: name.to_string(),
+            modified: now,
+        });
+        id
+    }
+    pub fn get(&self, id: Uuid) -> Option<&ProjectInfo> {
+        self.projects.iter().find(|p| p.id == id)
+    }
+    pub fn latest(&self) -> Option<Uuid> {
+        // The most recently modified project
+        self.projects
+            .iter()
+            .max_by(|a, b| a.modified.total_cmp(&b.modified))
+            .map(|p| p.id)
+    }
+    pub fn merge(&mut self, stored: Projects) {
+        // Takes the list another tab may have changed, which is always the latest.
+        // The project open here is kept, even if another tab deleted it.
+        let open = self.get(self.current).cloned();
+        self.projects = stored.projects;
+        if let Some(open) = open {
+            if self.get(open.id).is_none() {
+                self.projects.push(open);
+            }
+        }
+    }
+    pub fn rename(&mut self, id: Uuid, name: &str) {
+        if let Some(project) = self.projects.iter_mut().find(|p| p.id == id) {
+            project.name = name.to_string();
+        }
+    }
+    pub fn touch(&mut self, id: Uuid, now: f64) {
+        if let Some(project) = self.projects.iter_mut().find(|p| p.id == id) {
+            project.modified = now;
+        }
+    }
+    pub fn remove(&mut self, id: Uuid) {
+        // The open project can't be removed
+        if id != self.current {
+            self.projects.retain(|p| p.id != id);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use wasm_bindgen_test::*;
+
+    use super::*;
+    use crate::data::labware::PlateChemistry;
+    use crate::data::transfer_region::Region;
+
+    fn add_transfer(state: &mut MainState, volume: f32) -> Transfer {
+        let spi = state.source_plates[0].clone();
+        let dpi = state.destination_plates[0].clone();
+        let mut transfer = Transfer::new(
+            spi.clone(),
+            dpi.clone(),
+            Default::default(),
+            "T".to_string(),
+        );
+        transfer.transfer_region.source_plate = spi.plate;
+        transfer.transfer_region.dest_plate = dpi.plate;
+        transfer.transfer_region.source_region = Region::Point((1, 1));
+        transfer.transfer_region.dest_region = Region::Point((1, 1));
+        transfer.volume = volume;
+        state.transfers.push(transfer.clone());
+        transfer
+    }
+
+    fn state_with(source: PlateFormat, dest: PlateFormat) -> MainState {
+        let mut state = MainState::default();
+        state.add_source_plate(PlateInstance::new(
+            PlateType::Source,
+            source,
+            "S".to_string(),
+        ));
+        state.add_dest_plate(PlateInstance::new(
+            PlateType::Destination,
+            dest,
+            "D".to_string(),
+        ));
+        state
+    }
+
+    #[test]
+    #[wasm_bindgen_test]
+    fn test_validate_transfer_totals() {
+        // Two transfers of 200 µL into the same 300 µL well
+        let mut state = state_with(PlateFormat::W96, PlateFormat::W96);
+        let first = add_transfer(&mut state, 200_000.0);
+        assert!(state.validate_transfers()[&first.get_uuid()].is_ok());
+        let second = add_transfer(&mut state, 200_000.0);
+        assert!(state.validate_transfers()[&first.get_uuid()].is_err());
+        assert!(state.validate_transfers()[&second.get_uuid()].is_err());
+
+        // Moved to another plate, neither overfills a well
+        state.add_dest_plate(PlateInstance::new(
+            PlateType::Destination,
+            PlateFormat::W96,
+            "D2".to_string(),
+        ));
+        state.transfers[1].dest_id = state.destination_plates[1].get_uuid();
+        let statuses = state.validate_transfers();
+        assert!(statuses[&first.get_uuid()].is_ok());
+        assert!(statuses[&second.get_uuid()].is_ok());
+    }
+
+    #[test]
+    #[wasm_bindgen_test]
+    fn test_validate_transfer_custom_format() {
+        let format = CustomFormat::new("Tubes".to_string(), 4, 6, 50.0, 5.0, String::new());
+        let mut state = state_with(PlateFormat::W96, format.format());
+        state.add_custom_format(format);
+        let transfer = add_transfer(&mut state, 40_000.0);
+        assert!(state.validate_transfers()[&transfer.get_uuid()].is_ok());
+        state.transfers[0].volume = 60_000.0;
+        assert!(state.validate_transfers()[&state.transfers[0].get_uuid()].is_err());
+
+        // The definition has gone missing
+        state.custom_formats.clear();
+        let err = state.validate_transfers()[&state.transfers[0].get_uuid()]
+            .clone()
+            .unwrap_err();
+        assert!(err.contains("Unknown labware"), "{}", err);
+    }
+
+    #[test]
+    #[wasm_bindgen_test]
+    fn test_plate_mask() {
+        // A1:A2 into A1, and an unrelated transfer into H12
+        let mut state = state_with(PlateFormat::W96, PlateFormat::W96);
+        let moved = add_transfer(&mut state, 2.5);
+        state.transfers[0].transfer_region.source_region = Region::Rect((1, 1), (1, 2));
+        let kept = add_transfer(&mut state, 2.5);
+        state.transfers[1].transfer_region.dest_region = Region::Point((8, 12));
+        let dest_id = state.destination_plates[0].get_uuid();
+
+        let repacked = state.repacked_by_mask(dest_id, &[(1, 2)]);
+        assert_eq!(repacked.len(), 1);
+        assert_eq!(repacked[0].get_uuid(), moved.get_uuid());
+        assert!(state.repacked_by_mask(dest_id, &[(5, 5)]).is_empty());
+
+        // Saved transfers are left as they were, only the plate changes
+        let transfers = state.transfers.clone();
+        state.set_plate_mask(dest_id, vec![(1, 2)]);
+        assert_eq!(state.transfers, transfers);
+        assert_eq!(state.plate_mask(dest_id), &[(1, 2)]);
+        assert!(state.plate_mask(kept.get_uuid()).is_empty());
+        assert!(state.validate_transfers()[&state.transfers[0].get_uuid()].is_ok());
+    }
+
+    #[test]
+    #[wasm_bindgen_test]
+    fn test_move_transfer() {
+        let mut state = state_with(PlateFormat::W96, PlateFormat::W96);
+        let ids: Vec<Uuid> = (0..3)
+            .map(|_| add_transfer(&mut state, 2.5).get_uuid())
+            .collect();
+        let order = |state: &MainState| -> Vec<Uuid> {
+            state.transfers.iter().map(|t| t.get_uuid()).collect()
+        };
+
+        state.move_transfer(ids[1], ids[1]);
+        assert_eq!(order(&state), ids, "Moving before itself changes nothing");
+        state.move_transfer(ids[2], ids[0]);
+        assert_eq!(order(&state), vec![ids[2], ids[0], ids[1]]);
+        // Anything not in the list moves it to the end
+        state.move_transfer(ids[2], Uuid::nil());
+        assert_eq!(order(&state), ids);
+    }
+
+    #[test]
+    #[wasm_bindgen_test]
+    fn test_duplicate_transfer() {
+        let mut state = state_with(PlateFormat::W96, PlateFormat::W96);
+        let first = add_transfer(&mut state, 2.5);
+        let last = add_transfer(&mut state, 2.5);
+        let copy = state.duplicate_transfer(first.get_uuid()).unwrap();
+        assert_ne!(copy, first.get_uuid());
+        assert_eq!(state.transfers.len(), 3);
+        assert_eq!(
+            state.transfers[1].get_uuid(),
+            copy,
+            "The copy follows the original"
+        );
+        assert_eq!(state.transfers[1].name, "T (copy)");
+        assert_eq!(state.transfers[1].transfer_region, first.transfer_region);
+        assert_eq!(state.transfers[2].get_uuid(), last.get_uuid());
+        assert!(state.duplicate_transfer(Uuid::nil()).is_none());
+    }
+
+    #[test]
+    #[wasm_bindgen_test]
+    fn test_del_transfers() {
+        let mut state = state_with(PlateFormat::W96, PlateFormat::W96);
+        let kept = add_transfer(&mut state, 2.5);
+        let deleted = add_transfer(&mut state, 2.5);
+        state.selected_transfer = kept.get_uuid();
+        state.del_transfers(&[deleted.get_uuid()]);
+        assert_eq!(state.transfers.len(), 1);
+        assert_eq!(state.selected_transfer, kept.get_uuid());
+        state.del_transfers(&[kept.get_uuid()]);
+        assert!(state.transfers.is_empty());
+        assert!(state.selected_transfer.is_nil());
+    }
+
+    #[test]
+    #[wasm_bindgen_test]
+    fn test_move_transfer_to_plates() {
+        // 384 well regions can't go onto 96 well plates
+        let mut state = state_with(PlateFormat::W384, PlateFormat::W384);
+        let transfer = add_transfer(&mut state, 2.5);
+        state.transfers[0].transfer_region.source_region = Region::Rect((1, 1), (16, 1));
+        state.transfers[0].transfer_region.dest_region = Region::Rect((1, 1), (16, 1));
+        state.add_source_plate(PlateInstance::new(
+            PlateType::Source,
+            PlateFormat::W96,
+            "S96".to_string(),
+        ));
+        state.add_dest_plate(PlateInstance::new(
+            PlateType::Destination,
+            PlateFormat::W96,
+            "D96".to_string(),
+        ));
+        let (source_id, dest_id) = (transfer.source_id, transfer.dest_id);
+        let small_source = state.source_plates[1].get_uuid();
+        let small_dest = state.destination_plates[1].get_uuid();
+
+        let before = state.transfers[0].clone();
+        for (s, d) in [(small_source, dest_id), (source_id, small_dest)] {
+            assert!(state
+                .move_transfer_to_plates(transfer.get_uuid(), s, d)
+                .is_err());
+            assert_eq!(state.transfers[0], before, "A failed move changes nothing");
+        }
+
+        state.transfers[0].transfer_region.source_region = Region::Rect((1, 1), (8, 1));
+        state.transfers[0].transfer_region.dest_region = Region::Point((1, 1));
+        state
+            .move_transfer_to_plates(transfer.get_uuid(), small_source, small_dest)
+            .unwrap();
+        assert_eq!(state.transfers[0].dest_id, small_dest);
+        assert_eq!(
+            state.transfers[0].transfer_region.dest_plate.plate_format,
+            PlateFormat::W96
+        );
+    }
+
+    #[test]
+    #[wasm_bindgen_test]
+    fn test_validate_transfer_unknown_labware() {
+        // There is no 6 well LDV plate
+        let mut state = state_with(PlateFormat::W6, PlateFormat::W96);
+        state.source_plates[0].plate.chemistry = PlateChemistry::LDV;
+        let transfer = add_transfer(&mut state, 2.5);
+        let err = state.validate_transfers()[&transfer.get_uuid()]
+            .clone()
+            .unwrap_err();
+        assert!(err.contains("Unknown labware"), "{}", err);
+    }
+
+    #[test]
+    #[wasm_bindgen_test]
+    fn test_custom_formats() {
+        let mut state = MainState::default();
+        let rack = CustomFormat::new("Rack".to_string(), 8, 3, 1500.0, 50.0, "TR24".to_string());
+        let reservoir = CustomFormat::new("Trough".to_string(), 1, 1, 5e4, 2e3, String::new());
+        let rack_id = rack.get_uuid();
+        state.add_custom_format(rack.clone());
+        state.add_custom_format(reservoir.clone());
+        assert_eq!(state.custom_formats.len(), 2);
+        assert_eq!(state.get_custom_format(rack_id), Some(&rack));
+
+        // Unused formats can be edited freely
+        let mut edited = rack.clone();
+        edited.name = "Tube rack".to_string();
+        edited.rows = 4;
+        state.update_custom_format(edited.clone()).unwrap();
+        assert_eq!(state.get_custom_format(rack_id), Some(&edited));
+
+        // Once a plate uses it, only its size is fixed
+        state.add_source_plate(PlateInstance::new(
+            PlateType::Source,
+            edited.format(),
+            "S".to_string(),
+        ));
+        edited.rows = 8;
+        assert!(state.update_custom_format(edited.clone()).is_err());
+        edited.rows = 4;
+        edited.well_volume = 1000.0;
+        state.update_custom_format(edited.clone()).unwrap();
+        assert_eq!(state.get_custom_format(rack_id), Some(&edited));
+
+        assert!(state.del_custom_format(rack_id).is_err());
+        state.del_custom_format(reservoir.get_uuid()).unwrap();
+        assert_eq!(state.custom_formats, vec![edited]);
+        assert!(state.del_custom_format(reservoir.get_uuid()).is_err());
+        state.del_plate(state.source_plates[0].get_uuid());
+        state.del_custom_format(rack_id).unwrap();
+        assert!(state.custom_formats.is_empty());
+    }
+
+    #[test]
+    #[wasm_bindgen_test]
+    fn test_merge_projects() {
+        // Two tabs start from the same list, then each makes its own changes
+        let mut stored = Projects::default();
+        let first = stored.add("First", 1.0);
+        let second = stored.add("Second", 2.0);
+        let mut tab_a = stored.clone();
+        tab_a.current = first;
+        let mut tab_b = stored.clone();
+        tab_b.current = second;
+
+        let third = tab_a.add("Third", 3.0);
+        tab_a.rename(first, "Renamed");
+        tab_a.remove(second);
+        let stored = Projects {
+            current: Uuid::nil(),
+            ..tab_a.clone()
+        };
+
+        // Tab B's stale list is replaced before its own change
+        tab_b.merge(stored.clone());
+        tab_b.touch(second, 4.0);
+        assert_eq!(tab_b.current, second, "Each tab keeps its open project");
+        assert_eq!(tab_b.get(first).unwrap().name, "Renamed");
+        assert!(tab_b.get(third).is_some());
+        // Deleted in tab A while open in tab B, so it stays
+        assert_eq!(tab_b.get(second).unwrap().modified, 4.0);
+        assert_eq!(tab_b.latest(), Some(second));
+
+        // Without it open, a deleted project stays deleted
+        tab_b.current = first;
+        tab_b.merge(stored);
+        assert!(tab_b.get(second).is_none());
+        assert_eq!(tab_b.projects.len(), 2);
+        assert_eq!(tab_b.latest(), Some(third));
+    }
+}
@@ -1,16 +1,21 @@
 #![allow(non_snake_case)]
 
-use lazy_static::lazy_static;
-use regex::Regex;
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 use wasm_bindgen::JsCast;
-use web_sys::{EventTarget, HtmlInputElement};
+use web_sys::{EventTarget, HtmlInputElement, HtmlSelectElement};
 use yew::prelude::*;
 use yewdux::prelude::*;
 
-use crate::data::{transfer::Transfer, transfer_region::Region};
+use crate::data::{
+    transfer::Transfer,
+    transfer_region::{Corners, FillOrder, Region},
+    well_name::{format_well, parse_well, parse_well_name, WellNameStyle},
+};
+// Re-exported for the components that import them from here
+pub use crate::data::well_name::{letters_to_num, num_to_letters};
 
+use super::shortcuts::use_shortcuts;
 use super::states::{CurrentTransfer, MainState};
 
 #[function_component]
@@ -39,7 +44,10 @@ pub fn TransferMenu() -> Html {
         let ct_dispatch = ct_dispatch.clone();
 
         Callback::from(move |e: Event| {
-            if matches!(ct_dispatch.get().transfer.transfer_region.source_region, Region::Custom(_)) {
+            if matches!(
+                ct_dispatch.get().transfer.transfer_region.source_region,
+                Region::Custom(_)
+            ) {
                 return; // Do nothing here!
             }
             let target: Option<EventTarget> = e.target();
@@ -157,6 +165,182 @@ pub fn TransferMenu() -> Html {
         })
     };
 
+    let on_pooling_change = {
+        let ct_dispatch = ct_dispatch.clone();
+
+        Callback::from(move |e: Event| {
+            let input = e
+                .target()
+                .expect("Event must have target")
+                .dyn_into::<HtmlInputElement>()
+                .expect("Must have been emitted by input");
+            ct_dispatch.reduce_mut(|state| {
+                state.transfer.pooling_allowed = input.checked();
+            });
+        })
+    };
+
+    let on_fill_order_change = {
+        let ct_dispatch = ct_dispatch.clone();
+
+        Callback::from(move |e: Event| {
+            let select = e
+                .target()
+                .and_then(|t| t.dyn_into::<HtmlSelectElement>().ok());
+            if let Some(select) = select {
+                if let Some(order) = select
+                    .value()
+                    .parse::<usize>()
+                    .ok()
+                    .and_then(|i| FillOrder::ALL.get(i))
+                {
+                    ct_dispatch.reduce_mut(|state| {
+                        state.transfer.transfer_region.fill_order = *order;
+                    });
+                }
+            }
+        })
+    };
+
+    let on_custom_well_change = {
+        let ct_dispatch = ct_dispatch.clone();
+
+        Callback::from(move |(index, is_source, e): (usize, bool, Event)| {
+            let target: Option<EventTarget> = e.target();
+            let input = target.and_then(|t| t.dyn_into::<HtmlInputElement>().ok());
+            if let Some(input) = input {
+                let tr = ct_dispatch.get().transfer.transfer_region.clone();
+                let size = if is_source {
+                    tr.source_plate.size()
+                } else {
+                    tr.dest_plate.size()
+                };
+                if let Ok(well) = parse_well(&input.value(), size) {
+                    ct_dispatch.reduce_mut(|state| {
+                        state.transfer.transfer_region.edit_custom(|c| {
+                            if is_source {
+                                c.set_source(index, well)
+                            } else {
+                                c.set_destination(index, well)
+                            }
+                        });
+                    });
+                    input.set_custom_validity("");
+                } else {
+                    input.set_custom_validity("Invalid well.")
+                }
+            }
+        })
+    };
+    let on_custom_volume_change = {
+        let ct_dispatch = ct_dispatch.clone();
+
+        Callback::from(move |(index, e): (usize, Event)| {
+            let target: Option<EventTarget> = e.target();
+            let input = target.and_then(|t| t.dyn_into::<HtmlInputElement>().ok());
+            if let Some(input) = input {
+                // An empty field falls back to the transfer's volume
+                let volume = input.value().parse::<f32>().ok();
+                ct_dispatch.reduce_mut(|state| {
+                    state
+                        .transfer
+                        .transfer_region
+                        .edit_custom(|c| c.set_volume(index, volume));
+                });
+            }
+        })
+    };
+    let on_custom_pair_remove = {
+        let ct_dispatch = ct_dispatch.clone();
+
+        Callback::from(move |index: usize| {
+            ct_dispatch.reduce_mut(|state| {
+                state
+                    .transfer
+                    .transfer_region
+                    .edit_custom(|c| c.remove(index));
+            });
+        })
+    };
+    let on_custom_pair_add = {
+        let ct_dispatch = ct_dispatch.clone();
+
+        Callback::from(move |_: MouseEvent| {
+            ct_dispatch.reduce_mut(|state| {
+                state
+                    .transfer
+                    .transfer_region
+                    .edit_custom(|c| c.push((1, 1), (1, 1)));
+            });
+        })
+    };
+    let on_make_custom = {
+        let ct_dispatch = ct_dispatch.clone();
+        let mask = main_state.plate_mask(ct_state.transfer.dest_id).to_vec();
+
+        Callback::from(move |_: MouseEvent| {
+            ct_dispatch.reduce_mut(|state| {
+                let custom = state.transfer.transfer_region.to_custom(&mask);
+                state.transfer.transfer_region.source_region = custom.clone();
+                state.transfer.transfer_region.dest_region = custom;
+            });
+        })
+    };
+
+    let source_size = ct_state.transfer.transfer_region.source_plate.size();
+    let dest_size = ct_state.transfer.transfer_region.dest_plate.size();
+    let custom_pairs = match &ct_state.transfer.transfer_region.source_region {
+        Region::Custom(c) => c
+            .pairs()
+            .into_iter()
+            .enumerate()
+            .map(|(index, (src, dest))| {
+                let on_src_change = on_custom_well_change.reform(move |e| (index, true, e));
+                let on_dest_change = on_custom_well_change.reform(move |e| (index, false, e));
+                let on_volume_change = on_custom_volume_change.reform(move |e| (index, e));
+                let on_remove = on_custom_pair_remove.reform(move |_: MouseEvent| index);
+                html! {
+                    <tr>
+                        <td><input type="text" onchange={on_src_change}
+                        value={format_well(src, source_size, WellNameStyle::Letters)}/></td>
+                        <td><input type="text" onchange={on_dest_change}
+                        value={format_well(dest, dest_size, WellNameStyle::Letters)}/></td>
+                        <td><input type="number" class="volume_input" min="0" step="0.1"
+                        placeholder={ct_state.transfer.volume.to_string()}
+                        onchange={on_volume_change}
+                        value={c.volume(index).map(|v| v.to_string()).unwrap_or_default()}/></td>
+                        <td><input type="button" value={"Remove"} onclick={on_remove}/></td>
+                    </tr>
+                }
+            })
+            .collect::<Html>(),
+        _ => html! {},
+    };
+
+    let duplicate_transfer_button_callback = {
+        let main_dispatch = main_dispatch.clone();
+        let ct_dispatch = ct_dispatch.clone();
+        let selected = main_state.selected_transfer;
+
+        Callback::from(move |_: MouseEvent| {
+            main_dispatch.reduce_mut(|state| {
+                if let Some(id) = state.duplicate_transfer(selected) {
+                    state.selected_transfer = id;
+                }
+            });
+            let state = main_dispatch.get();
+            if let Some(copy) = state
+                .transfers
+                .iter()
+                .find(|t| t.get_uuid() == state.selected_transfer)
+            {
+                ct_dispatch.reduce_mut(|ct| ct.transfer = copy.clone());
+            }
+        })
+    };
+
+    let has_selected_transfer = !main_state.selected_transfer.is_nil();
+
     let new_transfer_button_callback = {
         let main_dispatch = main_dispatch.clone();
         let main_state = main_state.clone();
@@ -167,6 +351,7 @@ pub fn TransferMenu() -> Html {
             });
             ct_dispatch.reduce_mut(|state| {
                 state.transfer = Transfer::default();
+                state.pending_pair_source = None;
                 state.transfer.source_id = main_state.selected_source_plate;
                 state.transfer.dest_id = main_state.selected_dest_plate;
             });
@@ -192,12 +377,14 @@ pub fn TransferMenu() -> Html {
                         .iter()
                         .find(|dpi| dpi.get_uuid() == main_state.selected_dest_plate)
                     {
-                        let new_transfer = Transfer::new(
+                        let mut new_transfer = Transfer::new(
                             spi.clone(),
                             dpi.clone(),
                             ct_state.transfer.transfer_region.clone(),
                             ct_state.transfer.name.clone(),
                         );
+                        new_transfer.volume = ct_state.transfer.volume;
+                        new_transfer.pooling_allowed = ct_state.transfer.pooling_allowed;
                         main_dispatch.reduce_mut(|state| {
                             state.transfers.push(new_transfer);
                             state.selected_transfer = state
@@ -224,6 +411,7 @@ pub fn TransferMenu() -> Html {
     };
 
     let delete_transfer_button_callback = {
+        let main_state = main_state.clone();
         let ct_state = ct_state.clone();
         let new_callback = new_transfer_button_callback.clone();
 
@@ -244,6 +432,25 @@ pub fn TransferMenu() -> Html {
         })
     };
 
+    // n, s and Delete do the same as the New, Save and Delete buttons
+    {
+        let new_callback = new_transfer_button_callback.clone();
+        let save_callback = save_transfer_button_callback.clone();
+        let delete_callback = delete_transfer_button_callback.clone();
+        use_shortcuts(Callback::from(move |e: KeyboardEvent| {
+            let callback = match e.key().as_str() {
+                "n" => &new_callback,
+                "s" => &save_callback,
+                "Delete" if has_selected_transfer => &delete_callback,
+                _ => return,
+            };
+            e.prevent_default();
+            if let Ok(click) = MouseEvent::new("click") {
+                callback.emit(click);
+            }
+        }));
+    }
+
     html! {
         <div class="transfer_menu">
             <form>
@@ -252,9 +459,22 @@ pub fn TransferMenu() -> Html {
                 <input type="text" name="name"
                 onchange={on_name_change}
                 value={ct_state.transfer.name.clone()}/>
+                if let Some(seed) = ct_state.transfer.seed {
+                    <p>{format!("Randomized with seed {}", seed)}</p>
+                }
+            </div>
+            // Custom transfers are edited as a list of well pairs instead
+            if let Region::Custom(_) = &ct_state.transfer.transfer_region.source_region {
+            <div class="custom_region">
+                <h3>{"Wells:"}</h3>
+                <table>
+                    <tr><th>{"Source"}</th><th>{"Destination"}</th><th>{"Volume"}</th><th /></tr>
+                    { custom_pairs }
+                </table>
+                <input type="button" name="add_pair" onclick={on_custom_pair_add}
+                value={"Add Pair"} />
             </div>
-            // Anything below here is not rendered when a Custom transfer is selected
-            if !matches!(&ct_state.transfer.transfer_region.source_region, Region::Custom(_)) {
+            } else {
             <div>
                 <label for="src_region"><h3>{"Source Region:"}</h3></label>
                 <input type="text" name="src_region"
@@ -290,18 +510,55 @@ pub fn TransferMenu() -> Html {
             value={ct_state.transfer.transfer_region.interleave_dest.1.to_string()}/>
             </div>
             <div>
+            <label for="fill_order"><h3>{"Fill Order"}</h3></label>
+            <select name="fill_order" onchange={on_fill_order_change}>
+            { FillOrder::ALL.iter().enumerate().map(|(i, order)| html! {
+                <option value={i.to_string()}
+                selected={*order == ct_state.transfer.transfer_region.fill_order}>
+                    {order.label()}
+                </option>
+            }).collect::<Html>() }
+            </select>
+            if let Err(msg) = ct_state.transfer.transfer_region.validate() {
+                <p class="validation_error">{msg}</p>
+            }
+            </div>
+            <div>
+            <input type="button" name="make_custom" onclick={on_make_custom}
+            value={"Convert to Custom"} />
+            </div>
+            }
+            <div>
             <label for="volume"><h3>{"Volume"}</h3></label>
             <input type="number" name="volume" class="volume_input"
             min="0" step="0.1"
             onchange={on_volume_change}
             value={ct_state.transfer.volume.to_string()}/>
-            </div>
+            if let Err(msg) = main_state.validate_volume(&ct_state.transfer) {
+                <p class="validation_error">{msg}</p>
             }
+            if let Err(msg) = ct_state
+                .transfer
+                .transfer_region
+                .validate_mask(main_state.plate_mask(ct_state.transfer.dest_id))
+            {
+                <p class="validation_error">{msg}</p>
+            }
+            </div>
+            <div>
+            <label for="pooling_allowed">{"Pooling allowed"}</label>
+            <input type="checkbox" name="pooling_allowed"
+            onchange={on_pooling_change}
+            checked={ct_state.transfer.pooling_allowed}/>
+            </div>
             <div id="controls">
             <input type="button" name="new_transfer" onclick={new_transfer_button_callback}
             value={"New"} />
             <input type="button" name="save_transfer" onclick={save_transfer_button_callback}
             value={"Save"} />
+            <input type="button" name="duplicate_transfer" onclick={duplicate_transfer_button_callback}
+            disabled={!has_selected_transfer}
+            value={"Duplicate"} />
             <input type="button" name="delete_transfer" onclick={delete_transfer_button_callback}
             value={"Delete"} />
             </div>
@@ -317,68 +574,44 @@ pub struct RegionDisplay {
     pub row_start: u8,
     pub col_end: u8,
     pub row_end: u8,
+    // Every rectangle when more than one was given; the fields above hold the first
+    #[serde(default)]
+    pub rects: Vec<Corners>,
 }
 
 impl TryFrom<String> for RegionDisplay {
     type Error = &'static str;
 
     fn try_from(value: String) -> Result<Self, Self::Error> {
-        lazy_static! {
-            static ref REGION_REGEX: Regex = Regex::new(r"([A-Z]+)(\d+):([A-Z]+)(\d+)").unwrap();
-        }
-        if let Some(captures) = REGION_REGEX.captures(&value) {
-            if captures.len() != 5 {
-                return Err("Not enough capture groups");
-            }
-            let col_start = letters_to_num(&captures[1]).ok_or("Column start failed to parse")?;
-            let col_end = letters_to_num(&captures[3]).ok_or("Column end failed to parse")?;
-            let row_start: u8 = captures[2]
-                .parse::<u8>()
-                .or(Err("Row start failed to parse"))?;
-            let row_end: u8 = captures[4]
-                .parse::<u8>()
-                .or(Err("Row end failed to parse"))?;
-            Ok(RegionDisplay {
-                text: value,
-                col_start,
-                row_start,
-                col_end,
-                row_end,
-            })
-        } else {
-            Err("Regex match failed")
-        }
+        RegionDisplay::try_from(value.as_str())
     }
 }
 impl TryFrom<&str> for RegionDisplay {
     type Error = &'static str;
 
     fn try_from(value: &str) -> Result<Self, Self::Error> {
-        lazy_static! {
-            static ref REGION_REGEX: Regex = Regex::new(r"([A-Z]+)(\d+):([A-Z]+)(\d+)").unwrap();
+        // Accepts "A1:E5", "A01:E05", or a single well,
+        // or several of those separated by commas ("A1:B6, D1:D12, F3")
+        let mut rects = Vec::<Corners>::new();
+        for part in value.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+            let (start, end) = part.split_once(':').unwrap_or((part, part));
+            let start = parse_well_name(start).ok_or("Region start failed to parse")?;
+            let end = parse_well_name(end).ok_or("Region end failed to parse")?;
+            rects.push((start, end));
         }
-        if let Some(captures) = REGION_REGEX.captures(&value) {
-            if captures.len() != 5 {
-                return Err("Not enough capture groups");
-            }
-            let col_start = letters_to_num(&captures[1]).ok_or("Column start failed to parse")?;
-            let col_end = letters_to_num(&captures[3]).ok_or("Column end failed to parse")?;
-            let row_start: u8 = captures[2]
-                .parse::<u8>()
-                .or(Err("Row start failed to parse"))?;
-            let row_end: u8 = captures[4]
-                .parse::<u8>()
-                .or(Err("Row end failed to parse"))?;
-            Ok(RegionDisplay {
-                text: value.to_string(),
-                col_start,
-                row_start,
-                col_end,
-                row_end,
-            })
-        } else {
-            Err("Regex match failed")
+        let ((col_start, row_start), (col_end, row_end)) =
+            *rects.first().ok_or("Region is empty")?;
+        if rects.len() == 1 {
+            rects.clear();
         }
+        Ok(RegionDisplay {
+            text: value.to_string(),
+            col_start,
+            row_start,
+            col_end,
+            row_end,
+            rects,
+        })
     }
 }
 impl From<&Region> for RegionDisplay {
@@ -390,13 +623,40 @@ impl From<&Region> for RegionDisplay {
             Region::Rect(c1, c2) => RegionDisplay::try_from((c1.0, c1.1, c2.0, c2.1))
                 .ok()
                 .unwrap(),
-            Region::Custom(_) => RegionDisplay { text: "CUSTOM".to_string(), col_start: 0, row_start: 0, col_end: 0, row_end: 0 }
+            Region::Custom(_) => RegionDisplay {
+                text: "CUSTOM".to_string(),
+                ..Default::default()
+            },
+            Region::Multi(ref rects) => {
+                let parts: Vec<RegionDisplay> = rects
+                    .iter()
+                    .filter_map(|(c1, c2)| RegionDisplay::try_from((c1.0, c1.1, c2.0, c2.1)).ok())
+                    .collect();
+                let text = parts
+                    .iter()
+                    .map(|p| {
+                        if p.col_start == p.col_end && p.row_start == p.row_end {
+                            p.text.split(':').next().unwrap_or_default().to_string()
+                        } else {
+                            p.text.clone()
+                        }
+                    })
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                RegionDisplay {
+                    text,
+                    rects: rects.clone(),
+                    ..parts.into_iter().next().unwrap_or_default()
+                }
+            }
         }
     }
 }
 impl From<&RegionDisplay> for Region {
     fn from(value: &RegionDisplay) -> Self {
-        if value.col_start == value.col_end && value.row_start == value.row_end {
+        if value.rects.len() > 1 {
+            Region::Multi(value.rects.clone())
+        } else if value.col_start == value.col_end && value.row_start == value.row_end {
             Region::Point((value.col_start, value.row_start))
         } else {
             Region::Rect(
@@ -420,93 +680,64 @@ impl TryFrom<(u8, u8, u8, u8)> for RegionDisplay {
             row_start: value.1,
             col_end: value.2,
             row_end: value.3,
+            rects: vec![],
         })
     }
 }
-pub fn letters_to_num(letters: &str) -> Option<u8> {
-    let mut num: u8 = 0;
-    for (i, letter) in letters.to_ascii_uppercase().chars().rev().enumerate() {
-        log::debug!("{}, {}", i, letter);
-        let n = letter as u8;
-        if !(65..=90).contains(&n) {
-            return None;
-        }
-        num = num.checked_add((26_i32.pow(i as u32) * (n as i32 - 64)).try_into().ok()?)?;
-    }
-    Some(num)
-}
-pub fn num_to_letters(num: u8) -> Option<String> {
-    if num == 0 {
-        return None;
-    } // Otherwise, we will not return none!
-      // As another note, we can't represent higher than "IV" anyway;
-      // thus there's no reason for a loop (26^n with n>1 will NOT occur).
-    let mut text = "".to_string();
-    let mut digit1 = num.div_euclid(26u8);
-    let mut digit2 = num.rem_euclid(26u8);
-    if digit1 > 0 && digit2 == 0u8 {
-        digit1 -= 1;
-        digit2 = 26;
-    }
-    if digit1 != 0 {
-        text.push((64 + digit1) as char)
-    }
-    text.push((64 + digit2) as char);
-
-    Some(text.to_string())
-}
 
 #[cfg(test)]
 mod tests {
     use wasm_bindgen_test::*;
 
-    use super::{letters_to_num, num_to_letters, RegionDisplay};
+    use super::RegionDisplay;
+    use crate::data::transfer_region::Region;
 
     #[test]
     #[wasm_bindgen_test]
-    fn test_letters_to_num() {
-        assert_eq!(letters_to_num("D"), Some(4));
-        assert_eq!(letters_to_num("d"), None);
-        assert_eq!(letters_to_num("AD"), Some(26 + 4));
-        assert_eq!(letters_to_num("CG"), Some(3 * 26 + 7));
+    fn test_try_from_string_for_regiondisplay() {
+        let desired = RegionDisplay {
+            text: "A1:E5".to_string(),
+            row_start: 1,
+            row_end: 5,
+            col_start: 1,
+            col_end: 5,
+            rects: vec![],
+        };
+        assert_eq!(desired, "A1:E5".to_string().try_into().unwrap());
     }
 
     #[test]
     #[wasm_bindgen_test]
-    fn test_num_to_letters() {
-        println!("27 is {:?}", num_to_letters(27));
-        assert_eq!(num_to_letters(1), Some("A".to_string()));
-        assert_eq!(num_to_letters(26), Some("Z".to_string()));
-        assert_eq!(num_to_letters(27), Some("AA".to_string()));
-        assert_eq!(num_to_letters(111), Some("DG".to_string()));
+    fn test_try_from_padded_string_for_regiondisplay() {
+        let desired = RegionDisplay {
+            text: "B02:P24".to_string(),
+            row_start: 2,
+            row_end: 24,
+            col_start: 2,
+            col_end: 16,
+            rects: vec![],
+        };
+        assert_eq!(desired, "B02:P24".try_into().unwrap());
+
+        let point: RegionDisplay = "C7".try_into().unwrap();
+        assert_eq!((point.col_start, point.row_start), (3, 7));
+        assert_eq!((point.col_end, point.row_end), (3, 7));
     }
 
     #[test]
     #[wasm_bindgen_test]
-    fn test_l2n_and_n2l() {
-        assert_eq!(
-            num_to_letters(letters_to_num("A").unwrap()),
-            Some("A".to_string())
-        );
+    fn test_try_from_multi_string_for_regiondisplay() {
+        let multi: RegionDisplay = "A1:B6, D1:D12, F3".try_into().unwrap();
         assert_eq!(
-            num_to_letters(letters_to_num("BJ").unwrap()),
-            Some("BJ".to_string())
+            multi.rects,
+            vec![((1, 1), (2, 6)), ((4, 1), (4, 12)), ((6, 3), (6, 3))]
         );
-        for i in 1..=255 {
-            assert_eq!(letters_to_num(&num_to_letters(i).unwrap()), Some(i));
-        }
-    }
+        let region = Region::from(&multi);
+        assert_eq!(region, Region::Multi(multi.rects.clone()));
+        assert_eq!(RegionDisplay::from(&region).text, "A1:B6, D1:D12, F3");
 
-    #[test]
-    #[wasm_bindgen_test]
-    fn test_try_from_string_for_regiondisplay() {
-        let desired = RegionDisplay {
-            text: "A1:E5".to_string(),
-            row_start: 1,
-            row_end: 5,
-            col_start: 1,
-            col_end: 5,
-        };
-        assert_eq!(desired, "A1:E5".to_string().try_into().unwrap());
+        // A single rectangle is not a multi-region
+        let single: RegionDisplay = "A1:B6,".try_into().unwrap();
+        assert!(single.rects.is_empty());
     }
 }
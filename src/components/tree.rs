@@ -6,7 +6,15 @@ use web_sys::{EventTarget, HtmlDialogElement, HtmlElement, HtmlInputElement};
 use yew::prelude::*;
 use yewdux::prelude::*;
 
+use std::collections::HashMap;
+
+use crate::components::bulk_edit_dialog::BulkEditDialog;
+use crate::components::notifications::confirm;
+use crate::components::project_manager::take_snapshot;
+use crate::components::shortcuts::use_shortcuts;
 use crate::components::states::{CurrentTransfer, MainState};
+use crate::data::plate::PlateType;
+use crate::data::transfer::Transfer;
 use crate::data::transfer_region::Region;
 
 #[derive(PartialEq, Properties)]
@@ -19,6 +27,38 @@ pub fn Tree(props: &TreeProps) -> Html {
     let (main_state, main_dispatch) = use_store::<MainState>();
     let (_, ct_dispatch) = use_store::<CurrentTransfer>();
     let plate_modal_id: UseStateHandle<Option<Uuid>> = use_state(|| None);
+    let search: UseStateHandle<String> = use_state(String::new);
+    let only_selected: UseStateHandle<bool> = use_state(|| false);
+    // Transfers picked with Ctrl/Shift-click for bulk edits
+    let multi_selected: UseStateHandle<Vec<Uuid>> = use_state(Vec::new);
+    let bulk_edit_is_open: UseStateHandle<bool> = use_state(|| false);
+    // The transfer being dragged to a new place in the list
+    let dragging: UseStateHandle<Option<Uuid>> = use_state(|| None);
+    // Status icons, only worked out again when the project changes
+    let statuses = use_memo(|state| state.validate_transfers(), main_state.clone());
+
+    let on_search_input = {
+        let search = search.clone();
+        Callback::from(move |e: InputEvent| {
+            let input = e
+                .target()
+                .and_then(|t| t.dyn_into::<HtmlInputElement>().ok());
+            if let Some(input) = input {
+                search.set(input.value());
+            }
+        })
+    };
+    let on_only_selected_change = {
+        let only_selected = only_selected.clone();
+        Callback::from(move |e: Event| {
+            let input = e
+                .target()
+                .and_then(|t| t.dyn_into::<HtmlInputElement>().ok());
+            if let Some(input) = input {
+                only_selected.set(input.checked());
+            }
+        })
+    };
 
     let open_plate_info_callback = {
         let plate_menu_id = plate_modal_id.clone();
@@ -43,9 +83,34 @@ pub fn Tree(props: &TreeProps) -> Html {
         let plate_menu_id = plate_modal_id.clone();
         Callback::from(move |_| {
             if let Some(id) = *plate_menu_id {
-                dispatch.reduce_mut(|state| {
-                    state.del_plate(id);
-                });
+                let state = dispatch.get();
+                let name = state
+                    .source_plates
+                    .iter()
+                    .chain(state.destination_plates.iter())
+                    .find(|pi| pi.get_uuid() == id)
+                    .map(|pi| pi.name.clone())
+                    .unwrap_or_default();
+                let transfers = state
+                    .transfers
+                    .iter()
+                    .filter(|t| t.source_id == id || t.dest_id == id)
+                    .count();
+                let dispatch = dispatch.clone();
+                confirm(
+                    format!("Delete {}?", name),
+                    format!(
+                        "This will delete the plate and the {} transfers that use it.",
+                        transfers
+                    ),
+                    "Delete Plate",
+                    Callback::from(move |_| {
+                        take_snapshot(&format!("Before deleting {}", name));
+                        dispatch.reduce_mut(|state| {
+                            state.del_plate(id);
+                        });
+                    }),
+                );
             }
         })
     };
@@ -94,13 +159,32 @@ pub fn Tree(props: &TreeProps) -> Html {
 
     let transfer_select_callback = {
         let main_state = main_state.clone();
+        let main_dispatch = main_dispatch.clone();
+        let ct_dispatch = ct_dispatch.clone();
+        let multi_selected = multi_selected.clone();
 
         Callback::from(move |e: MouseEvent| {
-            let target: Option<EventTarget> = e.target();
+            // The name, count, and status are children of the entry
+            let target: Option<EventTarget> = e.current_target();
             let li = target.and_then(|t| t.dyn_into::<HtmlElement>().ok());
             if let Some(li) = li {
                 if let Ok(id) = li.id().as_str().parse::<u128>() {
                     let id = Uuid::from_u128(id);
+                    if e.ctrl_key() || e.shift_key() {
+                        // Starts from the transfer already open, if any
+                        let mut ids = (*multi_selected).clone();
+                        if ids.is_empty() && !main_state.selected_transfer.is_nil() {
+                            ids.push(main_state.selected_transfer);
+                        }
+                        if let Some(index) = ids.iter().position(|i| *i == id) {
+                            ids.remove(index);
+                        } else {
+                            ids.push(id);
+                        }
+                        multi_selected.set(ids);
+                        return;
+                    }
+                    multi_selected.set(Vec::new());
                     if let Some(transfer) = main_state
                         .transfers
                         .iter()
@@ -120,9 +204,109 @@ pub fn Tree(props: &TreeProps) -> Html {
         })
     };
 
+    let ondragstart = {
+        let dragging = dragging.clone();
+        Callback::from(move |e: DragEvent| {
+            let li = e
+                .current_target()
+                .and_then(|t| t.dyn_into::<HtmlElement>().ok());
+            if let Some(id) = li.and_then(|li| li.id().parse::<u128>().ok()) {
+                dragging.set(Some(Uuid::from_u128(id)));
+            }
+        })
+    };
+    let ondragover = Callback::from(|e: DragEvent| {
+        e.prevent_default(); // Allows dropping
+    });
+    let ondrop = {
+        let dragging = dragging.clone();
+        let main_dispatch = main_dispatch.clone();
+        Callback::from(move |e: DragEvent| {
+            e.prevent_default();
+            let li = e
+                .current_target()
+                .and_then(|t| t.dyn_into::<HtmlElement>().ok());
+            if let (Some(id), Some(before)) =
+                (*dragging, li.and_then(|li| li.id().parse::<u128>().ok()))
+            {
+                main_dispatch.reduce_mut(|state| state.move_transfer(id, Uuid::from_u128(before)));
+            }
+            dragging.set(None);
+        })
+    };
+    // [ and ] step through the source plates, { and } through the destination plates
+    {
+        let main_state = main_state.clone();
+        let main_dispatch = main_dispatch.clone();
+        let ct_dispatch = ct_dispatch.clone();
+        use_shortcuts(Callback::from(move |e: KeyboardEvent| {
+            let (source, step) = match e.key().as_str() {
+                "[" => (true, -1),
+                "]" => (true, 1),
+                "{" => (false, -1),
+                "}" => (false, 1),
+                _ => return,
+            };
+            let (plates, current) = if source {
+                (&main_state.source_plates, main_state.selected_source_plate)
+            } else {
+                (
+                    &main_state.destination_plates,
+                    main_state.selected_dest_plate,
+                )
+            };
+            let ids: Vec<Uuid> = plates.iter().map(|pi| pi.get_uuid()).collect();
+            if let Some(id) = cycle_plate(&ids, current, step) {
+                ct_dispatch.reduce_mut(|state| {
+                    state.transfer.transfer_region.source_region = Region::default();
+                    state.transfer.transfer_region.dest_region = Region::default();
+                });
+                main_dispatch.reduce_mut(|state| {
+                    if source {
+                        state.selected_source_plate = id;
+                    } else {
+                        state.selected_dest_plate = id;
+                    }
+                    state.selected_transfer = Uuid::nil();
+                });
+            }
+        }));
+    }
+    let open_bulk_edit_callback = {
+        let bulk_edit_is_open = bulk_edit_is_open.clone();
+        Callback::from(move |_: MouseEvent| {
+            bulk_edit_is_open.set(true);
+        })
+    };
+    let bulk_edit_close_callback = {
+        let bulk_edit_is_open = bulk_edit_is_open.clone();
+        let multi_selected = multi_selected.clone();
+        Callback::from(move |_| {
+            bulk_edit_is_open.set(false);
+            multi_selected.set(Vec::new());
+        })
+    };
+    // Deleted transfers drop out of the selection
+    let bulk_ids: Vec<Uuid> = multi_selected
+        .iter()
+        .filter(|id| main_state.transfers.iter().any(|t| t.get_uuid() == **id))
+        .copied()
+        .collect();
+
+    let query = search.to_lowercase();
+    let matches = |name: &str| name.to_lowercase().contains(&query);
+    let plate_names: HashMap<Uuid, &str> = main_state
+        .source_plates
+        .iter()
+        .chain(main_state.destination_plates.iter())
+        .map(|pi| (pi.get_uuid(), pi.name.as_str()))
+        .collect();
+    let plate_name = |id: &Uuid| plate_names.get(id).copied().unwrap_or("Unknown");
+
     let source_plates = main_state
         .source_plates
         .iter()
+        .filter(|spi| matches(&spi.name))
         .map(|spi| {
             html! { <li id={spi.get_uuid().as_u128().to_string()}
             ondblclick={open_plate_info_callback.clone()}
@@ -138,6 +322,7 @@ pub fn Tree(props: &TreeProps) -> Html {
     let dest_plates = main_state
         .destination_plates
         .iter()
+        .filter(|dpi| matches(&dpi.name))
         .map(|dpi| {
             html! { <li id={dpi.get_uuid().as_u128().to_string()}
             ondblclick={open_plate_info_callback.clone()}
@@ -148,16 +333,65 @@ pub fn Tree(props: &TreeProps) -> Html {
             )}> {String::from(dpi)} </li> }
         })
         .collect::<Html>();
-    let transfers = main_state
-        .transfers
+    // Transfers grouped by (source, destination) plate, in order of first appearance
+    let mut plate_pairs: Vec<((Uuid, Uuid), Vec<&Transfer>)> = Vec::new();
+    for transfer in main_state.transfers.iter().filter(|t| {
+        (!*only_selected
+            || t.source_id == main_state.selected_source_plate
+            || t.dest_id == main_state.selected_dest_plate)
+            && (matches(&t.name)
+                || matches(plate_name(&t.source_id))
+                || matches(plate_name(&t.dest_id)))
+    }) {
+        let pair = (transfer.source_id, transfer.dest_id);
+        match plate_pairs.iter_mut().find(|(p, _)| *p == pair) {
+            Some((_, transfers)) => transfers.push(transfer),
+            None => plate_pairs.push((pair, vec![transfer])),
+        }
+    }
+    let transfers = plate_pairs
         .iter()
-        .map(|transfer| {
-            html! { <li id={transfer.get_uuid().as_u128().to_string()}
-                     onclick={transfer_select_callback.clone()}
-                     class={classes!(
-                         if transfer.get_uuid() == main_state.selected_transfer {Some("selected")}
-                         else {None})}>
-                {transfer.name.clone()}
+        .map(|((source_id, dest_id), transfers)| {
+            let entries = transfers
+                .iter()
+                .map(|transfer| {
+                    let wells = (
+                        transfer.transfer_region.get_source_wells().len(),
+                        transfer
+                            .transfer_region
+                            .get_destination_wells(main_state.plate_mask(transfer.dest_id))
+                            .len(),
+                    );
+                    let status = statuses
+                        .get(&transfer.get_uuid())
+                        .cloned()
+                        .unwrap_or(Ok(()));
+                    html! { <li id={transfer.get_uuid().as_u128().to_string()}
+                             onclick={transfer_select_callback.clone()}
+                             draggable="true"
+                             ondragstart={ondragstart.clone()}
+                             ondragover={ondragover.clone()}
+                             ondrop={ondrop.clone()}
+                             class={classes!(
+                                 if transfer.get_uuid() == main_state.selected_transfer {Some("selected")}
+                                 else {None},
+                                 bulk_ids.contains(&transfer.get_uuid()).then_some("multi_selected"))}>
+                        <span class={classes!("status", status.is_err().then_some("invalid"))}
+                        title={status.clone().err().unwrap_or("OK".to_string())}>
+                            {if status.is_ok() { "\u{2714}" } else { "\u{26A0}" }}
+                        </span>
+                        {transfer.name.clone()}
+                        <span class="well_count" title="Source wells, destination wells">
+                            {format!("{} \u{2192} {}", wells.0, wells.1)}
+                        </span>
+                        </li>
+                    }
+                })
+                .collect::<Html>();
+            html! {
+                <li class="plate_pair">
+                    <span>{format!("{} \u{2192} {}", plate_name(source_id), plate_name(dest_id))}</span>
+                    <ul>{entries}</ul>
                 </li>
             }
         })
@@ -165,6 +399,14 @@ pub fn Tree(props: &TreeProps) -> Html {
 
     html! {
         <div class="tree">
+            <div id="tree-filter">
+            <input type="search" placeholder="Search plates and transfers"
+            value={(*search).clone()} oninput={on_search_input}/>
+            <label>
+            <input type="checkbox" checked={*only_selected} onchange={on_only_selected_change}/>
+            {"Selected plates only"}
+            </label>
+            </div>
             <div id="source-plates">
             <h3>{"Source Plates:"}</h3>
             <ul>
@@ -183,6 +425,9 @@ pub fn Tree(props: &TreeProps) -> Html {
                 {transfers}
             </ul>
             </div>
+            if *bulk_edit_is_open {
+                <BulkEditDialog ids={bulk_ids.clone()} close_callback={bulk_edit_close_callback}/>
+            }
             if let Some(id) = *plate_modal_id {
                 <PlateInfoModal id={id} dialog_close_callback={plate_info_close_callback}
                 delete_button_callback={plate_info_delete_callback}/>
@@ -195,11 +440,29 @@ pub fn Tree(props: &TreeProps) -> Html {
                 move |_| {open_new_plate_callback.emit(())}
             }>
             {"New Plate"}</button>
+            if !bulk_ids.is_empty() {
+                <button type="button" onclick={open_bulk_edit_callback}>
+                {format!("Edit Selected ({})", bulk_ids.len())}</button>
+            }
             </div>
         </div>
     }
 }
 
+// The plate `step` places from `current`, wrapping around at either end
+fn cycle_plate(ids: &[Uuid], current: Uuid, step: isize) -> Option<Uuid> {
+    if ids.is_empty() {
+        return None;
+    }
+    let n = ids.len() as isize;
+    let next = match ids.iter().position(|id| *id == current) {
+        Some(index) => (index as isize + step).rem_euclid(n),
+        None if step < 0 => n - 1,
+        None => 0,
+    };
+    ids.get(next as usize).copied()
+}
+
 #[derive(PartialEq, Properties)]
 struct PlateInfoModalProps {
     id: Uuid,
@@ -226,6 +489,18 @@ fn PlateInfoModal(props: &PlateInfoModalProps) -> Html {
         Some(plate) => plate.name.clone(),
         None => "Not Found".to_string(),
     };
+    let is_source = plate.is_some_and(|p| p.plate.plate_type == PlateType::Source);
+    // Stock concentration for source plates, final well volume for destinations
+    let plate_amount = plate
+        .and_then(|p| {
+            if is_source {
+                p.concentration
+            } else {
+                p.well_volume
+            }
+        })
+        .map(|v| v.to_string())
+        .unwrap_or_default();
     let onclose = {
         let dialog_close_callback = props.dialog_close_callback.clone();
         move |_| dialog_close_callback.emit(())
@@ -233,6 +508,7 @@ fn PlateInfoModal(props: &PlateInfoModalProps) -> Html {
 
     let rename_onchange = {
         let id = props.id;
+        let main_dispatch = main_dispatch.clone();
         Callback::from(move |e: Event| {
             log::debug!("Changed name");
             let input = e
@@ -244,6 +520,31 @@ fn PlateInfoModal(props: &PlateInfoModalProps) -> Html {
         })
     };
 
+    let amount_onchange = {
+        let id = props.id;
+        Callback::from(move |e: Event| {
+            let input = e
+                .target()
+                .expect("Event must have target")
+                .dyn_into::<HtmlInputElement>()
+                .unwrap();
+            // Clearing the field forgets the value
+            let value = input.value().parse::<f32>().ok().filter(|v| *v >= 0.0);
+            main_dispatch.reduce_mut(|state| {
+                if let Some(spi) = state.source_plates.iter_mut().find(|p| p.get_uuid() == id) {
+                    spi.concentration = value;
+                }
+                if let Some(dpi) = state
+                    .destination_plates
+                    .iter_mut()
+                    .find(|p| p.get_uuid() == id)
+                {
+                    dpi.well_volume = value;
+                }
+            })
+        })
+    };
+
     let delete_onclick = {
         let delete_button_callback = props.delete_button_callback.clone();
         let dialog_ref = dialog_ref.clone();
@@ -272,6 +573,8 @@ fn PlateInfoModal(props: &PlateInfoModalProps) -> Html {
         <dialog ref={dialog_ref} class="dialog" onclose={onclose}>
             <h2>{"Plate Info"}</h2>
             <h3>{"Name: "}<input type="text" value={plate_name} onchange={rename_onchange}/></h3>
+            <h3>{if is_source { "Concentration (µM): " } else { "Final well volume (µL): " }}
+            <input type="number" min="0" value={plate_amount} onchange={amount_onchange}/></h3>
             <button onclick={delete_onclick}>{"Delete"}</button>
             <form class="modal_close" method="dialog"><button /></form>
         </dialog>
@@ -0,0 +1,207 @@
+use super::transfer_region::Region;
+use super::well_name::{parse_row_col, parse_well_ordered, IndexOrder};
+
+#[derive(PartialEq, Clone, Debug)]
+pub struct CherryPick {
+    pub source_plate: Option<String>,
+    pub source_well: (u8, u8),
+    pub destination_plate: Option<String>,
+    pub destination_well: (u8, u8),
+    pub volume: Option<f32>,
+}
+
+#[derive(PartialEq, Clone, Debug)]
+pub struct CherryPickError {
+    pub line: usize,
+    pub message: String,
+}
+
+// Accepted header names, compared after lowercasing and dropping anything
+// that isn't a letter (so "Source Well", "source_well" and "SourceWell" match).
+const SOURCE_PLATE: &[&str] = &[
+    "sourceplate",
+    "srcplate",
+    "sourceplatebarcode",
+    "sourceplatename",
+];
+const SOURCE_WELL: &[&str] = &["sourcewell", "srcwell", "source"];
+const SOURCE_ROW: &[&str] = &["sourcerow", "srcrow"];
+const SOURCE_COL: &[&str] = &["sourcecolumn", "sourcecol", "srccolumn", "srccol"];
+const DEST_PLATE: &[&str] = &[
+    "destinationplate",
+    "destplate",
+    "destinationplatebarcode",
+    "destplatebarcode",
+    "destinationplatename",
+];
+const DEST_WELL: &[&str] = &["destinationwell", "destwell", "destination", "dest"];
+const DEST_ROW: &[&str] = &["destinationrow", "destrow"];
+const DEST_COL: &[&str] = &[
+    "destinationcolumn",
+    "destinationcol",
+    "destcolumn",
+    "destcol",
+];
+const VOLUME: &[&str] = &["volume", "transfervolume", "vol"];
+
+enum WellColumns {
+    Name(usize),
+    RowCol(usize, usize),
+}
+
+pub fn parse_cherry_picks(
+    data: &str,
+    source_size: (u8, u8),
+    dest_size: (u8, u8),
+    order: IndexOrder,
+) -> Result<(Vec<CherryPick>, Vec<CherryPickError>), String> {
+    // Reads a cherry-pick list (one transfer per line, with a header row).
+    // Lines that cannot be read are reported instead of aborting the import.
+    // Wells given as plain numbers are counted in the given order.
+    let mut rdr = csv::ReaderBuilder::new()
+        .flexible(true)
+        .trim(csv::Trim::All)
+        .from_reader(data.as_bytes());
+    let headers: Vec<String> = rdr
+        .headers()
+        .map_err(|e| e.to_string())?
+        .iter()
+        .map(|h| {
+            h.chars()
+                .filter(|c| c.is_ascii_alphabetic())
+                .collect::<String>()
+                .to_lowercase()
+        })
+        .collect();
+    let find = |names: &[&str]| headers.iter().position(|h| names.contains(&h.as_str()));
+
+    let well_columns = |well: &[&str], row: &[&str], col: &[&str], side: &str| {
+        if let Some(index) = find(well) {
+            Ok(WellColumns::Name(index))
+        } else if let (Some(row), Some(col)) = (find(row), find(col)) {
+            Ok(WellColumns::RowCol(row, col))
+        } else {
+            Err(format!("No {} well column found", side))
+        }
+    };
+    let source_columns = well_columns(SOURCE_WELL, SOURCE_ROW, SOURCE_COL, "source")?;
+    let dest_columns = well_columns(DEST_WELL, DEST_ROW, DEST_COL, "destination")?;
+    let source_plate_column = find(SOURCE_PLATE);
+    let dest_plate_column = find(DEST_PLATE);
+    let volume_column = find(VOLUME);
+
+    let mut picks = Vec::new();
+    let mut errors = Vec::new();
+    for (index, record) in rdr.records().enumerate() {
+        let line = index + 2; // Header is line 1
+        let record = match record {
+            Ok(record) => record,
+            Err(e) => {
+                errors.push(CherryPickError {
+                    line,
+                    message: e.to_string(),
+                });
+                continue;
+            }
+        };
+        if record.iter().all(|field| field.is_empty()) {
+            continue;
+        }
+        let get = |column: usize| record.get(column).unwrap_or("");
+        let read_well = |columns: &WellColumns, size: (u8, u8)| match columns {
+            WellColumns::Name(c) => parse_well_ordered(get(*c), size, order),
+            WellColumns::RowCol(r, c) => parse_row_col(get(*r), get(*c), size),
+        };
+
+        let pick = (|| {
+            let source_well = read_well(&source_columns, source_size)?;
+            let destination_well = read_well(&dest_columns, dest_size)?;
+            let volume = match volume_column.map(get) {
+                Some(text) if !text.is_empty() => Some(
+                    text.parse::<f32>()
+                        .ok()
+                        .filter(|v| *v > 0.0)
+                        .ok_or(format!("Invalid volume \"{}\"", text))?,
+                ),
+                _ => None,
+            };
+            Ok::<_, String>(CherryPick {
+                source_plate: source_plate_column.map(|c| get(c).to_string()),
+                source_well,
+                destination_plate: dest_plate_column.map(|c| get(c).to_string()),
+                destination_well,
+                volume,
+            })
+        })();
+        match pick {
+            Ok(pick) => picks.push(pick),
+            Err(message) => errors.push(CherryPickError { line, message }),
+        }
+    }
+    Ok((picks, errors))
+}
+
+pub fn cherry_picks_to_region(picks: &[CherryPick]) -> Region {
+    let transfers: Vec<_> = picks
+        .iter()
+        .map(|p| (p.source_well, p.destination_well, p.volume))
+        .collect();
+    Region::new_custom_with_volumes(&transfers)
+}
+
+#[cfg(test)]
+mod tests {
+    use wasm_bindgen_test::*;
+
+    use super::parse_cherry_picks;
+    use crate::data::well_name::IndexOrder;
+
+    #[test]
+    #[wasm_bindgen_test]
+    fn test_parse_cherry_picks() {
+        let data = "Source Plate,Source Well,Destination Well,Volume\n\
+                    P1,A01,1,25\n\
+                    P1,B2,H12,\n\
+                    P1,Z1,A1,5\n\
+                    P1,A1,A2,-1\n";
+        let (picks, errors) =
+            parse_cherry_picks(data, (8, 12), (8, 12), IndexOrder::RowMajor).unwrap();
+        assert_eq!(picks.len(), 2);
+        assert_eq!(picks[0].source_well, (1, 1));
+        assert_eq!(picks[0].destination_well, (1, 1));
+        assert_eq!(picks[0].volume, Some(25.0));
+        assert_eq!(picks[0].source_plate, Some("P1".to_string()));
+        assert_eq!(picks[1].destination_well, (8, 12));
+        assert_eq!(picks[1].volume, None);
+        assert_eq!(
+            errors.iter().map(|e| e.line).collect::<Vec<_>>(),
+            vec![4, 5]
+        );
+
+        let data = "src_row,src_col,dest_row,dest_col\n2,3,C,4\n";
+        let (picks, errors) =
+            parse_cherry_picks(data, (8, 12), (16, 24), IndexOrder::RowMajor).unwrap();
+        assert!(errors.is_empty());
+        assert_eq!(picks[0].source_well, (2, 3));
+        assert_eq!(picks[0].destination_well, (3, 4));
+
+        assert!(parse_cherry_picks("a,b\n1,2\n", (8, 12), (8, 12), IndexOrder::RowMajor).is_err());
+    }
+
+    #[test]
+    #[wasm_bindgen_test]
+    fn test_parse_cherry_picks_column_major() {
+        let data = "Source Well,Destination Well\n2,9\nB1,96\n";
+        let (picks, errors) =
+            parse_cherry_picks(data, (8, 12), (8, 12), IndexOrder::ColumnMajor).unwrap();
+        assert!(errors.is_empty());
+        assert_eq!(picks[0].source_well, (2, 1));
+        assert_eq!(picks[0].destination_well, (1, 2));
+        assert_eq!(picks[1].source_well, (2, 1));
+        assert_eq!(picks[1].destination_well, (8, 12));
+
+        let (picks, _) = parse_cherry_picks(data, (8, 12), (8, 12), IndexOrder::RowMajor).unwrap();
+        assert_eq!(picks[0].source_well, (1, 2));
+        assert_eq!(picks[0].destination_well, (1, 9));
+    }
+}
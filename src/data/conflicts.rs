@@ -0,0 +1,108 @@
+// Destination wells written to more than once.
+//
+// A well receiving from several transfers, or from several source wells,
+// is either intended pooling or an accidental overlap. Only the
+// transfers themselves can tell us which, via Transfer.pooling_allowed.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use uuid::Uuid;
+
+use super::plate_instances::{plate_mask, PlateInstance};
+use super::transfer::Transfer;
+
+// A well on a given plate instance
+type PlateWell = (Uuid, (u8, u8));
+
+#[derive(PartialEq, Clone, Debug)]
+pub struct WellConflict {
+    pub dest_plate: Uuid,
+    pub well: (u8, u8),
+    pub transfers: Vec<Uuid>,
+    pub sources: Vec<PlateWell>,
+    // Every transfer involved allows pooling
+    pub pooling_allowed: bool,
+}
+
+pub fn find_conflicts(transfers: &[Transfer], dest_plates: &[PlateInstance]) -> Vec<WellConflict> {
+    // Sorted by destination plate then well so the list is stable
+    let mut wells: BTreeMap<PlateWell, (Vec<&Transfer>, BTreeSet<PlateWell>)> = BTreeMap::new();
+    for transfer in transfers {
+        let mask = plate_mask(dest_plates, transfer.dest_id);
+        for (s_well, d_well, _) in transfer
+            .transfer_region
+            .get_transfer_pairs(transfer.volume, mask)
+        {
+            let (ts, sources) = wells.entry((transfer.dest_id, d_well)).or_default();
+            if !ts.iter().any(|t| t.get_uuid() == transfer.get_uuid()) {
+                ts.push(transfer);
+            }
+            sources.insert((transfer.source_id, s_well));
+        }
+    }
+    wells
+        .into_iter()
+        .filter(|(_, (ts, sources))| ts.len() > 1 || sources.len() > 1)
+        .map(|((dest_plate, well), (ts, sources))| WellConflict {
+            dest_plate,
+            well,
+            pooling_allowed: ts.iter().all(|t| t.pooling_allowed),
+            transfers: ts.iter().map(|t| t.get_uuid()).collect(),
+            sources: sources.into_iter().collect(),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use uuid::Uuid;
+    use wasm_bindgen_test::*;
+
+    use super::*;
+    use crate::data::plate::*;
+    use crate::data::transfer_region::*;
+
+    fn transfer(source: Region, dest: Region, pooling_allowed: bool) -> Transfer {
+        Transfer {
+            id: Uuid::now_v7(),
+            pooling_allowed,
+            transfer_region: TransferRegion {
+                source_plate: Plate::new(PlateType::Source, PlateFormat::W96),
+                dest_plate: Plate::new(PlateType::Destination, PlateFormat::W96),
+                source_region: source,
+                dest_region: dest,
+                interleave_source: (1, 1),
+                interleave_dest: (1, 1),
+                fill_order: Default::default(),
+            },
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    #[wasm_bindgen_test]
+    fn test_find_conflicts() {
+        // A1:A2 -> B1:B2, then A3 -> B2 (overlap on B2)
+        let t1 = transfer(
+            Region::Rect((1, 1), (1, 2)),
+            Region::Rect((2, 1), (2, 2)),
+            false,
+        );
+        let t2 = transfer(Region::Point((1, 3)), Region::Point((2, 2)), true);
+        let conflicts = find_conflicts(&[t1.clone(), t2.clone()], &[]);
+        assert_eq!(conflicts.len(), 1);
+        assert_eq!(conflicts[0].well, (2, 2));
+        assert_eq!(conflicts[0].transfers, vec![t1.get_uuid(), t2.get_uuid()]);
+        assert_eq!(conflicts[0].sources.len(), 2);
+        assert!(!conflicts[0].pooling_allowed);
+
+        // Pooling within one transfer (two sources into one well)
+        let pool = Region::new_custom(&vec![((1, 1), (3, 3)), ((1, 2), (3, 3))]);
+        let mut t3 = transfer(pool.clone(), pool, false);
+        assert_eq!(find_conflicts(&[t3.clone()], &[]).len(), 1);
+        t3.pooling_allowed = true;
+        assert!(find_conflicts(&[t3], &[])[0].pooling_allowed);
+
+        assert!(find_conflicts(&[t1], &[]).is_empty());
+    }
+}
@@ -0,0 +1,223 @@
+// Control well placement.
+//
+// A pattern decides which destination wells hold controls and which
+// control goes in each. Whatever is left over is for samples.
+
+use serde::{Deserialize, Serialize};
+
+use super::plate_instances::PlateInstance;
+use super::transfer::Transfer;
+use super::transfer_region::{Region, TransferRegion};
+
+#[derive(PartialEq, Eq, Clone, Copy, Serialize, Deserialize, Debug)]
+pub enum ControlPattern {
+    // The outer `width` columns on each side, controls alternating down them
+    EdgeColumns { width: u8 },
+    // The same columns, controls alternating in a checkerboard
+    Checkerboard { width: u8 },
+    // `per_row` wells spread evenly along every row
+    DistributedRows { per_row: u8 },
+}
+
+impl ControlPattern {
+    pub fn label(&self) -> &'static str {
+        match self {
+            ControlPattern::EdgeColumns { .. } => "Edge columns",
+            ControlPattern::Checkerboard { .. } => "Edge columns, checkerboard",
+            ControlPattern::DistributedRows { .. } => "Distributed across rows",
+        }
+    }
+}
+
+#[derive(PartialEq, Clone, Debug)]
+pub struct Control {
+    pub name: String,
+    pub source_well: (u8, u8),
+}
+
+pub fn control_layout(
+    pattern: ControlPattern,
+    size: (u8, u8),
+    controls: usize,
+) -> Vec<(usize, (u8, u8))> {
+    // (index into the controls, destination well) for every control well
+    if controls == 0 {
+        return vec![];
+    }
+    let (rows, cols) = size;
+    let edge_columns = |width: u8| {
+        // In u16 so a 255 column plate doesn't overflow
+        let cols = cols as u16;
+        let width = u16::min(width as u16, (cols + 1) / 2);
+        let mut columns: Vec<u8> = (1..=width)
+            .chain(cols + 1 - width..=cols)
+            .map(|col| col as u8)
+            .collect();
+        columns.dedup();
+        columns
+    };
+    match pattern {
+        ControlPattern::EdgeColumns { width } => edge_columns(width)
+            .into_iter()
+            .flat_map(|col| (1..=rows).map(move |row| (row, col)))
+            .enumerate()
+            .map(|(i, well)| (i % controls, well))
+            .collect(),
+        ControlPattern::Checkerboard { width } => edge_columns(width)
+            .into_iter()
+            .flat_map(|col| (1..=rows).map(move |row| (row, col)))
+            .map(|well| ((well.0 as usize + well.1 as usize) % controls, well))
+            .collect(),
+        ControlPattern::DistributedRows { per_row } => {
+            let per_row = u8::min(per_row, cols);
+            let mut layout = Vec::new();
+            for row in 1..=rows {
+                for k in 0..per_row {
+                    // Evenly spaced, first and last column included
+                    let col = if per_row == 1 {
+                        1
+                    } else {
+                        1 + (k as u16 * (cols as u16 - 1) / (per_row as u16 - 1)) as u8
+                    };
+                    // Shift by row so each control visits every position
+                    let control = (k as usize + row as usize - 1) % controls;
+                    layout.push((control, (row, col)));
+                }
+            }
+            layout
+        }
+    }
+}
+
+pub fn control_transfers(
+    controls: &[Control],
+    pattern: ControlPattern,
+    source: &PlateInstance,
+    destinations: &[PlateInstance],
+) -> Vec<Transfer> {
+    // One custom transfer per control and destination plate
+    let mut transfers = Vec::new();
+    for dest in destinations {
+        let layout = control_layout(pattern, dest.plate.size(), controls.len());
+        for (index, control) in controls.iter().enumerate() {
+            let pairs: Vec<((u8, u8), (u8, u8))> = layout
+                .iter()
+                .filter(|(i, _)| *i == index)
+                .map(|(_, well)| (control.source_well, *well))
+                .collect();
+            if pairs.is_empty() {
+                continue;
+            }
+            let region = Region::new_custom(&pairs);
+            let transfer_region = TransferRegion {
+                source_region: region.clone(),
+                dest_region: region,
+                interleave_source: (1, 1),
+                interleave_dest: (1, 1),
+                fill_order: Default::default(),
+                source_plate: source.plate,
+                dest_plate: dest.plate,
+            };
+            transfers.push(Transfer::new(
+                source.clone(),
+                dest.clone(),
+                transfer_region,
+                format!("{} ({})", control.name, dest.name),
+            ));
+        }
+    }
+    transfers
+}
+
+pub fn sample_wells(layout: &[(usize, (u8, u8))], size: (u8, u8)) -> Vec<(u8, u8)> {
+    // Wells left over for samples once the controls are placed
+    (1..=size.0)
+        .flat_map(|row| (1..=size.1).map(move |col| (row, col)))
+        .filter(|well| !layout.iter().any(|(_, w)| w == well))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use wasm_bindgen_test::*;
+
+    use super::*;
+    use crate::data::plate::{PlateFormat, PlateType};
+
+    #[test]
+    #[wasm_bindgen_test]
+    fn test_control_layout() {
+        let size = (16, 24);
+        let edges = control_layout(ControlPattern::EdgeColumns { width: 2 }, size, 2);
+        assert_eq!(edges.len(), 64);
+        assert_eq!(&edges[..3], &[(0, (1, 1)), (1, (2, 1)), (0, (3, 1))]);
+        assert!(edges.iter().all(|(_, w)| [1, 2, 23, 24].contains(&w.1)));
+        assert_eq!(sample_wells(&edges, size).len(), 384 - 64);
+
+        let checker = control_layout(ControlPattern::Checkerboard { width: 1 }, size, 2);
+        assert_eq!(&checker[..2], &[(0, (1, 1)), (1, (2, 1))]);
+        assert_eq!(checker[16], (1, (1, 24)));
+
+        let rows = control_layout(ControlPattern::DistributedRows { per_row: 3 }, (8, 12), 2);
+        assert_eq!(&rows[..3], &[(0, (1, 1)), (1, (1, 6)), (0, (1, 12))]);
+        assert_eq!(rows[3], (1, (2, 1)));
+
+        // Wider than the plate: every column once
+        let all = control_layout(ControlPattern::EdgeColumns { width: 9 }, (2, 3), 1);
+        assert_eq!(all.len(), 6);
+
+        assert!(control_layout(ControlPattern::EdgeColumns { width: 2 }, size, 0).is_empty());
+
+        // The largest custom format
+        let largest = control_layout(ControlPattern::Checkerboard { width: 1 }, (255, 255), 2);
+        assert_eq!(largest.len(), 510);
+        assert_eq!(largest.last(), Some(&(0, (255, 255))));
+    }
+
+    #[test]
+    #[wasm_bindgen_test]
+    fn test_control_transfers() {
+        let source = PlateInstance::new(PlateType::Source, PlateFormat::W96, "S".to_string());
+        let dests = vec![
+            PlateInstance::new(PlateType::Destination, PlateFormat::W384, "D1".to_string()),
+            PlateInstance::new(PlateType::Destination, PlateFormat::W96, "D2".to_string()),
+        ];
+        let controls = vec![
+            Control {
+                name: "Positive".to_string(),
+                source_well: (1, 1),
+            },
+            Control {
+                name: "Negative".to_string(),
+                source_well: (2, 1),
+            },
+        ];
+        let transfers = control_transfers(
+            &controls,
+            ControlPattern::EdgeColumns { width: 1 },
+            &source,
+            &dests,
+        );
+        assert_eq!(transfers.len(), 4);
+        assert_eq!(transfers[0].name, "Positive (D1)");
+        assert_eq!(transfers[0].dest_id, dests[0].get_uuid());
+        assert_eq!(
+            transfers[0].transfer_region.get_source_wells(),
+            vec![(1, 1)]
+        );
+        assert_eq!(
+            transfers[0]
+                .transfer_region
+                .get_destination_wells(&[])
+                .len(),
+            16
+        );
+        assert_eq!(
+            transfers[3]
+                .transfer_region
+                .get_destination_wells(&[])
+                .len(),
+            8
+        );
+    }
+}
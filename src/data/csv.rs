@@ -1,11 +1,14 @@
 use crate::components::states::MainState;
-use crate::components::transfer_menu::num_to_letters;
+use crate::data::plate::{CustomFormat, Plate, PlateFormat};
 use crate::data::transfer::Transfer;
+use crate::data::well_name::{format_well, WellNameStyle};
 
-use serde::{Serialize, Deserialize};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::error::Error;
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct TransferRecord {
     #[serde(rename = "Source Plate")]
     pub source_plate: String,
@@ -19,10 +22,114 @@ pub struct TransferRecord {
     pub volume: f32,
     #[serde(rename = "Concentration")]
     pub concentration: Option<f32>,
+    #[serde(rename = "Source Plate Type")]
+    pub source_plate_type: String,
+    #[serde(rename = "Destination Plate Type")]
+    pub destination_plate_type: String,
+}
+
+// Same columns, but with wells split into row and column numbers
+#[derive(Serialize, Deserialize, Debug)]
+pub struct RowColumnTransferRecord {
+    #[serde(rename = "Source Plate")]
+    pub source_plate: String,
+    #[serde(rename = "Source Row")]
+    pub source_row: u8,
+    #[serde(rename = "Source Column")]
+    pub source_column: u8,
+    #[serde(rename = "Dest Plate")]
+    pub destination_plate: String,
+    #[serde(rename = "Destination Row")]
+    pub destination_row: u8,
+    #[serde(rename = "Destination Column")]
+    pub destination_column: u8,
+    #[serde(rename = "Transfer Volume")]
+    pub volume: f32,
+    #[serde(rename = "Concentration")]
+    pub concentration: Option<f32>,
+    #[serde(rename = "Source Plate Type")]
+    pub source_plate_type: String,
+    #[serde(rename = "Destination Plate Type")]
+    pub destination_plate_type: String,
 }
 
-pub fn state_to_csv(state: &MainState) -> Result<String, Box<dyn Error>> {
-    let mut records: Vec<TransferRecord> = Vec::new();
+// Wells are kept alongside the record in case they need splitting up
+type RecordWithWells = (TransferRecord, (u8, u8), (u8, u8));
+
+#[derive(PartialEq, Eq, Clone, Copy, Serialize, Deserialize, JsonSchema, Debug)]
+pub enum TransferOrdering {
+    AsEntered, // Transfer list order, then source well scan order
+    Serpentine,
+    ColumnMajor,
+}
+impl Default for TransferOrdering {
+    fn default() -> Self {
+        Self::AsEntered
+    }
+}
+
+impl TransferOrdering {
+    pub const ALL: [TransferOrdering; 3] = [
+        TransferOrdering::AsEntered,
+        TransferOrdering::Serpentine,
+        TransferOrdering::ColumnMajor,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            TransferOrdering::AsEntered => "As entered",
+            TransferOrdering::Serpentine => "Group plates, serpentine wells",
+            TransferOrdering::ColumnMajor => "Group plates, wells by column",
+        }
+    }
+}
+
+#[derive(Default, Clone, Copy, PartialEq, Debug)]
+pub struct CsvExportOptions {
+    pub well_style: WellNameStyle,
+    pub ordering: TransferOrdering,
+}
+
+pub fn state_to_csv(
+    state: &MainState,
+    options: &CsvExportOptions,
+) -> Result<String, Box<dyn Error>> {
+    let records = collect_records(state, options)?;
+    if options.well_style == WellNameStyle::RowColumn {
+        return records_to_csv(records.into_iter().map(|(record, s_well, d_well)| {
+            RowColumnTransferRecord {
+                source_plate: record.source_plate,
+                source_row: s_well.0,
+                source_column: s_well.1,
+                destination_plate: record.destination_plate,
+                destination_row: d_well.0,
+                destination_column: d_well.1,
+                volume: record.volume,
+                concentration: record.concentration,
+                source_plate_type: record.source_plate_type,
+                destination_plate_type: record.destination_plate_type,
+            }
+        }));
+    }
+    return records_to_csv(records.into_iter().map(|(record, _, _)| record));
+}
+
+pub fn state_to_records(
+    state: &MainState,
+    options: &CsvExportOptions,
+) -> Result<Vec<TransferRecord>, Box<dyn Error>> {
+    // The rows state_to_csv would write, in the same order
+    Ok(collect_records(state, options)?
+        .into_iter()
+        .map(|(record, _, _)| record)
+        .collect())
+}
+
+fn collect_records(
+    state: &MainState,
+    options: &CsvExportOptions,
+) -> Result<Vec<RecordWithWells>, Box<dyn Error>> {
+    let mut records: Vec<RecordWithWells> = Vec::new();
     for transfer in &state.transfers {
         let src_barcode = state
             .source_plates
@@ -36,42 +143,96 @@ pub fn state_to_csv(state: &MainState) -> Result<String, Box<dyn Error>> {
             .ok_or("Found unpurged transfer")?;
         records.append(&mut transfer_to_records(
             transfer,
-            &src_barcode.name,
-            &dest_barcode.name,
+            (&src_barcode.name, &dest_barcode.name),
+            (
+                &plate_type(&src_barcode.plate, &state.custom_formats),
+                &plate_type(&dest_barcode.plate, &state.custom_formats),
+            ),
+            &dest_barcode.mask,
+            options.well_style,
         ))
     }
-    return records_to_csv(records);
+    order_records(&mut records, options.ordering);
+    Ok(records)
+}
+
+fn plate_type(plate: &Plate, custom_formats: &[CustomFormat]) -> String {
+    // Custom formats give their vendor plate type, built-in labware its catalogue name
+    match plate.plate_format {
+        PlateFormat::Custom { id, .. } => custom_formats
+            .iter()
+            .find(|cf| cf.get_uuid() == id)
+            .map(|cf| cf.vendor_type.clone())
+            .unwrap_or_default(),
+        _ => plate
+            .labware(custom_formats)
+            .map(|labware| labware.name.to_string())
+            .unwrap_or_default(),
+    }
 }
 
 fn transfer_to_records(
     tr: &Transfer,
-    src_barcode: &str,
-    dest_barcode: &str,
-) -> Vec<TransferRecord> {
-    let source_wells = tr.transfer_region.get_source_wells();
-    let map = tr.transfer_region.calculate_map();
-
-    let mut records: Vec<TransferRecord> = vec![];
-
-    for s_well in source_wells {
-        let dest_wells = map(s_well);
-        if let Some(dest_wells) = dest_wells {
-            for d_well in dest_wells {
-                records.push(TransferRecord {
-                    source_plate: src_barcode.to_string(),
-                    source_well: format!("{}{}", num_to_letters(s_well.0).unwrap(), s_well.1),
-                    destination_plate: dest_barcode.to_string(),
-                    destination_well: format!("{}{}", num_to_letters(d_well.0).unwrap(), d_well.1),
-                    volume: tr.volume,
-                    concentration: None,
-                })
-            }
-        }
+    (src_barcode, dest_barcode): (&str, &str),
+    (src_type, dest_type): (&str, &str),
+    dest_mask: &[(u8, u8)],
+    well_style: WellNameStyle,
+) -> Vec<RecordWithWells> {
+    let mut records = vec![];
+    let source_size = tr.transfer_region.source_plate.size();
+    let dest_size = tr.transfer_region.dest_plate.size();
+
+    for (s_well, d_well, volume) in tr.transfer_region.get_transfer_pairs(tr.volume, dest_mask) {
+        let record = TransferRecord {
+            source_plate: src_barcode.to_string(),
+            source_well: format_well(s_well, source_size, well_style),
+            destination_plate: dest_barcode.to_string(),
+            destination_well: format_well(d_well, dest_size, well_style),
+            volume,
+            concentration: None,
+            source_plate_type: src_type.to_string(),
+            destination_plate_type: dest_type.to_string(),
+        };
+        records.push((record, s_well, d_well))
     }
     records
 }
 
-fn records_to_csv(trs: Vec<TransferRecord>) -> Result<String, Box<dyn Error>> {
+fn order_records(records: &mut [RecordWithWells], ordering: TransferOrdering) {
+    // Each source plate is loaded once, and within it each destination plate,
+    // taken in the order they first appear. Wells within a plate pair are then
+    // visited in a sweep rather than jumping back and forth.
+    // The sort is stable so equal keys keep their original order,
+    // which keeps the output the same for the same project.
+    if ordering == TransferOrdering::AsEntered {
+        return;
+    }
+    let mut source_rank: HashMap<String, usize> = HashMap::new();
+    let mut dest_rank: HashMap<String, usize> = HashMap::new();
+    for (record, _, _) in records.iter() {
+        let n = source_rank.len();
+        source_rank.entry(record.source_plate.clone()).or_insert(n);
+        let n = dest_rank.len();
+        dest_rank
+            .entry(record.destination_plate.clone())
+            .or_insert(n);
+    }
+    let well_key = |well: (u8, u8)| match ordering {
+        TransferOrdering::Serpentine if well.0 % 2 == 0 => (well.0, u8::MAX - well.1),
+        TransferOrdering::Serpentine | TransferOrdering::AsEntered => well,
+        TransferOrdering::ColumnMajor => (well.1, well.0),
+    };
+    records.sort_by_key(|(record, s_well, d_well)| {
+        (
+            source_rank[&record.source_plate],
+            dest_rank[&record.destination_plate],
+            well_key(*s_well),
+            well_key(*d_well),
+        )
+    });
+}
+
+fn records_to_csv<R: Serialize>(trs: impl Iterator<Item = R>) -> Result<String, Box<dyn Error>> {
     let mut wtr = csv::WriterBuilder::new().from_writer(vec![]);
     for record in trs {
         wtr.serialize(record)?
@@ -79,3 +240,95 @@ fn records_to_csv(trs: Vec<TransferRecord>) -> Result<String, Box<dyn Error>> {
     let data = String::from_utf8(wtr.into_inner()?)?;
     Ok(data)
 }
+
+#[cfg(test)]
+mod tests {
+    use wasm_bindgen_test::*;
+
+    use super::*;
+
+    fn record(source_plate: &str, s_well: (u8, u8), d_well: (u8, u8)) -> RecordWithWells {
+        let record = TransferRecord {
+            source_plate: source_plate.to_string(),
+            source_well: String::new(),
+            destination_plate: "D".to_string(),
+            destination_well: String::new(),
+            volume: 2.5,
+            concentration: None,
+            source_plate_type: String::new(),
+            destination_plate_type: String::new(),
+        };
+        (record, s_well, d_well)
+    }
+
+    #[test]
+    #[wasm_bindgen_test]
+    fn test_order_records() {
+        let records = vec![
+            record("S1", (1, 2), (1, 1)),
+            record("S2", (1, 1), (1, 2)),
+            record("S1", (2, 1), (1, 3)),
+            record("S1", (2, 2), (1, 4)),
+            record("S1", (1, 1), (1, 5)),
+        ];
+        let order = |ordering| {
+            let mut records = records.clone();
+            order_records(&mut records, ordering);
+            records
+                .iter()
+                .map(|(r, s, _)| (r.source_plate.clone(), *s))
+                .collect::<Vec<_>>()
+        };
+        let s1 = |well| ("S1".to_string(), well);
+        let s2 = |well| ("S2".to_string(), well);
+        assert_eq!(
+            order(TransferOrdering::Serpentine),
+            vec![s1((1, 1)), s1((1, 2)), s1((2, 2)), s1((2, 1)), s2((1, 1))]
+        );
+        assert_eq!(
+            order(TransferOrdering::ColumnMajor),
+            vec![s1((1, 1)), s1((2, 1)), s1((1, 2)), s1((2, 2)), s2((1, 1))]
+        );
+        assert_eq!(
+            order(TransferOrdering::AsEntered),
+            vec![s1((1, 2)), s2((1, 1)), s1((2, 1)), s1((2, 2)), s1((1, 1))]
+        );
+    }
+
+    #[test]
+    #[wasm_bindgen_test]
+    fn test_plate_type_columns() {
+        use crate::data::labware::PlateChemistry;
+        use crate::data::plate::PlateType;
+        use crate::data::plate_instances::PlateInstance;
+        use crate::data::transfer_region::Region;
+
+        let rack = CustomFormat::new("Rack".to_string(), 8, 3, 1500.0, 50.0, "TR24".to_string());
+        let mut source = PlateInstance::new(PlateType::Source, PlateFormat::W384, "S".to_string());
+        source.plate.chemistry = PlateChemistry::PP;
+        let dest = PlateInstance::new(PlateType::Destination, rack.format(), "D".to_string());
+        let mut transfer = Transfer::new(
+            source.clone(),
+            dest.clone(),
+            Default::default(),
+            "T".to_string(),
+        );
+        transfer.transfer_region.source_region = Region::Point((1, 1));
+        transfer.transfer_region.dest_region = Region::Point((2, 3));
+
+        let mut state = MainState::default();
+        state.add_source_plate(source);
+        state.add_dest_plate(dest);
+        state.add_custom_format(rack);
+        state.transfers.push(transfer);
+        let records = state_to_records(&state, &CsvExportOptions::default()).unwrap();
+        assert_eq!(records[0].source_plate_type, "384PP");
+        assert_eq!(records[0].destination_plate_type, "TR24");
+        assert_eq!(records[0].destination_well, "B3");
+
+        // A format that has gone missing leaves the column blank
+        state.custom_formats.clear();
+        let records = state_to_records(&state, &CsvExportOptions::default()).unwrap();
+        assert_eq!(records[0].destination_plate_type, "");
+    }
+}
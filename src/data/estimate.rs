@@ -0,0 +1,171 @@
+// Rough run-time estimate for a picklist.
+//
+// Works from the exported rows, so it follows whatever ordering was
+// chosen for export. Times are in seconds, volumes in nL.
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use super::csv::{state_to_records, CsvExportOptions, TransferRecord};
+use crate::components::states::MainState;
+
+#[derive(PartialEq, Clone, Copy, Serialize, Deserialize, JsonSchema, Debug)]
+pub struct TimingParameters {
+    pub droplet_volume: f32,
+    pub seconds_per_droplet: f32,
+    pub seconds_per_well: f32,
+    pub seconds_per_plate_swap: f32,
+}
+
+impl Default for TimingParameters {
+    fn default() -> Self {
+        // Ballpark figures for an acoustic dispenser
+        Self {
+            droplet_volume: 2.5,
+            seconds_per_droplet: 0.002,
+            seconds_per_well: 0.1,
+            seconds_per_plate_swap: 20.0,
+        }
+    }
+}
+
+#[derive(PartialEq, Clone, Debug, Default)]
+pub struct RunEstimate {
+    pub total_seconds: f32,
+    // Every time the source or destination plate changes,
+    // counting the first pair being loaded
+    pub plate_swaps: usize,
+    pub well_moves: usize,
+    pub droplets: u64,
+    pub droplets_per_source: BTreeMap<String, u64>,
+    pub droplets_per_destination: BTreeMap<String, u64>,
+}
+
+pub fn estimate_run(records: &[TransferRecord], params: &TimingParameters) -> RunEstimate {
+    let mut estimate = RunEstimate::default();
+    let mut loaded: Option<(&str, &str)> = None;
+    for record in records {
+        let pair = (
+            record.source_plate.as_str(),
+            record.destination_plate.as_str(),
+        );
+        if let Some(loaded) = loaded {
+            estimate.plate_swaps += (loaded.0 != pair.0) as usize + (loaded.1 != pair.1) as usize;
+        } else {
+            estimate.plate_swaps += 2;
+        }
+        loaded = Some(pair);
+
+        let droplets = if params.droplet_volume > 0.0 {
+            (record.volume / params.droplet_volume).ceil() as u64
+        } else {
+            0
+        };
+        estimate.well_moves += 1;
+        estimate.droplets += droplets;
+        *estimate
+            .droplets_per_source
+            .entry(record.source_plate.clone())
+            .or_default() += droplets;
+        *estimate
+            .droplets_per_destination
+            .entry(record.destination_plate.clone())
+            .or_default() += droplets;
+    }
+    estimate.total_seconds = estimate.droplets as f32 * params.seconds_per_droplet
+        + estimate.well_moves as f32 * params.seconds_per_well
+        + estimate.plate_swaps as f32 * params.seconds_per_plate_swap;
+    estimate
+}
+
+/// Estimates the whole project, with the row order chosen for CSV export.
+pub fn estimate_state(state: &MainState, params: &TimingParameters) -> Result<RunEstimate, String> {
+    let options = CsvExportOptions {
+        well_style: state.preferences.export_well_style,
+        ordering: state.preferences.export_ordering,
+    };
+    state_to_records(state, &options)
+        .map(|records| estimate_run(&records, params))
+        .map_err(|e| e.to_string())
+}
+
+pub fn format_duration(seconds: f32) -> String {
+    let seconds = seconds.round() as u64;
+    match (seconds / 3600, seconds % 3600 / 60, seconds % 60) {
+        (0, 0, s) => format!("{}s", s),
+        (0, m, s) => format!("{}m {}s", m, s),
+        (h, m, s) => format!("{}h {}m {}s", h, m, s),
+    }
+}
+
+impl fmt::Display for RunEstimate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "Estimated run time: {}",
+            format_duration(self.total_seconds)
+        )?;
+        writeln!(f, "Plate swaps: {}", self.plate_swaps)?;
+        writeln!(f, "Wells visited: {}", self.well_moves)?;
+        write!(f, "Droplets: {}", self.droplets)?;
+        for (heading, counts) in [
+            ("Source Plate", &self.droplets_per_source),
+            ("Destination Plate", &self.droplets_per_destination),
+        ] {
+            write!(f, "\n\n{}\tDroplets", heading)?;
+            for (name, droplets) in counts {
+                write!(f, "\n{}\t{}", name, droplets)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use wasm_bindgen_test::*;
+
+    use super::*;
+
+    fn record(source_plate: &str, destination_plate: &str, volume: f32) -> TransferRecord {
+        TransferRecord {
+            source_plate: source_plate.to_string(),
+            source_well: "A1".to_string(),
+            destination_plate: destination_plate.to_string(),
+            destination_well: "A1".to_string(),
+            volume,
+            concentration: None,
+            source_plate_type: String::new(),
+            destination_plate_type: String::new(),
+        }
+    }
+
+    #[test]
+    #[wasm_bindgen_test]
+    fn test_estimate_run() {
+        let records = vec![
+            record("S1", "D1", 5.0),
+            record("S1", "D1", 6.0),
+            record("S2", "D1", 2.5),
+            record("S1", "D2", 2.5),
+        ];
+        let params = TimingParameters {
+            droplet_volume: 2.5,
+            seconds_per_droplet: 1.0,
+            seconds_per_well: 10.0,
+            seconds_per_plate_swap: 100.0,
+        };
+        let estimate = estimate_run(&records, &params);
+        assert_eq!(estimate.plate_swaps, 5);
+        assert_eq!(estimate.well_moves, 4);
+        assert_eq!(estimate.droplets, 2 + 3 + 1 + 1);
+        assert_eq!(estimate.droplets_per_source["S1"], 6);
+        assert_eq!(estimate.droplets_per_destination["D2"], 1);
+        assert_eq!(estimate.total_seconds, 7.0 + 40.0 + 500.0);
+
+        assert_eq!(estimate_run(&[], &params).total_seconds, 0.0);
+    }
+}
@@ -0,0 +1,172 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use uuid::Uuid;
+
+use super::plate_instances::{plate_mask, PlateInstance};
+use super::transfer::Transfer;
+
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug, Serialize, Deserialize, JsonSchema)]
+pub enum Overlay {
+    // Color wells by the last transfer to use them
+    #[default]
+    Transfers,
+    DestinationVolume,
+    Concentration,
+    SourceUsage,
+}
+
+impl Overlay {
+    pub const ALL: [Overlay; 4] = [
+        Overlay::Transfers,
+        Overlay::DestinationVolume,
+        Overlay::Concentration,
+        Overlay::SourceUsage,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Overlay::Transfers => "Transfers",
+            Overlay::DestinationVolume => "Volume received",
+            Overlay::Concentration => "Final concentration",
+            Overlay::SourceUsage => "Volume drawn",
+        }
+    }
+
+    pub fn unit(&self) -> &'static str {
+        match self {
+            Overlay::Transfers => "",
+            Overlay::DestinationVolume | Overlay::SourceUsage => "nL",
+            Overlay::Concentration => "µM",
+        }
+    }
+}
+
+pub fn destination_volumes<'a>(
+    transfers: impl IntoIterator<Item = &'a Transfer>,
+    dest_plates: &[PlateInstance],
+    dest_id: Uuid,
+) -> HashMap<(u8, u8), f32> {
+    // Total nL landing in each well, from the same pairs used for export
+    let mask = plate_mask(dest_plates, dest_id);
+    let mut volumes: HashMap<(u8, u8), f32> = HashMap::new();
+    for transfer in transfers.into_iter().filter(|t| t.dest_id == dest_id) {
+        for (_, d_well, volume) in transfer
+            .transfer_region
+            .get_transfer_pairs(transfer.volume, mask)
+        {
+            *volumes.entry(d_well).or_default() += volume;
+        }
+    }
+    volumes
+}
+
+pub fn source_usage(
+    transfers: &[Transfer],
+    dest_plates: &[PlateInstance],
+    source_id: Uuid,
+) -> HashMap<(u8, u8), f32> {
+    // Total nL drawn from each well
+    let mut volumes: HashMap<(u8, u8), f32> = HashMap::new();
+    for transfer in transfers.iter().filter(|t| t.source_id == source_id) {
+        let mask = plate_mask(dest_plates, transfer.dest_id);
+        for (s_well, _, volume) in transfer
+            .transfer_region
+            .get_transfer_pairs(transfer.volume, mask)
+        {
+            *volumes.entry(s_well).or_default() += volume;
+        }
+    }
+    volumes
+}
+
+pub fn destination_concentrations(
+    transfers: &[Transfer],
+    source_plates: &[PlateInstance],
+    dest: &PlateInstance,
+) -> HashMap<(u8, u8), f32> {
+    // µM in each well, diluted into the plate's final well volume if it is known,
+    // otherwise into the transferred liquid alone.
+    // Wells receiving from a plate of unknown concentration are left out.
+    let mut amounts: HashMap<(u8, u8), Option<(f32, f32)>> = HashMap::new();
+    for transfer in transfers.iter().filter(|t| t.dest_id == dest.get_uuid()) {
+        let concentration = source_plates
+            .iter()
+            .find(|spi| spi.get_uuid() == transfer.source_id)
+            .and_then(|spi| spi.concentration);
+        for (_, d_well, volume) in transfer
+            .transfer_region
+            .get_transfer_pairs(transfer.volume, &dest.mask)
+        {
+            let entry = amounts.entry(d_well).or_insert(Some((0.0, 0.0)));
+            *entry = match (*entry, concentration) {
+                (Some((amount, total)), Some(c)) => Some((amount + c * volume, total + volume)),
+                _ => None,
+            };
+        }
+    }
+    amounts
+        .into_iter()
+        .filter_map(|(well, amount)| {
+            let (amount, total) = amount?;
+            let total = dest.well_volume.map(|v| v * 1000.0).unwrap_or(total);
+            (total > 0.0).then_some((well, amount / total))
+        })
+        .collect()
+}
+
+pub fn value_range(values: &HashMap<(u8, u8), f32>) -> Option<(f32, f32)> {
+    let min = values.values().copied().reduce(f32::min)?;
+    let max = values.values().copied().reduce(f32::max)?;
+    Some((min, max))
+}
+
+#[cfg(test)]
+mod tests {
+    use wasm_bindgen_test::*;
+
+    use super::*;
+    use crate::data::plate::{PlateFormat, PlateType};
+    use crate::data::transfer_region::{Region, TransferRegion};
+
+    #[test]
+    #[wasm_bindgen_test]
+    fn test_overlay_values() {
+        let mut source = PlateInstance::new(PlateType::Source, PlateFormat::W384, "S".to_string());
+        source.concentration = Some(1000.0);
+        let mut dest =
+            PlateInstance::new(PlateType::Destination, PlateFormat::W96, "D".to_string());
+        // A1:B1 into B1, pooled (zero row interleave), twice over
+        let region = TransferRegion {
+            source_plate: source.plate,
+            dest_plate: dest.plate,
+            source_region: Region::Rect((1, 1), (2, 1)),
+            dest_region: Region::Point((2, 1)),
+            interleave_source: (1, 1),
+            interleave_dest: (0, 1),
+            fill_order: Default::default(),
+        };
+        let mut transfer = Transfer::new(source.clone(), dest.clone(), region, "T".to_string());
+        transfer.volume = 5.0;
+        let transfers = vec![transfer.clone(), transfer];
+
+        let dests = vec![dest.clone()];
+        let received = destination_volumes(&transfers, &dests, dest.get_uuid());
+        assert_eq!(received.get(&(2, 1)), Some(&20.0));
+        let drawn = source_usage(&transfers, &dests, source.get_uuid());
+        assert_eq!(drawn.get(&(1, 1)), Some(&10.0));
+        assert_eq!(value_range(&drawn), Some((10.0, 10.0)));
+
+        let sources = vec![source.clone()];
+        let neat = destination_concentrations(&transfers, &sources, &dest);
+        assert_eq!(neat.get(&(2, 1)), Some(&1000.0));
+        // 20 nL into 20 µL is a thousandfold dilution
+        dest.well_volume = Some(20.0);
+        let diluted = destination_concentrations(&transfers, &sources, &dest);
+        assert_eq!(diluted.get(&(2, 1)), Some(&1.0));
+
+        source.concentration = None;
+        let unknown = destination_concentrations(&transfers, &[source], &dest);
+        assert!(unknown.is_empty());
+    }
+}
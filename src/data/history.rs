@@ -0,0 +1,177 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use uuid::Uuid;
+
+use crate::components::states::MainState;
+use crate::data::schema::versioned;
+
+// Oldest snapshots are dropped past this many
+pub const MAX_SNAPSHOTS: usize = 30;
+
+#[derive(PartialEq, Clone, Serialize, Deserialize)]
+pub struct Snapshot {
+    // Milliseconds since the epoch, as from Date.now()
+    pub time: f64,
+    // Why it was taken, e.g. "Autosave" or "Before import"
+    pub reason: String,
+    // Versioned, so snapshots from older versions are upgraded when loaded
+    #[serde(with = "versioned")]
+    pub state: MainState,
+}
+
+/// Adds a snapshot to the end of `history`, keeping at most `MAX_SNAPSHOTS`.
+/// Returns false if nothing changed since the latest snapshot.
+pub fn push_snapshot(history: &mut Vec<Snapshot>, snapshot: Snapshot) -> bool {
+    if history.last().map(|s| &s.state) == Some(&snapshot.state) {
+        return false;
+    }
+    history.push(snapshot);
+    if history.len() > MAX_SNAPSHOTS {
+        history.drain(..history.len() - MAX_SNAPSHOTS);
+    }
+    true
+}
+
+/// Describes what changed between `old` and `new`, one line per change.
+pub fn diff_summary(old: &MainState, new: &MainState) -> Vec<String> {
+    let mut changes = Vec::new();
+
+    let plates = |state: &MainState| -> HashMap<Uuid, String> {
+        state
+            .source_plates
+            .iter()
+            .chain(state.destination_plates.iter())
+            .map(|pi| (pi.get_uuid(), pi.name.clone()))
+            .collect()
+    };
+    let (old_plates, new_plates) = (plates(old), plates(new));
+    for pi in new
+        .source_plates
+        .iter()
+        .chain(new.destination_plates.iter())
+    {
+        match old_plates.get(&pi.get_uuid()) {
+            None => changes.push(format!("Added plate {}", pi.name)),
+            Some(name) if *name != pi.name => {
+                changes.push(format!("Renamed plate {} to {}", name, pi.name))
+            }
+            _ => (),
+        }
+    }
+    for pi in old
+        .source_plates
+        .iter()
+        .chain(old.destination_plates.iter())
+    {
+        if !new_plates.contains_key(&pi.get_uuid()) {
+            changes.push(format!("Removed plate {}", pi.name));
+        }
+    }
+
+    for t in new.transfers.iter() {
+        match old.transfers.iter().find(|o| o.get_uuid() == t.get_uuid()) {
+            None => changes.push(format!("Added transfer {}", t.name)),
+            Some(o) if o != t => changes.push(format!("Changed transfer {}", t.name)),
+            _ => (),
+        }
+    }
+    for t in old.transfers.iter() {
+        if !new.transfers.iter().any(|n| n.get_uuid() == t.get_uuid()) {
+            changes.push(format!("Removed transfer {}", t.name));
+        }
+    }
+    changes
+}
+
+#[cfg(test)]
+mod tests {
+    use wasm_bindgen_test::*;
+
+    use super::*;
+    use crate::data::plate::{PlateFormat, PlateType};
+    use crate::data::plate_instances::PlateInstance;
+    use crate::data::transfer::Transfer;
+
+    #[test]
+    #[wasm_bindgen_test]
+    fn test_push_snapshot() {
+        let mut history = Vec::new();
+        let snapshot = |n: usize| {
+            let mut state = MainState::default();
+            for _ in 0..n {
+                state.add_source_plate(PlateInstance::new(
+                    PlateType::Source,
+                    PlateFormat::W96,
+                    "S".to_string(),
+                ));
+            }
+            Snapshot {
+                time: n as f64,
+                reason: "Autosave".to_string(),
+                state,
+            }
+        };
+        assert!(push_snapshot(&mut history, snapshot(0)));
+        // Nothing changed
+        assert!(!push_snapshot(&mut history, snapshot(0)));
+        for n in 1..=MAX_SNAPSHOTS {
+            push_snapshot(&mut history, snapshot(n));
+        }
+        assert_eq!(history.len(), MAX_SNAPSHOTS);
+        assert_eq!(history[0].time, 1.0);
+    }
+
+    #[test]
+    #[wasm_bindgen_test]
+    fn test_snapshot_versioned() {
+        let snapshot = Snapshot {
+            time: 1.0,
+            reason: "Manual".to_string(),
+            state: MainState::default(),
+        };
+        let json = serde_json::to_string(&snapshot).unwrap();
+        assert!(json.contains("\"version\":"));
+        assert!(serde_json::from_str::<Snapshot>(&json).unwrap() == snapshot);
+
+        // Stored before snapshots were versioned
+        let old = r#"{"time": 1.0, "reason": "Autosave", "state": {
+            "source_plates": [], "destination_plates": [], "transfers": [],
+            "selected_source_plate": "00000000-0000-0000-0000-000000000000",
+            "selected_dest_plate": "00000000-0000-0000-0000-000000000000",
+            "selected_transfer": "00000000-0000-0000-0000-000000000000"}}"#;
+        assert!(serde_json::from_str::<Snapshot>(old).unwrap().state == MainState::default());
+    }
+
+    #[test]
+    #[wasm_bindgen_test]
+    fn test_diff_summary() {
+        let source = PlateInstance::new(PlateType::Source, PlateFormat::W96, "S".to_string());
+        let dest = PlateInstance::new(PlateType::Destination, PlateFormat::W96, "D".to_string());
+        let mut old = MainState::default();
+        old.add_source_plate(source.clone());
+        old.add_dest_plate(dest.clone());
+        let kept = Transfer::new(
+            source.clone(),
+            dest.clone(),
+            Default::default(),
+            "Kept".to_string(),
+        );
+        let dropped = Transfer::new(source, dest.clone(), Default::default(), "Gone".to_string());
+        old.transfers = vec![kept.clone(), dropped];
+
+        let mut new = old.clone();
+        assert!(diff_summary(&old, &new).is_empty());
+
+        new.rename_plate(dest.get_uuid(), "D2");
+        new.transfers = vec![kept];
+        new.transfers[0].volume = 10.0;
+        assert_eq!(
+            diff_summary(&old, &new),
+            vec![
+                "Renamed plate D to D2".to_string(),
+                "Changed transfer Kept".to_string(),
+                "Removed transfer Gone".to_string(),
+            ]
+        );
+    }
+}
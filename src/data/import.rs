@@ -0,0 +1,176 @@
+use super::transfer_region::{Corners, Region};
+use super::well_name::parse_well_name;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ImportKind {
+    // A project saved with "Export as JSON"
+    Project,
+    // One transfer per line, including Plate Tool's own CSV export
+    PickList,
+    // A grid with a row per plate row and a column per plate column
+    PlateMap,
+}
+
+impl ImportKind {
+    pub fn label(&self) -> &'static str {
+        match self {
+            ImportKind::Project => "Plate Tool project",
+            ImportKind::PickList => "Pick list",
+            ImportKind::PlateMap => "Plate map",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlateMap {
+    // Rows and columns covered by the map
+    pub size: (u8, u8),
+    // Only the wells with something in them
+    pub wells: Vec<((u8, u8), String)>,
+}
+
+pub fn detect_import(text: &str) -> ImportKind {
+    if text.trim_start().starts_with('{') {
+        ImportKind::Project
+    } else if parse_plate_map(text).is_ok() {
+        ImportKind::PlateMap
+    } else {
+        ImportKind::PickList
+    }
+}
+
+pub fn parse_plate_map(data: &str) -> Result<PlateMap, String> {
+    // The header row holds the column numbers (after one corner cell),
+    // and every other row starts with its row letter.
+    let mut rdr = csv::ReaderBuilder::new()
+        .has_headers(false)
+        .flexible(true)
+        .trim(csv::Trim::All)
+        .from_reader(data.as_bytes());
+    let mut records = rdr.records();
+    let header = records
+        .next()
+        .ok_or("The file is empty")?
+        .map_err(|e| e.to_string())?;
+    let columns = header
+        .iter()
+        .skip(1)
+        .enumerate()
+        .map(|(index, field)| match field.parse::<u8>() {
+            Ok(col) if col as usize == index + 1 => Ok(col),
+            _ => Err(format!("\"{}\" is not column {}", field, index + 1)),
+        })
+        .collect::<Result<Vec<u8>, String>>()?;
+    if columns.is_empty() {
+        return Err("No column numbers in the first row".to_string());
+    }
+
+    let mut wells = Vec::new();
+    let mut rows = 0;
+    for (index, record) in records.enumerate() {
+        let record = record.map_err(|e| e.to_string())?;
+        if record.iter().all(|field| field.is_empty()) {
+            continue;
+        }
+        let label = record.get(0).unwrap_or("");
+        let row = match parse_well_name(&format!("{}1", label)) {
+            Some((row, _)) if label.chars().all(|c| c.is_ascii_alphabetic()) => row,
+            _ => {
+                return Err(format!(
+                    "Line {}: \"{}\" is not a row letter",
+                    index + 2,
+                    label
+                ))
+            }
+        };
+        rows = u8::max(rows, row);
+        for (col, field) in record.iter().skip(1).enumerate().take(columns.len()) {
+            if !field.is_empty() {
+                wells.push(((row, col as u8 + 1), field.to_string()));
+            }
+        }
+    }
+    if rows == 0 {
+        return Err("No rows found".to_string());
+    }
+    Ok(PlateMap {
+        size: (rows, columns.len() as u8),
+        wells,
+    })
+}
+
+pub fn wells_to_region(wells: &[(u8, u8)]) -> Region {
+    // Runs of neighbouring wells along each row become rectangles
+    let mut wells = wells.to_vec();
+    wells.sort_unstable();
+    wells.dedup();
+    let mut rects: Vec<Corners> = Vec::new();
+    for well in wells {
+        match rects.last_mut() {
+            Some((_, end)) if end.0 == well.0 && end.1 + 1 == well.1 => *end = well,
+            _ => rects.push((well, well)),
+        }
+    }
+    match rects.as_slice() {
+        [] => Region::default(),
+        [(c1, c2)] if c1 == c2 => Region::Point(*c1),
+        [(c1, c2)] => Region::Rect(*c1, *c2),
+        _ => Region::Multi(rects),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use wasm_bindgen_test::*;
+
+    use super::*;
+
+    #[test]
+    #[wasm_bindgen_test]
+    fn test_detect_import() {
+        assert_eq!(
+            detect_import("  {\"source_plates\": []}"),
+            ImportKind::Project
+        );
+        assert_eq!(
+            detect_import("Source Plate,Source Well,Dest Plate,Destination Well\nP1,A1,P2,B2\n"),
+            ImportKind::PickList
+        );
+        assert_eq!(
+            detect_import(",1,2,3\nA,ctrl,,s1\nB,,s2,\n"),
+            ImportKind::PlateMap
+        );
+    }
+
+    #[test]
+    #[wasm_bindgen_test]
+    fn test_parse_plate_map() {
+        let map = parse_plate_map("Row,1,2,3\nA,ctrl,,s1\nB,,s2,\n\n").unwrap();
+        assert_eq!(map.size, (2, 3));
+        assert_eq!(
+            map.wells,
+            vec![
+                ((1, 1), "ctrl".to_string()),
+                ((1, 3), "s1".to_string()),
+                ((2, 2), "s2".to_string()),
+            ]
+        );
+        // Columns must count up from 1
+        assert!(parse_plate_map(",1,3\nA,x,y\n").is_err());
+        assert!(parse_plate_map(",1,2\n7,x,y\n").is_err());
+    }
+
+    #[test]
+    #[wasm_bindgen_test]
+    fn test_wells_to_region() {
+        assert_eq!(wells_to_region(&[(2, 3)]), Region::Point((2, 3)));
+        assert_eq!(
+            wells_to_region(&[(1, 3), (1, 1), (1, 2)]),
+            Region::Rect((1, 1), (1, 3))
+        );
+        assert_eq!(
+            wells_to_region(&[(1, 1), (1, 2), (2, 5)]),
+            Region::Multi(vec![((1, 1), (1, 2)), ((2, 5), (2, 5))])
+        );
+    }
+}
@@ -0,0 +1,131 @@
+// Built-in labware catalogue.
+//
+// Well volumes are in µL, transfer volumes (like Transfer.volume) in nL,
+// matching what ends up in an Echo picklist.
+// These are nominal figures; adjust them here if your instrument's
+// calibration says otherwise.
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use super::plate::{CustomFormat, PlateFormat};
+
+#[allow(clippy::upper_case_acronyms)] // Named after the vendor plate types
+#[derive(PartialEq, Eq, Clone, Copy, Serialize, Deserialize, JsonSchema, Debug)]
+pub enum PlateChemistry {
+    Standard,
+    PP,
+    LDV,
+    Reservoir,
+}
+impl Default for PlateChemistry {
+    fn default() -> Self {
+        Self::Standard
+    }
+}
+
+impl std::fmt::Display for PlateChemistry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PlateChemistry::Standard => write!(f, "Standard"),
+            PlateChemistry::PP => write!(f, "PP"),
+            PlateChemistry::LDV => write!(f, "LDV"),
+            PlateChemistry::Reservoir => write!(f, "RES"),
+        }
+    }
+}
+
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub struct Labware {
+    pub name: &'static str,
+    pub format: PlateFormat,
+    pub chemistry: PlateChemistry,
+    pub max_volume: f32,
+    pub dead_volume: f32,
+    pub min_transfer: Option<f32>,
+    pub max_transfer: Option<f32>,
+}
+
+impl Labware {
+    const fn standard(name: &'static str, format: PlateFormat, max_volume: f32) -> Self {
+        Labware {
+            name,
+            format,
+            chemistry: PlateChemistry::Standard,
+            max_volume,
+            dead_volume: 0.0,
+            min_transfer: None,
+            max_transfer: None,
+        }
+    }
+
+    // A custom format's own well volumes, with no transfer limits
+    pub fn custom(format: PlateFormat, custom_format: &CustomFormat) -> Self {
+        Labware {
+            name: "Custom",
+            format,
+            chemistry: PlateChemistry::Standard,
+            max_volume: custom_format.well_volume,
+            dead_volume: custom_format.dead_volume,
+            min_transfer: None,
+            max_transfer: None,
+        }
+    }
+
+    pub fn usable_volume(&self) -> f32 {
+        self.max_volume - self.dead_volume
+    }
+}
+
+pub const LABWARE: &[Labware] = &[
+    Labware::standard("6", PlateFormat::W6, 3000.0),
+    Labware::standard("12", PlateFormat::W12, 2000.0),
+    Labware::standard("24", PlateFormat::W24, 1000.0),
+    Labware::standard("48", PlateFormat::W48, 500.0),
+    Labware::standard("96", PlateFormat::W96, 300.0),
+    Labware::standard("384", PlateFormat::W384, 80.0),
+    Labware::standard("1536", PlateFormat::W1536, 10.0),
+    Labware::standard("3456", PlateFormat::W3456, 2.0),
+    Labware {
+        name: "384PP",
+        format: PlateFormat::W384,
+        chemistry: PlateChemistry::PP,
+        max_volume: 65.0,
+        dead_volume: 15.0,
+        min_transfer: Some(2.5),
+        max_transfer: Some(10000.0),
+    },
+    Labware {
+        name: "384LDV",
+        format: PlateFormat::W384,
+        chemistry: PlateChemistry::LDV,
+        max_volume: 12.0,
+        dead_volume: 2.5,
+        min_transfer: Some(2.5),
+        max_transfer: Some(9500.0),
+    },
+    Labware {
+        name: "1536LDV",
+        format: PlateFormat::W1536,
+        chemistry: PlateChemistry::LDV,
+        max_volume: 5.0,
+        dead_volume: 1.0,
+        min_transfer: Some(2.5),
+        max_transfer: Some(4000.0),
+    },
+    Labware {
+        name: "6RES",
+        format: PlateFormat::W6,
+        chemistry: PlateChemistry::Reservoir,
+        max_volume: 2800.0,
+        dead_volume: 250.0,
+        min_transfer: Some(2.5),
+        max_transfer: Some(10000.0),
+    },
+];
+
+pub fn get_labware(format: PlateFormat, chemistry: PlateChemistry) -> Option<&'static Labware> {
+    LABWARE
+        .iter()
+        .find(|lw| lw.format == format && lw.chemistry == chemistry)
+}
@@ -0,0 +1,315 @@
+// Checks run over the whole project before export.
+//
+// Errors are things that would produce a wrong or unusable picklist;
+// warnings are worth a look but may well be intended.
+
+use std::collections::{BTreeMap, HashSet};
+
+use uuid::Uuid;
+
+use crate::components::states::MainState;
+use crate::data::plate_instances::{plate_mask, PlateInstance};
+use crate::data::transfer::Transfer;
+use crate::data::transfer_region::Region;
+use crate::data::well_name::{format_well, WellNameStyle};
+
+#[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Debug)]
+pub enum LintSeverity {
+    Error,
+    Warning,
+}
+
+#[derive(PartialEq, Clone, Debug)]
+pub struct LintIssue {
+    pub severity: LintSeverity,
+    pub message: String,
+}
+
+impl LintIssue {
+    fn error(message: String) -> Self {
+        Self {
+            severity: LintSeverity::Error,
+            message,
+        }
+    }
+    fn warning(message: String) -> Self {
+        Self {
+            severity: LintSeverity::Warning,
+            message,
+        }
+    }
+}
+
+pub fn has_errors(issues: &[LintIssue]) -> bool {
+    issues.iter().any(|i| i.severity == LintSeverity::Error)
+}
+
+pub fn lint_state(state: &MainState, current: Option<&Transfer>) -> Vec<LintIssue> {
+    // `current` is the transfer being edited, to catch unsaved changes
+    let mut issues = Vec::new();
+    let find_plate = |plates: &[PlateInstance], id: Uuid| -> Option<PlateInstance> {
+        plates.iter().find(|pi| pi.get_uuid() == id).cloned()
+    };
+
+    // Per-well totals in nL, keyed by plate then well
+    let mut dest_totals: BTreeMap<Uuid, BTreeMap<(u8, u8), f32>> = BTreeMap::new();
+    let mut source_totals: BTreeMap<Uuid, BTreeMap<(u8, u8), f32>> = BTreeMap::new();
+    let mut used_plates: HashSet<Uuid> = HashSet::new();
+
+    for transfer in &state.transfers {
+        let spi = find_plate(&state.source_plates, transfer.source_id);
+        let dpi = find_plate(&state.destination_plates, transfer.dest_id);
+        if spi.is_none() || dpi.is_none() {
+            issues.push(LintIssue::error(format!(
+                "Transfer \"{}\" refers to a plate that no longer exists",
+                transfer.name
+            )));
+        }
+        used_plates.insert(transfer.source_id);
+        used_plates.insert(transfer.dest_id);
+
+        let mask = plate_mask(&state.destination_plates, transfer.dest_id);
+        let pairs = transfer
+            .transfer_region
+            .get_transfer_pairs(transfer.volume, mask);
+        if pairs.is_empty() {
+            issues.push(LintIssue::error(format!(
+                "Transfer \"{}\" does not transfer into any wells",
+                transfer.name
+            )));
+        }
+
+        let labware = spi
+            .as_ref()
+            .and_then(|spi| spi.plate.labware(&state.custom_formats).ok());
+        let out_of_limits = pairs.iter().filter(|(_, _, volume)| {
+            *volume <= 0.0
+                || labware.is_some_and(|lw| {
+                    lw.min_transfer.is_some_and(|min| *volume < min)
+                        || lw.max_transfer.is_some_and(|max| *volume > max)
+                })
+        });
+        if let Some((_, _, volume)) = out_of_limits.clone().next() {
+            issues.push(LintIssue::error(format!(
+                "Transfer \"{}\" has {} transfers outside the instrument's volume limits (e.g. {} nL)",
+                transfer.name,
+                out_of_limits.count(),
+                volume
+            )));
+        }
+
+        if let Some(dpi) = &dpi {
+            // Other regions are packed around the excluded wells,
+            // custom pairs into them are left out of the picklist
+            let masked: Vec<(u8, u8)> = match transfer.transfer_region.dest_region {
+                Region::Custom(_) => transfer
+                    .transfer_region
+                    .get_destination_wells(&[])
+                    .into_iter()
+                    .filter(|w| dpi.mask.contains(w))
+                    .collect(),
+                _ => vec![],
+            };
+            if let Some(well) = masked.first() {
+                issues.push(LintIssue::error(format!(
+                    "Transfer \"{}\" writes into {} excluded wells on \"{}\" (e.g. {})",
+                    transfer.name,
+                    masked.len(),
+                    dpi.name,
+                    format_well(*well, dpi.plate.size(), WellNameStyle::Letters)
+                )));
+            } else if let Err(e) = transfer.transfer_region.validate_mask(&dpi.mask) {
+                issues.push(LintIssue::error(format!(
+                    "Transfer \"{}\" on \"{}\": {}",
+                    transfer.name, dpi.name, e
+                )));
+            }
+        }
+
+        for (s_well, d_well, volume) in pairs {
+            *source_totals
+                .entry(transfer.source_id)
+                .or_default()
+                .entry(s_well)
+                .or_default() += volume;
+            *dest_totals
+                .entry(transfer.dest_id)
+                .or_default()
+                .entry(d_well)
+                .or_default() += volume;
+        }
+    }
+
+    for (id, totals) in &dest_totals {
+        let Some(dpi) = find_plate(&state.destination_plates, *id) else {
+            continue;
+        };
+        let Ok(labware) = dpi.plate.labware(&state.custom_formats) else {
+            continue;
+        };
+        let max_volume = labware.max_volume;
+        let over: Vec<_> = totals
+            .iter()
+            .filter(|(_, total)| **total / 1000.0 > max_volume)
+            .collect();
+        if let Some((well, total)) = over.first() {
+            issues.push(LintIssue::error(format!(
+                "Destination plate \"{}\" has {} overfilled wells (e.g. {}: {} µL of {} µL)",
+                dpi.name,
+                over.len(),
+                format_well(**well, dpi.plate.size(), WellNameStyle::Letters),
+                **total / 1000.0,
+                max_volume
+            )));
+        }
+    }
+    for (id, totals) in &source_totals {
+        let Some(spi) = find_plate(&state.source_plates, *id) else {
+            continue;
+        };
+        let Ok(labware) = spi.plate.labware(&state.custom_formats) else {
+            continue;
+        };
+        // We don't know how full the wells are, so assume full
+        let usable = labware.usable_volume();
+        let under: Vec<_> = totals
+            .iter()
+            .filter(|(_, total)| **total / 1000.0 > usable)
+            .collect();
+        if let Some((well, total)) = under.first() {
+            issues.push(LintIssue::warning(format!(
+                "Source plate \"{}\" has {} wells asked for more than they can give (e.g. {}: {} µL of {} µL usable)",
+                spi.name,
+                under.len(),
+                format_well(**well, spi.plate.size(), WellNameStyle::Letters),
+                **total / 1000.0,
+                usable
+            )));
+        }
+    }
+
+    for pi in state
+        .source_plates
+        .iter()
+        .chain(state.destination_plates.iter())
+    {
+        if let Err(e) = pi.plate.labware(&state.custom_formats) {
+            issues.push(LintIssue::warning(format!(
+                "Plate \"{}\" can't be checked for volumes. {}",
+                pi.name, e
+            )));
+        }
+        if !used_plates.contains(&pi.get_uuid()) {
+            issues.push(LintIssue::warning(format!(
+                "Plate \"{}\" is not used by any transfer",
+                pi.name
+            )));
+        }
+    }
+
+    // Plate names end up in the picklist, so they must tell plates apart
+    for (kind, plates) in [
+        ("source", &state.source_plates),
+        ("destination", &state.destination_plates),
+    ] {
+        for name in duplicates(plates.iter().map(|pi| pi.name.as_str())) {
+            issues.push(LintIssue::error(format!(
+                "More than one {} plate is named \"{}\"",
+                kind, name
+            )));
+        }
+    }
+    for name in duplicates(state.transfers.iter().map(|t| t.name.as_str())) {
+        issues.push(LintIssue::warning(format!(
+            "More than one transfer is named \"{}\"",
+            name
+        )));
+    }
+
+    if let Some(current) = current {
+        let saved = state
+            .transfers
+            .iter()
+            .find(|t| t.get_uuid() == current.get_uuid());
+        let unsaved = match saved {
+            Some(saved) => saved != current,
+            // A new transfer counts once a region has been picked
+            None => {
+                current.transfer_region.source_region != Default::default()
+                    || current.transfer_region.dest_region != Default::default()
+            }
+        };
+        if unsaved {
+            issues.push(LintIssue::warning(format!(
+                "Transfer \"{}\" has changes that have not been saved",
+                current.name
+            )));
+        }
+    }
+
+    issues.sort_by_key(|i| i.severity);
+    issues
+}
+
+fn duplicates<'a>(names: impl Iterator<Item = &'a str>) -> Vec<&'a str> {
+    let mut seen = HashSet::new();
+    let mut dups = Vec::new();
+    for name in names {
+        if !seen.insert(name) && !dups.contains(&name) {
+            dups.push(name);
+        }
+    }
+    dups
+}
+
+#[cfg(test)]
+mod tests {
+    use wasm_bindgen_test::*;
+
+    use super::*;
+    use crate::data::plate::{PlateFormat, PlateType};
+    use crate::data::transfer_region::{Region, TransferRegion};
+
+    #[test]
+    #[wasm_bindgen_test]
+    fn test_lint_state() {
+        let mut state = MainState::default();
+        let spi = PlateInstance::new(PlateType::Source, PlateFormat::W96, "S".to_string());
+        let dpi = PlateInstance::new(PlateType::Destination, PlateFormat::W96, "D".to_string());
+        let unused = PlateInstance::new(PlateType::Destination, PlateFormat::W96, "D".to_string());
+        let region = TransferRegion {
+            source_plate: spi.plate,
+            dest_plate: dpi.plate,
+            source_region: Region::Point((1, 1)),
+            dest_region: Region::Point((1, 1)),
+            interleave_source: (1, 1),
+            interleave_dest: (1, 1),
+            fill_order: Default::default(),
+        };
+        let mut transfer = Transfer::new(spi.clone(), dpi.clone(), region, "T".to_string());
+        state.source_plates.push(spi);
+        state.destination_plates.push(dpi);
+        state.transfers.push(transfer.clone());
+        assert!(lint_state(&state, None).is_empty());
+
+        // 400 µL into a 300 µL well
+        state.transfers[0].volume = 400_000.0;
+        state.destination_plates.push(unused);
+        let issues = lint_state(&state, Some(&transfer));
+        assert!(has_errors(&issues));
+        let errors: Vec<_> = issues
+            .iter()
+            .filter(|i| i.severity == LintSeverity::Error)
+            .collect();
+        assert_eq!(errors.len(), 2, "{:?}", issues); // Overfill, duplicate name
+        assert!(issues.iter().any(|i| i.message.contains("not used")));
+        assert!(issues.iter().any(|i| i.message.contains("not been saved")));
+        assert_eq!(issues.last().unwrap().severity, LintSeverity::Warning);
+
+        transfer.source_id = Uuid::nil();
+        state.transfers[0] = transfer;
+        assert!(lint_state(&state, None)
+            .iter()
+            .any(|i| i.message.contains("no longer exists")));
+    }
+}
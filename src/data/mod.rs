@@ -1,5 +1,17 @@
+pub mod cherry_pick;
+pub mod conflicts;
+pub mod controls;
 pub mod csv;
+pub mod estimate;
+pub mod heatmap;
+pub mod history;
+pub mod import;
+pub mod labware;
+pub mod lint;
 pub mod plate;
 pub mod plate_instances;
+pub mod randomize;
+pub mod schema;
 pub mod transfer;
 pub mod transfer_region;
+pub mod well_name;
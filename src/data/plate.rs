@@ -1,9 +1,15 @@
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
+use uuid::Uuid;
 
-#[derive(PartialEq, Eq, Default, Clone, Copy, Serialize, Deserialize, Debug)]
+use super::labware::{get_labware, Labware, PlateChemistry};
+
+#[derive(PartialEq, Eq, Default, Clone, Copy, Serialize, Deserialize, JsonSchema, Debug)]
 pub struct Plate {
     pub plate_type: PlateType,
     pub plate_format: PlateFormat,
+    #[serde(default)]
+    pub chemistry: PlateChemistry,
 }
 
 impl Plate {
@@ -11,15 +17,31 @@ impl Plate {
         Plate {
             plate_type,
             plate_format,
+            chemistry: PlateChemistry::default(),
         }
     }
 
     pub fn size(&self) -> (u8, u8) {
         self.plate_format.size()
     }
+
+    /// Custom formats are looked up in `custom_formats`, the project's definitions.
+    pub fn labware(&self, custom_formats: &[CustomFormat]) -> Result<Labware, String> {
+        match self.plate_format {
+            PlateFormat::Custom { id, .. } => custom_formats
+                .iter()
+                .find(|cf| cf.get_uuid() == id)
+                .map(|cf| Labware::custom(self.plate_format, cf))
+                .ok_or("Unknown labware: the plate's custom format is not defined".to_string()),
+            format => get_labware(format, self.chemistry).copied().ok_or(format!(
+                "Unknown labware: there is no {} well plate with {} chemistry",
+                format, self.chemistry
+            )),
+        }
+    }
 }
 
-#[derive(PartialEq, Eq, Clone, Copy, Serialize, Deserialize, Debug)]
+#[derive(PartialEq, Eq, Clone, Copy, Serialize, Deserialize, JsonSchema, Debug)]
 pub enum PlateType {
     Source,
     Destination,
@@ -30,7 +52,7 @@ impl Default for PlateType {
     }
 }
 
-#[derive(PartialEq, Eq, Clone, Copy, Serialize, Deserialize, Debug)]
+#[derive(PartialEq, Eq, Clone, Copy, Serialize, Deserialize, JsonSchema, Debug)]
 pub enum PlateFormat {
     W6,
     W12,
@@ -40,6 +62,7 @@ pub enum PlateFormat {
     W384,
     W1536,
     W3456,
+    Custom { id: Uuid, rows: u8, columns: u8 },
 }
 impl Default for PlateFormat {
     fn default() -> Self {
@@ -57,6 +80,8 @@ impl std::fmt::Display for PlateFormat {
             PlateFormat::W384 => write!(f, "384"),
             PlateFormat::W1536 => write!(f, "1536"),
             PlateFormat::W3456 => write!(f, "3456"),
+            // Also used for the "W{format}" CSS class, so keep it free of spaces
+            PlateFormat::Custom { rows, columns, .. } => write!(f, "{}x{}", rows, columns),
         }
     }
 }
@@ -72,6 +97,90 @@ impl PlateFormat {
             PlateFormat::W384 => (16, 24),
             PlateFormat::W1536 => (32, 48),
             PlateFormat::W3456 => (48, 72),
+            PlateFormat::Custom { rows, columns, .. } => (*rows, *columns),
+        }
+    }
+}
+
+/// A user-defined labware definition, for geometries not covered by the
+/// built-in formats (reservoirs, tube racks, vendor-specific plates...).
+///
+/// Definitions live in MainState; a plate using one only carries its
+/// dimensions and id (see `PlateFormat::Custom`).
+#[derive(PartialEq, Clone, Serialize, Deserialize, JsonSchema, Debug)]
+pub struct CustomFormat {
+    #[serde(rename = "id_v7")]
+    id: Uuid,
+    pub name: String,
+    pub rows: u8,
+    pub columns: u8,
+    pub well_volume: f32, // µL
+    pub dead_volume: f32, // µL
+    pub vendor_type: String,
+}
+
+impl CustomFormat {
+    pub fn new(
+        name: String,
+        rows: u8,
+        columns: u8,
+        well_volume: f32,
+        dead_volume: f32,
+        vendor_type: String,
+    ) -> Self {
+        CustomFormat {
+            id: Uuid::now_v7(),
+            name,
+            rows,
+            columns,
+            well_volume,
+            dead_volume,
+            vendor_type,
+        }
+    }
+
+    pub fn get_uuid(&self) -> Uuid {
+        self.id
+    }
+
+    pub fn format(&self) -> PlateFormat {
+        PlateFormat::Custom {
+            id: self.id,
+            rows: self.rows,
+            columns: self.columns,
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use wasm_bindgen_test::*;
+
+    use super::*;
+
+    #[test]
+    #[wasm_bindgen_test]
+    fn test_custom_format_size() {
+        let reservoir = CustomFormat::new("Trough".to_string(), 1, 12, 2e4, 1e3, String::new());
+        let plate = Plate::new(PlateType::Source, reservoir.format());
+        assert_eq!(plate.size(), (1, 12));
+        assert_eq!(plate.plate_format.to_string(), "1x12");
+        assert_eq!(
+            plate.plate_format,
+            PlateFormat::Custom {
+                id: reservoir.get_uuid(),
+                rows: 1,
+                columns: 12
+            }
+        );
+
+        let labware = plate.labware(&[reservoir.clone()]).unwrap();
+        assert_eq!(labware.max_volume, 2e4);
+        assert_eq!(labware.usable_volume(), 1.9e4);
+        assert!(plate.labware(&[]).is_err());
+
+        let rack = CustomFormat::new("Rack".to_string(), 8, 3, 1500.0, 0.0, String::new());
+        assert_eq!(rack.format().size(), (8, 3));
+        assert_ne!(rack.format(), reservoir.format());
+    }
+}
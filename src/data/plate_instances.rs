@@ -1,25 +1,34 @@
 use super::plate::*;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
-#[derive(PartialEq, Clone, Serialize, Deserialize)]
+#[derive(PartialEq, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct PlateInstance {
     pub plate: Plate,
     #[serde(rename = "id_v7")]
-    #[serde(default = "Uuid::now_v7")]
     id: Uuid,
     pub name: String,
+    // Wells no transfer should write into (destination plates only)
+    #[serde(default)]
+    pub mask: Vec<(u8, u8)>,
+    // Stock concentration in every well, µM (source plates only)
+    #[serde(default)]
+    pub concentration: Option<f32>,
+    // Final volume of each well after the run, µL (destination plates only)
+    #[serde(default)]
+    pub well_volume: Option<f32>,
 }
 
 impl PlateInstance {
     pub fn new(sort: PlateType, format: PlateFormat, name: String) -> Self {
         PlateInstance {
-            plate: Plate {
-                plate_type: sort,
-                plate_format: format,
-            },
+            plate: Plate::new(sort, format),
             id: Uuid::now_v7(),
             name,
+            mask: Vec::new(),
+            concentration: None,
+            well_volume: None,
         }
     }
 
@@ -32,12 +41,23 @@ impl PlateInstance {
     }
 }
 
+/// The excluded wells of the plate with this id, none if it is not in `plates`.
+pub fn plate_mask(plates: &[PlateInstance], id: Uuid) -> &[(u8, u8)] {
+    plates
+        .iter()
+        .find(|pi| pi.get_uuid() == id)
+        .map_or(&[], |pi| &pi.mask)
+}
+
 impl From<Plate> for PlateInstance {
     fn from(value: Plate) -> Self {
         PlateInstance {
             plate: value,
             id: Uuid::now_v7(),
             name: "New Plate".to_string(),
+            mask: Vec::new(),
+            concentration: None,
+            well_volume: None,
         }
     }
 }
@@ -0,0 +1,91 @@
+// Randomized plate layouts.
+//
+// Source wells are assigned to destination wells in a shuffled order
+// so samples don't always sit in the same position on the plate.
+// The shuffle is driven by a seed, and the same seed always gives the
+// same layout. ChaCha8Rng is used since its output is fixed by the
+// algorithm, where StdRng may change between rand versions and SmallRng
+// differs between 32 and 64-bit targets.
+
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+
+// (source well, destination well)
+type WellPair = ((u8, u8), (u8, u8));
+
+pub fn new_seed() -> u64 {
+    // Kept short so it's easy to write down and type back in
+    rand::random::<u32>() as u64
+}
+
+pub fn randomized_pairs(
+    sources: &[(u8, u8)],
+    destinations: &[(u8, u8)],
+    excluded: &[(u8, u8)],
+    seed: u64,
+) -> Result<Vec<WellPair>, String> {
+    // Excluded wells (controls, edges...) are never assigned.
+    let mut available: Vec<(u8, u8)> = destinations
+        .iter()
+        .filter(|w| !excluded.contains(w))
+        .copied()
+        .collect();
+    if available.len() < sources.len() {
+        return Err(format!(
+            "{} source wells but only {} destination wells available",
+            sources.len(),
+            available.len()
+        ));
+    }
+    let mut rng = ChaCha8Rng::seed_from_u64(seed);
+    available.shuffle(&mut rng);
+    Ok(sources.iter().copied().zip(available).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use wasm_bindgen_test::*;
+
+    use super::*;
+
+    #[test]
+    #[wasm_bindgen_test]
+    fn test_randomized_pairs() {
+        let sources: Vec<(u8, u8)> = (1..=8).map(|r| (r, 1)).collect();
+        let destinations: Vec<(u8, u8)> =
+            (1..=8).flat_map(|r| (1..=2).map(move |c| (r, c))).collect();
+        let excluded = [(1, 1), (8, 2)];
+
+        let pairs = randomized_pairs(&sources, &destinations, &excluded, 42).unwrap();
+        assert_eq!(pairs.len(), sources.len());
+        assert_eq!(
+            pairs.iter().map(|p| p.0).collect::<Vec<_>>(),
+            sources,
+            "Sources should keep their order"
+        );
+        assert!(pairs.iter().all(|p| !excluded.contains(&p.1)));
+        let mut dests: Vec<_> = pairs.iter().map(|p| p.1).collect();
+        dests.sort();
+        dests.dedup();
+        assert_eq!(dests.len(), sources.len(), "Destinations must be distinct");
+
+        // Reproducible for a seed, different for another
+        assert_eq!(
+            randomized_pairs(&sources, &destinations, &excluded, 42).unwrap(),
+            pairs
+        );
+        assert_ne!(
+            randomized_pairs(&sources, &destinations, &excluded, 43).unwrap(),
+            pairs
+        );
+
+        // Saved seeds must keep giving the same layout
+        assert_eq!(
+            randomized_pairs(&sources[..3], &destinations, &[], 7).unwrap(),
+            vec![((1, 1), (6, 2)), ((2, 1), (3, 1)), ((3, 1), (5, 2))]
+        );
+
+        assert!(randomized_pairs(&destinations, &sources, &[], 1).is_err());
+    }
+}
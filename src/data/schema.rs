@@ -0,0 +1,403 @@
+// Every exported state carries a `version`. Files from older versions are
+// upgraded one step at a time by `MIGRATIONS` before being deserialized,
+// and `state_schema` describes the current version for other tools
+// (generated from the types' JsonSchema derives).
+
+use schemars::gen::SchemaSettings;
+use schemars::schema::SchemaObject;
+use serde_json::{json, Map, Value};
+use uuid::Uuid;
+
+use crate::components::states::{MainState, Preferences};
+use crate::data::transfer::default_volume;
+
+pub const STATE_VERSION: u64 = 2;
+// Where the schema is published, relative to the repository root
+pub const SCHEMA_ID: &str = "plate-tool-state.schema.json";
+
+type Migration = fn(&mut Value) -> Result<(), String>;
+
+// MIGRATIONS[n] upgrades a version n + 1 file to version n + 2
+const MIGRATIONS: [Migration; (STATE_VERSION - 1) as usize] = [migrate_v1];
+
+/// Serializes the state with its schema version.
+pub fn to_json(state: &MainState) -> Result<String, String> {
+    serde_json::to_string(&to_value(state)?).map_err(|e| e.to_string())
+}
+
+/// Reads a state exported by any version up to this one, upgrading it as needed.
+pub fn from_json(text: &str) -> Result<MainState, String> {
+    // serde's message includes the line and column
+    from_value(serde_json::from_str(text).map_err(|e| e.to_string())?)
+}
+
+pub fn to_value(state: &MainState) -> Result<Value, String> {
+    let mut value = serde_json::to_value(state).map_err(|e| e.to_string())?;
+    if let Value::Object(map) = &mut value {
+        map.insert("version".to_string(), json!(STATE_VERSION));
+    }
+    Ok(value)
+}
+
+pub fn from_value(mut value: Value) -> Result<MainState, String> {
+    let version = file_version(&value)?;
+    if version > STATE_VERSION {
+        return Err(format!(
+            "This file is from a newer version of Plate Tool (format version {}, \
+             this version reads up to {}). Update Plate Tool to open it.",
+            version, STATE_VERSION
+        ));
+    }
+    for migration in &MIGRATIONS[(version - 1) as usize..] {
+        migration(&mut value)?;
+    }
+    if let Value::Object(map) = &mut value {
+        map.remove("version");
+    }
+    serde_json::from_value(value).map_err(|e| e.to_string())
+}
+
+/// For a MainState kept inside other stored data, e.g. `#[serde(with = "versioned")]`,
+/// so that it is upgraded the same way as an exported file.
+pub mod versioned {
+    use serde::de::Error as _;
+    use serde::ser::Error as _;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+    use serde_json::Value;
+
+    use crate::components::states::MainState;
+
+    pub fn serialize<S: Serializer>(state: &MainState, serializer: S) -> Result<S::Ok, S::Error> {
+        super::to_value(state)
+            .map_err(S::Error::custom)?
+            .serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<MainState, D::Error> {
+        super::from_value(Value::deserialize(deserializer)?).map_err(D::Error::custom)
+    }
+}
+
+fn file_version(value: &Value) -> Result<u64, String> {
+    let Value::Object(map) = value else {
+        return Err("Expected a JSON object".to_string());
+    };
+    // Files exported before versioning have no version field
+    match map.get("version") {
+        None => Ok(1),
+        Some(v) => match v.as_u64() {
+            Some(version) if version >= 1 => Ok(version),
+            _ => Err(format!("{} is not a valid format version", v)),
+        },
+    }
+}
+
+fn object_array<'a>(
+    value: &'a mut Value,
+    key: &str,
+) -> impl Iterator<Item = &'a mut Map<String, Value>> {
+    value
+        .get_mut(key)
+        .and_then(Value::as_array_mut)
+        .into_iter()
+        .flatten()
+        .filter_map(Value::as_object_mut)
+}
+
+/// Version 1 files may lack ids, transfer volumes and preferences,
+/// which version 2 always writes out.
+fn migrate_v1(value: &mut Value) -> Result<(), String> {
+    // Items from before `id_v7` are given a fresh id,
+    // and references to their old `id` follow it
+    let mut renamed: Vec<(Value, Value)> = Vec::new();
+    let mut assign_ids = |items: &mut Map<String, Value>| {
+        if !items.contains_key("id_v7") {
+            let id = json!(Uuid::now_v7());
+            if let Some(old) = items.remove("id") {
+                renamed.push((old, id.clone()));
+            }
+            items.insert("id_v7".to_string(), id);
+        }
+    };
+    for key in [
+        "source_plates",
+        "destination_plates",
+        "transfers",
+        "custom_formats",
+    ] {
+        object_array(value, key).for_each(&mut assign_ids);
+    }
+
+    let rename = |v: &mut Value| {
+        if let Some((_, new)) = renamed.iter().find(|(old, _)| old == v) {
+            *v = new.clone();
+        }
+    };
+    for transfer in object_array(value, "transfers") {
+        for key in ["source_id", "dest_id"] {
+            if let Some(id) = transfer.get_mut(key) {
+                rename(id);
+            }
+        }
+        transfer
+            .entry("volume")
+            .or_insert_with(|| json!(default_volume()));
+    }
+    let Value::Object(map) = value else {
+        return Err("Expected a JSON object".to_string());
+    };
+    for key in [
+        "selected_source_plate",
+        "selected_dest_plate",
+        "selected_transfer",
+    ] {
+        if let Some(id) = map.get_mut(key) {
+            rename(id);
+        }
+    }
+    if !map.contains_key("preferences") {
+        let preferences =
+            serde_json::to_value(Preferences::default()).map_err(|e| e.to_string())?;
+        map.insert("preferences".to_string(), preferences);
+    }
+    Ok(())
+}
+
+/// The JSON Schema document for the current state format.
+pub fn state_schema() -> Value {
+    let mut root = SchemaSettings::draft2019_09()
+        .into_generator()
+        .into_root_schema_for::<MainState>();
+    let metadata = root.schema.metadata();
+    metadata.id = Some(SCHEMA_ID.to_string());
+    metadata.title = Some("Plate Tool state".to_string());
+    metadata.description = Some(format!(
+        "A project exported by Plate Tool, format version {}",
+        STATE_VERSION
+    ));
+    let object = root.schema.object();
+    object.required.insert("version".to_string());
+    object.properties.insert(
+        "version".to_string(),
+        SchemaObject {
+            const_value: Some(json!(STATE_VERSION)),
+            ..Default::default()
+        }
+        .into(),
+    );
+    serde_json::to_value(root).expect("Schemas are always valid JSON")
+}
+
+#[cfg(test)]
+mod tests {
+    use wasm_bindgen_test::*;
+
+    use super::*;
+    use crate::data::plate::{CustomFormat, PlateFormat, PlateType};
+    use crate::data::plate_instances::PlateInstance;
+    use crate::data::transfer::Transfer;
+    use crate::data::transfer_region::Region;
+    use crate::data::well_name::{IndexOrder, WellNameStyle};
+
+    const PUBLISHED: &str = include_str!("../../plate-tool-state.schema.json");
+
+    fn example_state() -> MainState {
+        let mut state = MainState::default();
+        let format = CustomFormat::new("Tubes".to_string(), 4, 6, 1500.0, 50.0, "T24".to_string());
+        let source = PlateInstance::new(
+            PlateType::Source,
+            PlateFormat::Custom {
+                id: format.get_uuid(),
+                rows: 4,
+                columns: 6,
+            },
+            "S".to_string(),
+        );
+        let mut dest =
+            PlateInstance::new(PlateType::Destination, PlateFormat::W384, "D".to_string());
+        dest.mask = vec![(1, 1)];
+        let mut transfer = Transfer::new(
+            source.clone(),
+            dest.clone(),
+            Default::default(),
+            "T".to_string(),
+        );
+        transfer.transfer_region.source_region = Region::Rect((1, 1), (2, 2));
+        transfer.transfer_region.dest_region = Region::Multi(vec![((1, 1), (2, 2))]);
+        transfer.seed = Some(7);
+        state.selected_source_plate = source.get_uuid();
+        state.add_custom_format(format);
+        state.add_source_plate(source);
+        state.add_dest_plate(dest);
+        state.transfers.push(transfer);
+        state.preferences.export_well_style = WellNameStyle::Index(IndexOrder::ColumnMajor);
+        state
+    }
+
+    // Checks the parts of JSON Schema that schemars produces
+    fn validate(value: &Value, schema: &Value, doc: &Value, path: &str) -> Result<(), String> {
+        let matches = |schema: &Value| validate(value, schema, doc, path).is_ok();
+        if let Some(r) = schema.get("$ref").and_then(Value::as_str) {
+            let target = doc.pointer(r.trim_start_matches('#')).unwrap();
+            validate(value, target, doc, path)?;
+        }
+        if let Some(all) = schema.get("allOf").and_then(Value::as_array) {
+            for s in all {
+                validate(value, s, doc, path)?;
+            }
+        }
+        if let Some(any) = schema.get("anyOf").or(schema.get("oneOf")) {
+            if !any.as_array().unwrap().iter().any(matches) {
+                return Err(format!("{}: no option matches", path));
+            }
+        }
+        if let Some(c) = schema.get("const") {
+            if value != c {
+                return Err(format!("{}: expected {}", path, c));
+            }
+        }
+        if let Some(allowed) = schema.get("enum").and_then(Value::as_array) {
+            if !allowed.contains(value) {
+                return Err(format!("{}: {} not allowed", path, value));
+            }
+        }
+        if let Some(types) = schema.get("type") {
+            let types = match types {
+                Value::Array(types) => types.iter().filter_map(Value::as_str).collect(),
+                t => vec![t.as_str().unwrap()],
+            };
+            let type_ok = types.iter().any(|t| match *t {
+                "object" => value.is_object(),
+                "array" => value.is_array(),
+                "string" => value.is_string(),
+                "number" => value.is_number(),
+                "integer" => value.is_u64() || value.is_i64(),
+                "boolean" => value.is_boolean(),
+                "null" => value.is_null(),
+                _ => false,
+            });
+            if !type_ok {
+                return Err(format!("{}: expected {:?}", path, types));
+            }
+        }
+        if let Value::Object(map) = value {
+            for (key, v) in map {
+                match schema.get("properties").and_then(|p| p.get(key)) {
+                    Some(property) => validate(v, property, doc, &format!("{}.{}", path, key))?,
+                    None if schema.get("additionalProperties") == Some(&json!(false)) => {
+                        return Err(format!("{}: unexpected key {}", path, key))
+                    }
+                    None => (),
+                }
+            }
+            for key in schema
+                .get("required")
+                .and_then(Value::as_array)
+                .into_iter()
+                .flatten()
+            {
+                if !map.contains_key(key.as_str().unwrap()) {
+                    return Err(format!("{}: missing {}", path, key));
+                }
+            }
+        }
+        if let Value::Array(items) = value {
+            match schema.get("items") {
+                // A tuple
+                Some(Value::Array(each)) => {
+                    if items.len() != each.len() {
+                        return Err(format!("{}: expected {} items", path, each.len()));
+                    }
+                    for (i, (v, s)) in items.iter().zip(each).enumerate() {
+                        validate(v, s, doc, &format!("{}[{}]", path, i))?;
+                    }
+                }
+                Some(all) => {
+                    for (i, v) in items.iter().enumerate() {
+                        validate(v, all, doc, &format!("{}[{}]", path, i))?;
+                    }
+                }
+                None => (),
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    #[wasm_bindgen_test]
+    fn test_round_trip() {
+        let state = example_state();
+        let json = to_json(&state).unwrap();
+        assert!(json.contains(&format!("\"version\":{}", STATE_VERSION)));
+        assert!(from_json(&json).unwrap() == state);
+    }
+
+    #[test]
+    #[wasm_bindgen_test]
+    fn test_migrate_v1() {
+        // As exported before versioning: no preferences, volume or id_v7
+        let old = r#"{
+            "source_plates": [{"plate": {"plate_type": "Source", "plate_format": "W96"},
+                "id": "old-source", "name": "S"}],
+            "destination_plates": [{"plate": {"plate_type": "Destination", "plate_format": "W96"},
+                "id": "old-dest", "name": "D"}],
+            "transfers": [{"source_id": "old-source", "dest_id": "old-dest", "name": "T",
+                "transfer_region": {
+                    "source_plate": {"plate_type": "Source", "plate_format": "W96"},
+                    "source_region": {"Point": [1, 1]},
+                    "dest_plate": {"plate_type": "Destination", "plate_format": "W96"},
+                    "dest_region": {"Point": [2, 2]},
+                    "interleave_source": [1, 1], "interleave_dest": [1, 1]}}],
+            "selected_source_plate": "old-source",
+            "selected_dest_plate": "00000000-0000-0000-0000-000000000000",
+            "selected_transfer": "00000000-0000-0000-0000-000000000000"
+        }"#;
+        let state = from_json(old).unwrap();
+        let transfer = &state.transfers[0];
+        assert_eq!(transfer.source_id, state.source_plates[0].get_uuid());
+        assert_eq!(transfer.dest_id, state.destination_plates[0].get_uuid());
+        assert_eq!(state.selected_source_plate, transfer.source_id);
+        assert_eq!(transfer.volume, 2.5);
+        assert!(state.preferences == Preferences::default());
+    }
+
+    #[test]
+    #[wasm_bindgen_test]
+    fn test_newer_version() {
+        let mut value = serde_json::to_value(MainState::default()).unwrap();
+        value["version"] = json!(STATE_VERSION + 1);
+        let error = from_json(&value.to_string()).err().unwrap();
+        assert!(error.contains("newer version"));
+        assert!(from_json(r#"{"version": "two"}"#).is_err());
+        assert!(from_json("[]").is_err());
+    }
+
+    #[test]
+    #[wasm_bindgen_test]
+    fn test_state_matches_schema() {
+        let doc = state_schema();
+        let value: Value = serde_json::from_str(&to_json(&example_state()).unwrap()).unwrap();
+        assert_eq!(validate(&value, &doc, &doc, "$"), Ok(()));
+
+        let mut wrong = value.clone();
+        wrong["transfers"][0]["volume"] = json!("lots");
+        assert!(validate(&wrong, &doc, &doc, "$").is_err());
+        let mut missing = value.clone();
+        missing["source_plates"][0]
+            .as_object_mut()
+            .unwrap()
+            .remove("id_v7");
+        assert!(validate(&missing, &doc, &doc, "$").is_err());
+        let mut newer = value;
+        newer["version"] = json!(STATE_VERSION + 1);
+        assert!(validate(&newer, &doc, &doc, "$").is_err());
+    }
+
+    #[test]
+    #[wasm_bindgen_test]
+    fn test_published_schema() {
+        // Regenerate the file with File > Export > JSON Schema if this fails
+        let published: Value = serde_json::from_str(PUBLISHED).unwrap();
+        assert_eq!(published, state_schema());
+    }
+}
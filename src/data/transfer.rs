@@ -1,21 +1,26 @@
 use super::plate_instances::*;
 use super::transfer_region::*;
+use schemars::JsonSchema;
 use serde::Deserialize;
 use serde::Serialize;
 use uuid::Uuid;
 
-#[derive(PartialEq, Clone, Debug, Serialize, Deserialize)]
+#[derive(PartialEq, Clone, Debug, Serialize, Deserialize, JsonSchema)]
 #[non_exhaustive]
 pub struct Transfer {
     pub source_id: Uuid,
     pub dest_id: Uuid,
     pub name: String,
     #[serde(rename = "id_v7")]
-    #[serde(default = "Uuid::now_v7")]
     pub id: Uuid,
     pub transfer_region: TransferRegion,
-    #[serde(default = "default_volume")]
     pub volume: f32,
+    // Set when several sources are meant to land in the same destination well
+    #[serde(default)]
+    pub pooling_allowed: bool,
+    // Seed used to generate a randomized layout, kept for auditing
+    #[serde(default)]
+    pub seed: Option<u64>,
 }
 
 impl Default for Transfer {
@@ -27,11 +32,14 @@ impl Default for Transfer {
             id: Default::default(),
             transfer_region: Default::default(),
             volume: 2.5f32,
+            pooling_allowed: false,
+            seed: None,
         }
     }
 }
 
-fn default_volume() -> f32 {
+// For transfers saved before the volume could be set, see schema::migrate_v1
+pub(crate) fn default_volume() -> f32 {
     2.5f32
 }
 
@@ -49,6 +57,8 @@ impl Transfer {
             id: Uuid::now_v7(),
             transfer_region: tr,
             volume: 2.5,
+            pooling_allowed: false,
+            seed: None,
         }
     }
 
@@ -56,3 +66,49 @@ impl Transfer {
         self.id
     }
 }
+
+pub fn apply_name_pattern(pattern: &str, name: &str, n: usize) -> String {
+    // "{name}" is replaced by the current name and "{n}" by a counter.
+    // Done in one pass, so a "{n}" in the name itself is kept.
+    let mut renamed = String::new();
+    let mut rest = pattern;
+    while let Some(start) = rest.find('{') {
+        renamed.push_str(&rest[..start]);
+        rest = &rest[start..];
+        if let Some(after) = rest.strip_prefix("{name}") {
+            renamed.push_str(name);
+            rest = after;
+        } else if let Some(after) = rest.strip_prefix("{n}") {
+            renamed.push_str(&n.to_string());
+            rest = after;
+        } else {
+            renamed.push('{');
+            rest = &rest[1..];
+        }
+    }
+    renamed.push_str(rest);
+    renamed
+}
+
+#[cfg(test)]
+mod tests {
+    use wasm_bindgen_test::*;
+
+    use super::apply_name_pattern;
+
+    #[test]
+    #[wasm_bindgen_test]
+    fn test_apply_name_pattern() {
+        assert_eq!(apply_name_pattern("{name}", "Dilution", 3), "Dilution");
+        assert_eq!(
+            apply_name_pattern("Rep {n} of {name}", "Dilution", 3),
+            "Rep 3 of Dilution"
+        );
+        assert_eq!(apply_name_pattern("Fixed", "Dilution", 1), "Fixed");
+        assert_eq!(
+            apply_name_pattern("{name} {n}", "Plate {n}", 2),
+            "Plate {n} 2"
+        );
+        assert_eq!(apply_name_pattern("{{n}}", "Dilution", 4), "{4}");
+    }
+}
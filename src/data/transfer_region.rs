@@ -1,20 +1,83 @@
+use std::collections::HashMap;
+
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
 use crate::components::transfer_menu::RegionDisplay;
 
+use super::labware::Labware;
 use super::plate::Plate;
 
-#[derive(Clone, PartialEq, Eq, Serialize, Deserialize, Debug)]
+#[derive(Clone, PartialEq, Serialize, Deserialize, JsonSchema, Debug)]
 pub struct CustomRegion {
     src: Vec<(u8, u8)>,
     dest: Vec<(u8, u8)>,
+    // Per-pair volume overrides; None falls back to Transfer.volume.
+    // May be shorter than src/dest in older files.
+    #[serde(default)]
+    volumes: Vec<Option<f32>>,
 }
 
-#[derive(Clone, PartialEq, Eq, Serialize, Deserialize, Debug)]
+impl CustomRegion {
+    pub fn pairs(&self) -> Vec<((u8, u8), (u8, u8))> {
+        self.src
+            .iter()
+            .copied()
+            .zip(self.dest.iter().copied())
+            .collect()
+    }
+
+    pub fn volume(&self, index: usize) -> Option<f32> {
+        self.volumes.get(index).copied().flatten()
+    }
+
+    pub fn push(&mut self, src: (u8, u8), dest: (u8, u8)) {
+        self.src.push(src);
+        self.dest.push(dest);
+        self.volumes.resize(self.src.len(), None);
+    }
+
+    pub fn remove(&mut self, index: usize) {
+        if index < self.src.len() {
+            self.src.remove(index);
+            self.dest.remove(index);
+            if index < self.volumes.len() {
+                self.volumes.remove(index);
+            }
+        }
+    }
+
+    pub fn set_volume(&mut self, index: usize, volume: Option<f32>) {
+        if index < self.src.len() {
+            self.volumes.resize(self.src.len(), None);
+            self.volumes[index] = volume;
+        }
+    }
+
+    pub fn set_source(&mut self, index: usize, well: (u8, u8)) {
+        if let Some(src) = self.src.get_mut(index) {
+            *src = well;
+        }
+    }
+
+    pub fn set_destination(&mut self, index: usize, well: (u8, u8)) {
+        if let Some(dest) = self.dest.get_mut(index) {
+            *dest = well;
+        }
+    }
+}
+
+// Two opposite corners of a rectangle
+pub type Corners = ((u8, u8), (u8, u8));
+
+#[derive(Clone, PartialEq, Serialize, Deserialize, JsonSchema, Debug)]
 pub enum Region {
     Rect((u8, u8), (u8, u8)),
     Point((u8, u8)),
     Custom(CustomRegion),
+    // Several rectangles (a point is a rectangle with equal corners),
+    // taken in the order listed
+    Multi(Vec<Corners>),
 }
 impl Default for Region {
     fn default() -> Self {
@@ -45,11 +108,106 @@ impl Region {
         Region::Custom(CustomRegion {
             src: src_pts,
             dest: dest_pts,
+            volumes: Vec::new(),
+        })
+    }
+
+    /// Adds a rectangle to the region, making it a multi-rectangle region.
+    /// Custom regions are left as they are.
+    pub fn add_rect(&mut self, c1: (u8, u8), c2: (u8, u8)) {
+        match self {
+            Region::Point(p) => *self = Region::Multi(vec![(*p, *p), (c1, c2)]),
+            Region::Rect(r1, r2) => *self = Region::Multi(vec![(*r1, *r2), (c1, c2)]),
+            Region::Multi(rects) => rects.push((c1, c2)),
+            Region::Custom(_) => (),
+        }
+    }
+
+    /// The rectangles of a multi-rectangle region, empty for any other region
+    pub fn rects(&self) -> &[Corners] {
+        match self {
+            Region::Multi(rects) => rects,
+            _ => &[],
+        }
+    }
+
+    /// Whether a well lies inside the region's rectangles (Custom regions contain nothing)
+    pub fn contains(&self, well: (u8, u8)) -> bool {
+        let inside = |(c1, c2): &Corners| {
+            let (ul, br) = standardize_rectangle(c1, c2);
+            (ul.0..=br.0).contains(&well.0) && (ul.1..=br.1).contains(&well.1)
+        };
+        match self {
+            Region::Point(p) => *p == well,
+            Region::Rect(c1, c2) => inside(&(*c1, *c2)),
+            Region::Multi(rects) => rects.iter().any(inside),
+            Region::Custom(_) => false,
+        }
+    }
+
+    /// The region moved by (rows, columns), or None if it would leave a plate of the given size.
+    /// Custom regions cannot be moved.
+    pub fn translate(&self, offset: (i16, i16), size: (u8, u8)) -> Option<Region> {
+        let shift = |(i, j): (u8, u8)| -> Option<(u8, u8)> {
+            let i = i as i16 + offset.0;
+            let j = j as i16 + offset.1;
+            if (1..=size.0 as i16).contains(&i) && (1..=size.1 as i16).contains(&j) {
+                Some((i as u8, j as u8))
+            } else {
+                None
+            }
+        };
+        match self {
+            Region::Point(p) => Some(Region::Point(shift(*p)?)),
+            Region::Rect(c1, c2) => Some(Region::Rect(shift(*c1)?, shift(*c2)?)),
+            Region::Multi(rects) => Some(Region::Multi(
+                rects
+                    .iter()
+                    .map(|(c1, c2)| Some((shift(*c1)?, shift(*c2)?)))
+                    .collect::<Option<Vec<Corners>>>()?,
+            )),
+            Region::Custom(_) => None,
+        }
+    }
+
+    /// For a corner of a rectangular region, the corner diagonally opposite it
+    pub fn opposite_corner(&self, corner: (u8, u8)) -> Option<(u8, u8)> {
+        if let Region::Rect(c1, c2) = self {
+            let (ul, br) = standardize_rectangle(c1, c2);
+            let rows = if corner.0 == ul.0 { br.0 } else { ul.0 };
+            let cols = if corner.1 == ul.1 { br.1 } else { ul.1 };
+            if [ul.0, br.0].contains(&corner.0) && [ul.1, br.1].contains(&corner.1) {
+                return Some((rows, cols));
+            }
+        }
+        None
+    }
+
+    pub fn multi_wells(rects: &[Corners], interleave: (i8, i8), order: FillOrder) -> Vec<(u8, u8)> {
+        // Rectangle by rectangle, in the given order within each, skipping repeats
+        let mut wells = Vec::<(u8, u8)>::new();
+        for (c1, c2) in rects {
+            let (ul, br) = standardize_rectangle(c1, c2);
+            for well in fill_rectangle(ul, br, interleave, order) {
+                if !wells.contains(&well) {
+                    wells.push(well);
+                }
+            }
+        }
+        wells
+    }
+
+    #[allow(clippy::type_complexity)]
+    pub fn new_custom_with_volumes(transfers: &[((u8, u8), (u8, u8), Option<f32>)]) -> Self {
+        Region::Custom(CustomRegion {
+            src: transfers.iter().map(|t| t.0).collect(),
+            dest: transfers.iter().map(|t| t.1).collect(),
+            volumes: transfers.iter().map(|t| t.2).collect(),
         })
     }
 }
 
-#[derive(PartialEq, Eq, Clone, Serialize, Deserialize, Debug)]
+#[derive(PartialEq, Clone, Serialize, Deserialize, JsonSchema, Debug)]
 pub struct TransferRegion {
     pub source_plate: Plate,
     pub source_region: Region, // Even if it is just a point, we don't want corners.
@@ -57,6 +215,36 @@ pub struct TransferRegion {
     pub dest_region: Region,
     pub interleave_source: (i8, i8),
     pub interleave_dest: (i8, i8),
+    // Order in which a destination rectangle is filled from the source
+    #[serde(default)]
+    pub fill_order: FillOrder,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema, Debug, Default)]
+pub enum FillOrder {
+    // Keeps the source's layout
+    #[default]
+    RowMajor,
+    // Down each column, so a source row can become a destination column
+    ColumnMajor,
+    // Row by row, alternating direction
+    Serpentine,
+}
+
+impl FillOrder {
+    pub const ALL: [FillOrder; 3] = [
+        FillOrder::RowMajor,
+        FillOrder::ColumnMajor,
+        FillOrder::Serpentine,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            FillOrder::RowMajor => "Row-major (keep layout)",
+            FillOrder::ColumnMajor => "Column-major",
+            FillOrder::Serpentine => "Serpentine",
+        }
+    }
 }
 
 impl Default for TransferRegion {
@@ -68,6 +256,7 @@ impl Default for TransferRegion {
             dest_region: Region::default(),
             interleave_source: (1, 1),
             interleave_dest: (1, 1),
+            fill_order: FillOrder::RowMajor,
         }
     }
 }
@@ -97,12 +286,25 @@ impl TransferRegion {
                 wells
             }
             Region::Point(p) => vec![*p],
-            Region::Custom(c) => c.src.clone(),
+            Region::Multi(rects) => {
+                Region::multi_wells(rects, self.interleave_source, FillOrder::RowMajor)
+            }
+            Region::Custom(c) => {
+                // A source well may be paired with several destinations,
+                // but should only be listed once.
+                let mut wells = Vec::<(u8, u8)>::with_capacity(c.src.len());
+                for well in &c.src {
+                    if !wells.contains(well) {
+                        wells.push(*well);
+                    }
+                }
+                wells
+            }
         }
     }
 
-    pub fn get_destination_wells(&self) -> Vec<(u8, u8)> {
-        let map = self.calculate_map();
+    pub fn get_destination_wells(&self, mask: &[(u8, u8)]) -> Vec<(u8, u8)> {
+        let map = self.calculate_map(mask);
         let source_wells = self.get_source_wells();
 
         let mut wells = Vec::<(u8, u8)>::new();
@@ -119,8 +321,64 @@ impl TransferRegion {
         wells
     }
 
+    #[allow(clippy::type_complexity)]
+    pub fn get_transfer_pairs(
+        &self,
+        volume: f32,
+        mask: &[(u8, u8)],
+    ) -> Vec<((u8, u8), (u8, u8), f32)> {
+        // Every (source, destination, volume) the transfer will perform.
+        // Custom regions keep their own order and may override the volume.
+        if let Region::Custom(c) = &self.source_region {
+            return c
+                .pairs()
+                .into_iter()
+                .enumerate()
+                .filter(|(_, (_, d))| !mask.contains(d))
+                .map(|(index, (s, d))| (s, d, c.volume(index).unwrap_or(volume)))
+                .collect();
+        }
+        let map = self.calculate_map(mask);
+        let mut pairs = Vec::new();
+        for s_well in self.get_source_wells() {
+            if let Some(dest_wells) = map(s_well) {
+                for d_well in dest_wells {
+                    pairs.push((s_well, d_well, volume));
+                }
+            }
+        }
+        pairs
+    }
+
+    pub fn to_custom(&self, mask: &[(u8, u8)]) -> Region {
+        // Spells out the current mapping as explicit well pairs
+        let map = self.calculate_map(mask);
+        let mut pairs: Vec<((u8, u8), (u8, u8))> = Vec::new();
+        for s_well in self.get_source_wells() {
+            if let Some(dest_wells) = map(s_well) {
+                for d_well in dest_wells {
+                    pairs.push((s_well, d_well));
+                }
+            }
+        }
+        Region::new_custom(&pairs)
+    }
+
+    pub fn edit_custom<F: Fn(&mut CustomRegion)>(&mut self, edit: F) {
+        // Custom transfers keep the same region on both sides
+        for region in [&mut self.source_region, &mut self.dest_region] {
+            if let Region::Custom(c) = region {
+                edit(c);
+            }
+        }
+    }
+
     #[allow(clippy::type_complexity)] // Resolving gives inherent associated type error
-    pub fn calculate_map(&self) -> Box<dyn Fn((u8, u8)) -> Option<Vec<(u8, u8)>> + '_> {
+    pub fn calculate_map(
+        &self,
+        // The destination plate's excluded wells
+        mask: &[(u8, u8)],
+    ) -> Box<dyn Fn((u8, u8)) -> Option<Vec<(u8, u8)>> + '_> {
         // By validating first, we have a stronger guarantee that
         // this function will not panic. :)
         // log::debug!("Validating: {:?}", self.validate());
@@ -129,16 +387,22 @@ impl TransferRegion {
             eprintln!("This transfer will be empty.");
             return Box::new(|(_, _)| None);
         }
+        if !mask.is_empty() && !matches!(self.dest_region, Region::Custom(_)) {
+            let packed = self.packed_map(mask);
+            return Box::new(move |well| packed.get(&well).cloned());
+        }
 
         // log::debug!("What is ild? {:?}", self);
         let source_wells = self.get_source_wells();
         let il_dest = self.interleave_dest;
         let il_source = self.interleave_source;
 
-        let source_corners: ((u8, u8), (u8, u8)) = match self.source_region {
-            Region::Point((x, y)) => ((x, y), (x, y)),
-            Region::Rect(c1, c2) => (c1, c2),
+        let source_corners: ((u8, u8), (u8, u8)) = match &self.source_region {
+            Region::Point((x, y)) => ((*x, *y), (*x, *y)),
+            Region::Rect(c1, c2) => (*c1, *c2),
             Region::Custom(_) => ((0, 0), (0, 0)),
+            // Laid out by its bounding box, so the rectangles keep their positions
+            Region::Multi(rects) => bounding_box(rects),
         };
         let (source_ul, _) = standardize_rectangle(&source_corners.0, &source_corners.1);
         // This map is not necessarily injective or surjective,
@@ -146,8 +410,37 @@ impl TransferRegion {
         // If the transfer is not a pooling transfer (interleave == 0)
         // and simple then we *will* have injectivity.
 
+        // Rectangles filled in another order take the source wells one by one.
+        // Pooling (a zero interleave) keeps the usual layout.
+        if let Region::Rect(c1, c2) = &self.dest_region {
+            if self.fill_order != FillOrder::RowMajor && il_dest.0 != 0 && il_dest.1 != 0 {
+                let (d_ul, d_br) = standardize_rectangle(c1, c2);
+                let slots = fill_rectangle(d_ul, d_br, il_dest, self.fill_order);
+                let n = source_wells.len();
+                // Only whole replicates, unless not even one fits
+                let replicates = usize::max(slots.len() / usize::max(n, 1), 1);
+                let pairs: HashMap<(u8, u8), Vec<(u8, u8)>> = source_wells
+                    .iter()
+                    .enumerate()
+                    .map(|(k, s)| {
+                        let wells = (0..replicates).filter_map(|r| slots.get(r * n + k).copied());
+                        (*s, wells.collect::<Vec<_>>())
+                    })
+                    .filter(|(_, wells)| !wells.is_empty())
+                    .collect();
+                return Box::new(move |well| pairs.get(&well).cloned());
+            }
+        }
+
         // Non-replicate transfers:
         match &self.dest_region {
+            Region::Multi(rects) => {
+                // Filled in order: the n-th source well goes to the n-th destination well
+                let dest_wells = Region::multi_wells(rects, il_dest, self.fill_order);
+                let pairs: HashMap<(u8, u8), (u8, u8)> =
+                    source_wells.iter().copied().zip(dest_wells).collect();
+                Box::new(move |well| pairs.get(&well).map(|d| vec![*d]))
+            }
             Region::Point((x, y)) => {
                 Box::new(move |(i, j)| {
                     if source_wells.contains(&(i, j)) {
@@ -251,23 +544,122 @@ impl TransferRegion {
                     }
                 })
             }
-            Region::Custom(c) => Box::new(move |(i, j)| {
-                let src = c.src.clone();
-                let dest = c.dest.clone();
+            Region::Custom(c) => {
+                // Explicit pairs can't be moved, so excluded wells are just dropped
+                let mask = mask.to_vec();
+                Box::new(move |(i, j)| {
+                    let src = c.src.clone();
+                    let dest = c.dest.clone();
 
-                let points: Vec<(u8, u8)> = src
-                    .iter()
-                    .enumerate()
-                    .filter(|(_index, (x, y))| *x == i && *y == j)
-                    .map(|(index, _)| dest[index])
-                    .collect();
-                if points.is_empty() {
-                    None
-                } else {
-                    Some(points)
+                    let points: Vec<(u8, u8)> = src
+                        .iter()
+                        .enumerate()
+                        .filter(|(_index, (x, y))| *x == i && *y == j)
+                        .map(|(index, _)| dest[index])
+                        .filter(|w| !mask.contains(w))
+                        .collect();
+                    if points.is_empty() {
+                        None
+                    } else {
+                        Some(points)
+                    }
+                })
+            }
+        }
+    }
+
+    fn packed_map(&self, mask: &[(u8, u8)]) -> HashMap<(u8, u8), Vec<(u8, u8)>> {
+        // Lays the transfer out as if nothing were excluded, then moves
+        // every well after an excluded one along to the next allowed well.
+        // Simple transfers may run on past the end of their block (downwards);
+        // replicates stay inside their rectangle and only whole replicates are kept.
+        // Pooling transfers just lose the excluded wells.
+        let map = self.calculate_map(&[]);
+        let sources = self.get_source_wells();
+        let natural: Vec<Vec<(u8, u8)>> = sources
+            .iter()
+            .map(|s| map(*s).unwrap_or_default())
+            .collect();
+        let allowed = |w: &(u8, u8)| !mask.contains(w);
+        let mut packed: HashMap<(u8, u8), Vec<(u8, u8)>> = HashMap::new();
+
+        if self.interleave_dest.0 == 0 || self.interleave_dest.1 == 0 {
+            for (s, wells) in sources.iter().zip(natural) {
+                let wells: Vec<(u8, u8)> = wells.into_iter().filter(allowed).collect();
+                if !wells.is_empty() {
+                    packed.insert(*s, wells);
+                }
+            }
+            return packed;
+        }
+
+        let slots: Vec<(u8, u8)> = match self.dest_region {
+            Region::Point((x, y)) => {
+                let step = (
+                    self.interleave_dest.0.unsigned_abs(),
+                    self.interleave_dest.1.unsigned_abs(),
+                );
+                let mut columns: Vec<u8> = natural.iter().flatten().map(|w| w.1).collect();
+                columns.sort_unstable();
+                columns.dedup();
+                let width = columns.len() as u16;
+                let size = self.dest_plate.size();
+                let mut slots = Vec::new();
+                let mut row = x as u16;
+                while row <= size.0 as u16 {
+                    for b in 0..width {
+                        let col = y as u16 + b * step.1 as u16;
+                        if col <= size.1 as u16 {
+                            slots.push((row as u8, col as u8));
+                        }
+                    }
+                    row += step.0 as u16;
                 }
-            }),
+                slots
+            }
+            Region::Multi(ref rects) => {
+                Region::multi_wells(rects, self.interleave_dest, self.fill_order)
+            }
+            _ => {
+                let replicates = natural.iter().map(|w| w.len()).min().unwrap_or(0);
+                (0..replicates)
+                    .flat_map(|r| natural.iter().map(move |wells| wells[r]))
+                    .collect()
+            }
+        };
+        let slots: Vec<(u8, u8)> = slots.into_iter().filter(allowed).collect();
+        let whole = match self.dest_region {
+            Region::Point(_) | Region::Multi(_) => usize::min(slots.len(), sources.len()),
+            _ if sources.is_empty() => 0,
+            _ => slots.len() / sources.len() * sources.len(),
+        };
+        for (k, well) in slots[..whole].iter().enumerate() {
+            packed
+                .entry(sources[k % sources.len()])
+                .or_default()
+                .push(*well);
         }
+        packed
+    }
+
+    pub fn validate_mask(&self, mask: &[(u8, u8)]) -> Result<(), &'static str> {
+        // Checks the transfer against the destination plate's excluded wells.
+        // Other regions are packed around them, custom pairs can only lose wells.
+        if matches!(self.dest_region, Region::Custom(_))
+            && self
+                .get_destination_wells(&[])
+                .iter()
+                .any(|w| mask.contains(w))
+        {
+            return Err("Transfer writes into excluded wells!");
+        }
+        if !mask.is_empty() {
+            let map = self.calculate_map(mask);
+            if self.get_source_wells().iter().any(|s| map(*s).is_none()) {
+                return Err("Not every source well fits around the excluded wells!");
+            }
+        }
+        Ok(())
     }
 
     pub fn validate(&self) -> Result<(), &'static str> {
@@ -301,12 +693,43 @@ impl TransferRegion {
                 }
             }
             Region::Custom(_) => return Ok(()),
+            Region::Multi(ref rects) => {
+                let source_max = self.source_plate.size();
+                if rects.is_empty() {
+                    return Err("Source region is empty!");
+                }
+                for (s1, s2) in rects {
+                    if s1.0 == 0 || s1.1 == 0 || s2.0 == 0 || s2.1 == 0 {
+                        return Err("Source region is out-of-bounds! (Too small)");
+                    }
+                    if s1.0 > source_max.0 || s2.0 > source_max.0 {
+                        return Err("Source region is out-of-bounds! (Too tall)");
+                    }
+                    if s1.1 > source_max.1 || s2.1 > source_max.1 {
+                        return Err("Source region is out-of-bounds! (Too wide)");
+                    }
+                }
+            }
         }
 
         if il_source.0 == 0 || il_dest.1 == 0 {
             return Err("Source interleave cannot be zero!");
         }
 
+        let dest_slots = match self.dest_region {
+            Region::Multi(ref rects) => Some(Region::multi_wells(rects, il_dest, self.fill_order)),
+            Region::Rect(c1, c2)
+                if self.fill_order != FillOrder::RowMajor && il_dest.0 != 0 && il_dest.1 != 0 =>
+            {
+                let (d_ul, d_br) = standardize_rectangle(&c1, &c2);
+                Some(fill_rectangle(d_ul, d_br, il_dest, self.fill_order))
+            }
+            _ => None,
+        };
+        if dest_slots.is_some_and(|slots| slots.len() < self.get_source_wells().len()) {
+            return Err("Destination region has fewer wells than the source!");
+        }
+
         // Check if all destination wells exist:
         // NOT IMPLEMENTED
         // Should *not* happen in this function---otherwise
@@ -314,6 +737,39 @@ impl TransferRegion {
 
         Ok(())
     }
+
+    pub fn validate_volume(
+        &self,
+        volume: f32,
+        source: &Labware,
+        dest: &Labware,
+        // nL already landing in each destination well from other transfers
+        existing: &HashMap<(u8, u8), f32>,
+        mask: &[(u8, u8)],
+    ) -> Result<(), &'static str> {
+        // Checks a transfer volume (nL) against the labware of both plates.
+        let pairs = self.get_transfer_pairs(volume, mask);
+        for (_, _, volume) in pairs.iter() {
+            if source.min_transfer.is_some_and(|min| *volume < min) {
+                return Err("Transfer volume is below the source plate's minimum!");
+            }
+            if source.max_transfer.is_some_and(|max| *volume > max) {
+                return Err("Transfer volume is above the source plate's maximum!");
+            }
+        }
+        // Pooling transfers may land in the same well several times
+        let mut totals = existing.clone();
+        for (_, d_well, volume) in pairs.iter() {
+            *totals.entry(*d_well).or_default() += volume;
+        }
+        if totals
+            .values()
+            .any(|total| total / 1000.0 > dest.max_volume)
+        {
+            return Err("Transfer would overfill a destination well!");
+        }
+        Ok(())
+    }
 }
 
 fn create_dense_rectangle(c1: &(u8, u8), c2: &(u8, u8)) -> Vec<(u8, u8)> {
@@ -330,6 +786,56 @@ fn create_dense_rectangle(c1: &(u8, u8), c2: &(u8, u8)) -> Vec<(u8, u8)> {
     points
 }
 
+fn bounding_box(rects: &[Corners]) -> Corners {
+    let corners = || rects.iter().flat_map(|(c1, c2)| [c1, c2]);
+    (
+        (
+            corners().map(|c| c.0).min().unwrap_or(0),
+            corners().map(|c| c.1).min().unwrap_or(0),
+        ),
+        (
+            corners().map(|c| c.0).max().unwrap_or(0),
+            corners().map(|c| c.1).max().unwrap_or(0),
+        ),
+    )
+}
+
+fn fill_rectangle(
+    ul: (u8, u8),
+    br: (u8, u8),
+    interleave: (i8, i8),
+    order: FillOrder,
+) -> Vec<(u8, u8)> {
+    // Wells of a standardized rectangle, stepping by the interleave, in the given order
+    let step = (
+        i8::max(interleave.0.abs(), 1) as usize,
+        i8::max(interleave.1.abs(), 1) as usize,
+    );
+    let rows: Vec<u8> = (ul.0..=br.0).step_by(step.0).collect();
+    let cols: Vec<u8> = (ul.1..=br.1).step_by(step.1).collect();
+    match order {
+        FillOrder::RowMajor => rows
+            .iter()
+            .flat_map(|i| cols.iter().map(move |j| (*i, *j)))
+            .collect(),
+        FillOrder::ColumnMajor => cols
+            .iter()
+            .flat_map(|j| rows.iter().map(move |i| (*i, *j)))
+            .collect(),
+        FillOrder::Serpentine => rows
+            .iter()
+            .enumerate()
+            .flat_map(|(n, i)| {
+                let mut row: Vec<(u8, u8)> = cols.iter().map(|j| (*i, *j)).collect();
+                if n % 2 == 1 {
+                    row.reverse();
+                }
+                row
+            })
+            .collect(),
+    }
+}
+
 fn standardize_rectangle(c1: &(u8, u8), c2: &(u8, u8)) -> ((u8, u8), (u8, u8)) {
     let upper_left_i = u8::min(c1.0, c2.0);
     let upper_left_j = u8::min(c1.1, c2.1);
@@ -366,7 +872,7 @@ impl fmt::Display for TransferRegion {
 
         writeln!(f, "Dest Plate:")?;
         let dest_dims = self.dest_plate.size();
-        let dest_wells = self.get_destination_wells();
+        let dest_wells = self.get_destination_wells(&[]);
         let mut dest_string = String::new();
         for i in 1..=dest_dims.0 {
             for j in 1..=dest_dims.1 {
@@ -386,6 +892,7 @@ impl fmt::Display for TransferRegion {
 mod tests {
     use wasm_bindgen_test::*;
 
+    use crate::data::labware::PlateChemistry;
     use crate::data::plate::*;
     use crate::data::transfer_region::*;
 
@@ -402,8 +909,9 @@ mod tests {
             dest_region: Region::Point((3, 3)),
             interleave_source: (1, 1),
             interleave_dest: (1, 1),
+            fill_order: Default::default(),
         };
-        let transfer1_map = transfer1.calculate_map();
+        let transfer1_map = transfer1.calculate_map(&[]);
         assert_eq!(
             transfer1_map((1, 1)),
             Some(vec! {(3,3)}),
@@ -427,8 +935,9 @@ mod tests {
             dest_region: Region::Point((3, 3)),
             interleave_source: (2, 2),
             interleave_dest: (1, 1),
+            fill_order: Default::default(),
         };
-        let transfer2_map = transfer2.calculate_map();
+        let transfer2_map = transfer2.calculate_map(&[]);
         assert_eq!(
             transfer2_map((1, 1)),
             Some(vec! {(3,3)}),
@@ -457,8 +966,9 @@ mod tests {
             dest_region: Region::Point((3, 3)),
             interleave_source: (1, 1),
             interleave_dest: (2, 3),
+            fill_order: Default::default(),
         };
-        let transfer3_map = transfer3.calculate_map();
+        let transfer3_map = transfer3.calculate_map(&[]);
         assert_eq!(
             transfer3_map((1, 1)),
             Some(vec! {(3,3)}),
@@ -494,8 +1004,9 @@ mod tests {
             dest_region: Region::Rect((2, 2), (11, 11)),
             interleave_source: (1, 1),
             interleave_dest: (3, 3),
+            fill_order: Default::default(),
         };
-        let transfer1_map = transfer1.calculate_map();
+        let transfer1_map = transfer1.calculate_map(&[]);
         assert_eq!(
             transfer1_map((1, 1)),
             Some(vec! {(2, 2), (2, 8), (8, 2), (8, 8)}),
@@ -514,9 +1025,10 @@ mod tests {
             dest_region: Region::Rect((2, 2), (11, 16)),
             interleave_source: (1, 1),
             interleave_dest: (2, 2),
+            fill_order: Default::default(),
         };
         let transfer2_source = transfer2.get_source_wells();
-        let transfer2_dest = transfer2.get_destination_wells();
+        let transfer2_dest = transfer2.get_destination_wells(&[]);
         assert_eq!(
             transfer2_source,
             vec![(1, 1), (1, 2), (1, 3), (2, 1), (2, 2), (2, 3)],
@@ -564,12 +1076,13 @@ mod tests {
             dest_region: Region::Point((1, 9)),
             interleave_source: (1, 1),
             interleave_dest: (0, 2),
+            fill_order: Default::default(),
         };
         //let transfer1_source = transfer1.get_source_wells();
-        let mut transfer1_dest = transfer1.get_destination_wells();
+        let mut transfer1_dest = transfer1.get_destination_wells(&[]);
         transfer1_dest.sort();
         transfer1_dest.dedup(); // Makes our check easier, otherwise we have repeated wells
-        let transfer1_map = transfer1.calculate_map();
+        let transfer1_map = transfer1.calculate_map(&[]);
         // Skipping source check---it's just 12 wells.
         assert_eq!(
             transfer1_dest,
@@ -587,4 +1100,263 @@ mod tests {
             "Failed type pool 1 map 2"
         );
     }
+
+    #[test]
+    #[wasm_bindgen_test]
+    fn test_custom_transfer() {
+        let transfer1 = TransferRegion {
+            source_plate: Plate::new(PlateType::Source, PlateFormat::W96),
+            dest_plate: Plate::new(PlateType::Destination, PlateFormat::W384),
+            source_region: Region::Rect((1, 1), (1, 2)),
+            dest_region: Region::Point((3, 3)),
+            interleave_source: (1, 1),
+            interleave_dest: (1, 1),
+            fill_order: Default::default(),
+        };
+        let custom = transfer1.to_custom(&[]);
+        let mut transfer2 = TransferRegion {
+            source_region: custom.clone(),
+            dest_region: custom,
+            ..transfer1
+        };
+        assert_eq!(
+            transfer2.get_destination_wells(&[]),
+            vec![(3, 3), (3, 4)],
+            "Failed custom conversion"
+        );
+
+        transfer2.edit_custom(|c| c.push((1, 1), (5, 5)));
+        assert_eq!(
+            transfer2.get_source_wells(),
+            vec![(1, 1), (1, 2)],
+            "Failed custom source wells"
+        );
+        assert_eq!(
+            transfer2.calculate_map(&[])((1, 1)),
+            Some(vec![(3, 3), (5, 5)]),
+            "Failed custom map"
+        );
+
+        transfer2.edit_custom(|c| c.remove(0));
+        assert_eq!(
+            transfer2.get_destination_wells(&[]),
+            vec![(3, 4), (5, 5)],
+            "Failed custom removal"
+        );
+    }
+
+    #[test]
+    #[wasm_bindgen_test]
+    fn test_validate_volume() {
+        let mut source = Plate::new(PlateType::Source, PlateFormat::W384);
+        source.chemistry = PlateChemistry::LDV;
+        let destination = Plate::new(PlateType::Destination, PlateFormat::W1536);
+
+        let transfer1 = TransferRegion {
+            source_plate: source,
+            source_region: Region::Rect((1, 1), (2, 2)),
+            dest_plate: destination,
+            dest_region: Region::Point((1, 1)),
+            interleave_source: (1, 1),
+            interleave_dest: (1, 1),
+            fill_order: Default::default(),
+        };
+        let source_lw = source.labware(&[]).unwrap();
+        let dest_lw = destination.labware(&[]).unwrap();
+        let empty = HashMap::new();
+        let check = |tr: &TransferRegion, volume: f32, existing: &HashMap<(u8, u8), f32>| {
+            tr.validate_volume(volume, &source_lw, &dest_lw, existing, &[])
+        };
+        assert!(check(&transfer1, 2.5, &empty).is_ok(), "Failed volume 1");
+        assert!(check(&transfer1, 1.0, &empty).is_err(), "Failed volume 2");
+        assert!(
+            check(&transfer1, 9600.0, &empty).is_err(),
+            "Failed volume 3"
+        );
+
+        // Two source wells pooled into each 10 µL well
+        let transfer2 = TransferRegion {
+            interleave_dest: (0, 1),
+            ..transfer1.clone()
+        };
+        assert!(
+            check(&transfer2, 4000.0, &empty).is_ok(),
+            "Failed pooled volume 1"
+        );
+        assert!(
+            check(&transfer2, 6000.0, &empty).is_err(),
+            "Failed pooled volume 2"
+        );
+
+        // Another transfer already put 8 µL into A1
+        let existing = HashMap::from([((1, 1), 8000.0)]);
+        assert!(
+            check(&transfer1, 2000.0, &existing).is_ok(),
+            "Failed existing volume 1"
+        );
+        assert!(
+            check(&transfer1, 2500.0, &existing).is_err(),
+            "Failed existing volume 2"
+        );
+    }
+
+    #[test]
+    #[wasm_bindgen_test]
+    fn test_masked_transfer() {
+        // A1:A3 into A1, with A2 excluded: everything moves along one
+        let simple = TransferRegion {
+            source_plate: Plate::new(PlateType::Source, PlateFormat::W96),
+            dest_plate: Plate::new(PlateType::Destination, PlateFormat::W96),
+            source_region: Region::Rect((1, 1), (1, 3)),
+            dest_region: Region::Point((1, 1)),
+            interleave_source: (1, 1),
+            interleave_dest: (1, 1),
+            fill_order: Default::default(),
+        };
+        let map = simple.calculate_map(&[(1, 2)]);
+        assert_eq!(map((1, 1)), Some(vec![(1, 1)]), "Failed packed simple 1");
+        assert_eq!(map((1, 2)), Some(vec![(1, 3)]), "Failed packed simple 2");
+        assert_eq!(map((1, 3)), Some(vec![(2, 1)]), "Failed packed simple 3");
+        assert!(simple.validate_mask(&[(1, 2)]).is_ok());
+
+        // A1:A2 replicated over A1:A6 with A3 excluded: only two whole replicates fit
+        let replicate = TransferRegion {
+            source_region: Region::Rect((1, 1), (1, 2)),
+            dest_region: Region::Rect((1, 1), (1, 6)),
+            ..simple.clone()
+        };
+        let map = replicate.calculate_map(&[(1, 3)]);
+        assert_eq!(
+            map((1, 1)),
+            Some(vec![(1, 1), (1, 4)]),
+            "Failed packed replicate 1"
+        );
+        assert_eq!(
+            map((1, 2)),
+            Some(vec![(1, 2), (1, 5)]),
+            "Failed packed replicate 2"
+        );
+
+        // Custom transfers lose the excluded wells, and are flagged
+        let custom = Region::new_custom(&vec![((1, 1), (1, 2)), ((1, 2), (1, 3))]);
+        let custom = TransferRegion {
+            source_region: custom.clone(),
+            dest_region: custom,
+            ..simple.clone()
+        };
+        assert_eq!(custom.get_destination_wells(&[(1, 2)]), vec![(1, 3)]);
+        assert_eq!(
+            custom.get_transfer_pairs(2.5, &[(1, 2)]),
+            vec![((1, 2), (1, 3), 2.5)]
+        );
+        assert!(custom.validate_mask(&[(1, 2)]).is_err());
+        assert!(custom.validate_mask(&[(2, 2)]).is_ok());
+    }
+
+    #[test]
+    #[wasm_bindgen_test]
+    fn test_multi_region() {
+        // A1:A2 then C1, into B1:B2 then D3:D4
+        let multi = TransferRegion {
+            source_plate: Plate::new(PlateType::Source, PlateFormat::W96),
+            dest_plate: Plate::new(PlateType::Destination, PlateFormat::W96),
+            source_region: Region::Multi(vec![((1, 1), (1, 2)), ((3, 1), (3, 1))]),
+            dest_region: Region::Multi(vec![((2, 1), (2, 2)), ((4, 3), (4, 4))]),
+            interleave_source: (1, 1),
+            interleave_dest: (1, 1),
+            fill_order: Default::default(),
+        };
+        assert_eq!(multi.get_source_wells(), vec![(1, 1), (1, 2), (3, 1)]);
+        let map = multi.calculate_map(&[]);
+        assert_eq!(map((1, 1)), Some(vec![(2, 1)]), "Failed multi 1");
+        assert_eq!(map((1, 2)), Some(vec![(2, 2)]), "Failed multi 2");
+        assert_eq!(map((3, 1)), Some(vec![(4, 3)]), "Failed multi 3");
+        assert_eq!(map((2, 1)), None, "Failed multi 4");
+        assert!(multi.validate().is_ok());
+
+        // Into a point, the rectangles keep their layout
+        let point = TransferRegion {
+            dest_region: Region::Point((2, 2)),
+            ..multi.clone()
+        };
+        let map = point.calculate_map(&[]);
+        assert_eq!(map((1, 1)), Some(vec![(2, 2)]), "Failed multi point 1");
+        assert_eq!(map((3, 1)), Some(vec![(4, 2)]), "Failed multi point 2");
+
+        // Too few destination wells
+        let short = TransferRegion {
+            dest_region: Region::Multi(vec![((2, 1), (2, 2))]),
+            ..multi.clone()
+        };
+        assert!(short.validate().is_err());
+    }
+
+    #[test]
+    #[wasm_bindgen_test]
+    fn test_fill_order() {
+        // A1:B2 into A1:B2, read row by row: A1, A2, B1, B2
+        let square = TransferRegion {
+            source_plate: Plate::new(PlateType::Source, PlateFormat::W96),
+            dest_plate: Plate::new(PlateType::Destination, PlateFormat::W96),
+            source_region: Region::Rect((1, 1), (2, 2)),
+            dest_region: Region::Rect((1, 1), (2, 2)),
+            interleave_source: (1, 1),
+            interleave_dest: (1, 1),
+            fill_order: FillOrder::ColumnMajor,
+        };
+        let map = square.calculate_map(&[]);
+        assert_eq!(map((1, 2)), Some(vec![(2, 1)]), "Failed transpose 1");
+        assert_eq!(map((2, 1)), Some(vec![(1, 2)]), "Failed transpose 2");
+        assert_eq!(map((2, 2)), Some(vec![(2, 2)]), "Failed transpose 3");
+
+        let serpentine = TransferRegion {
+            fill_order: FillOrder::Serpentine,
+            ..square.clone()
+        };
+        assert_eq!(
+            serpentine.get_destination_wells(&[]),
+            vec![(1, 1), (1, 2), (2, 2), (2, 1)]
+        );
+
+        // A source row down a destination column, twice over
+        let column = TransferRegion {
+            source_region: Region::Rect((1, 1), (1, 4)),
+            dest_region: Region::Rect((1, 1), (8, 1)),
+            ..square.clone()
+        };
+        let map = column.calculate_map(&[]);
+        assert_eq!(map((1, 1)), Some(vec![(1, 1), (5, 1)]), "Failed column 1");
+        assert_eq!(map((1, 4)), Some(vec![(4, 1), (8, 1)]), "Failed column 2");
+
+        // Too few destination wells
+        let short = TransferRegion {
+            dest_region: Region::Rect((1, 1), (3, 1)),
+            ..column.clone()
+        };
+        assert!(short.validate().is_err());
+    }
+
+    #[test]
+    #[wasm_bindgen_test]
+    fn test_region_editing() {
+        let rect = Region::Rect((2, 2), (3, 4));
+        assert!(rect.contains((3, 3)));
+        assert!(!rect.contains((1, 3)));
+
+        assert_eq!(
+            rect.translate((1, -1), (8, 12)),
+            Some(Region::Rect((3, 1), (4, 3)))
+        );
+        assert_eq!(rect.translate((0, -2), (8, 12)), None, "Left the plate");
+        assert_eq!(rect.translate((6, 0), (8, 12)), None, "Left the plate");
+        let multi = Region::Multi(vec![((1, 1), (1, 2)), ((3, 3), (3, 3))]);
+        assert_eq!(
+            multi.translate((1, 0), (8, 12)),
+            Some(Region::Multi(vec![((2, 1), (2, 2)), ((4, 3), (4, 3))]))
+        );
+
+        assert_eq!(rect.opposite_corner((2, 4)), Some((3, 2)));
+        assert_eq!(rect.opposite_corner((3, 4)), Some((2, 2)));
+        assert_eq!(rect.opposite_corner((3, 3)), None, "Not a corner");
+    }
 }
@@ -0,0 +1,373 @@
+// Reading and writing well names.
+//
+// Wells are (row, column), both starting at 1.
+// Instruments and LIMS disagree on how to spell them, so we accept
+// "A1", "A01", numeric well indices and separate row/column values,
+// and can write any of them back out.
+
+use lazy_static::lazy_static;
+use regex::Regex;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+#[derive(PartialEq, Eq, Clone, Copy, Serialize, Deserialize, JsonSchema, Debug)]
+pub enum IndexOrder {
+    RowMajor,    // A1, A2, A3...
+    ColumnMajor, // A1, B1, C1...
+}
+impl Default for IndexOrder {
+    fn default() -> Self {
+        Self::RowMajor
+    }
+}
+
+#[derive(PartialEq, Eq, Clone, Copy, Serialize, Deserialize, JsonSchema, Debug)]
+pub enum WellNameStyle {
+    Letters,       // A1
+    PaddedLetters, // A01
+    Index(IndexOrder),
+    RowColumn,
+}
+impl Default for WellNameStyle {
+    fn default() -> Self {
+        Self::Letters
+    }
+}
+
+impl WellNameStyle {
+    pub const ALL: [WellNameStyle; 5] = [
+        WellNameStyle::Letters,
+        WellNameStyle::PaddedLetters,
+        WellNameStyle::Index(IndexOrder::RowMajor),
+        WellNameStyle::Index(IndexOrder::ColumnMajor),
+        WellNameStyle::RowColumn,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            WellNameStyle::Letters => "A1",
+            WellNameStyle::PaddedLetters => "A01",
+            WellNameStyle::Index(IndexOrder::RowMajor) => "Well number (across rows)",
+            WellNameStyle::Index(IndexOrder::ColumnMajor) => "Well number (down columns)",
+            WellNameStyle::RowColumn => "Separate row and column",
+        }
+    }
+}
+
+pub fn format_well(well: (u8, u8), size: (u8, u8), style: WellNameStyle) -> String {
+    match style {
+        WellNameStyle::Letters => {
+            format!("{}{}", num_to_letters(well.0).unwrap_or_default(), well.1)
+        }
+        WellNameStyle::PaddedLetters => {
+            // Pad to the widest column on the plate, but never less than "01"
+            let width = usize::max(2, size.1.to_string().len());
+            format!(
+                "{}{:0>width$}",
+                num_to_letters(well.0).unwrap_or_default(),
+                well.1,
+                width = width
+            )
+        }
+        WellNameStyle::Index(order) => well_to_index(well, size, order).to_string(),
+        WellNameStyle::RowColumn => format!("{},{}", well.0, well.1),
+    }
+}
+
+pub fn well_to_index(well: (u8, u8), size: (u8, u8), order: IndexOrder) -> u32 {
+    let (row, col) = (well.0 as u32, well.1 as u32);
+    match order {
+        IndexOrder::RowMajor => (row - 1) * size.1 as u32 + col,
+        IndexOrder::ColumnMajor => (col - 1) * size.0 as u32 + row,
+    }
+}
+
+pub fn index_to_well(index: u32, size: (u8, u8), order: IndexOrder) -> Option<(u8, u8)> {
+    let (rows, cols) = (size.0 as u32, size.1 as u32);
+    if index == 0 || index > rows * cols {
+        return None;
+    }
+    let well = match order {
+        IndexOrder::RowMajor => ((index - 1) / cols + 1, (index - 1) % cols + 1),
+        IndexOrder::ColumnMajor => ((index - 1) % rows + 1, (index - 1) / rows + 1),
+    };
+    Some((well.0 as u8, well.1 as u8))
+}
+
+pub fn parse_well_name(text: &str) -> Option<(u8, u8)> {
+    // Letter forms only ("A1", "A01"), for when the plate size is not known.
+    lazy_static! {
+        static ref WELL_REGEX: Regex = Regex::new(r"^([A-Za-z]+)0*(\d+)$").unwrap();
+    }
+    let captures = WELL_REGEX.captures(text.trim())?;
+    let row = letters_to_num(&captures[1])?;
+    let col = captures[2].parse::<u8>().ok()?;
+    Some((row, col))
+}
+
+pub fn parse_well(text: &str, size: (u8, u8)) -> Result<(u8, u8), String> {
+    parse_well_ordered(text, size, IndexOrder::RowMajor)
+}
+
+pub fn parse_well_ordered(
+    text: &str,
+    size: (u8, u8),
+    order: IndexOrder,
+) -> Result<(u8, u8), String> {
+    // Accepts "A1", "A01", "1,2" (row, column) and 1-based well indices.
+    let text = text.trim();
+    let well = if let Some(well) = parse_well_name(text) {
+        well
+    } else if let Ok(index) = text.parse::<u32>() {
+        index_to_well(index, size, order)
+            .ok_or(format!("Well index {} is not on the plate", index))?
+    } else if let Some((row, col)) = text.split_once(',') {
+        return parse_row_col(row, col, size);
+    } else {
+        return Err(format!("Could not read well \"{}\"", text));
+    };
+    check_bounds(well, size, text)
+}
+
+pub fn parse_row_col(row: &str, col: &str, size: (u8, u8)) -> Result<(u8, u8), String> {
+    // Rows may be given as letters or numbers, columns only as numbers.
+    let (row, col) = (row.trim(), col.trim());
+    let row_num = row
+        .parse::<u8>()
+        .ok()
+        .or_else(|| letters_to_num(row))
+        .ok_or(format!("Invalid row \"{}\"", row))?;
+    let col_num = col
+        .parse::<u8>()
+        .map_err(|_| format!("Invalid column \"{}\"", col))?;
+    check_bounds((row_num, col_num), size, &format!("{}, {}", row, col))
+}
+
+pub fn parse_well_list(text: &str, size: (u8, u8)) -> Result<Vec<(u8, u8)>, String> {
+    // A list of wells and ranges, e.g. "A1, H12, B2:C3".
+    // Ranges expand row by row; repeated wells are only listed once.
+    let mut wells: Vec<(u8, u8)> = Vec::new();
+    for item in text
+        .split([',', ';'])
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+    {
+        let read = |name: &str| {
+            parse_well_name(name)
+                .ok_or(format!("Could not read well \"{}\"", name.trim()))
+                .and_then(|well| check_bounds(well, size, name.trim()))
+        };
+        let (start, end) = match item.split_once(':') {
+            Some((start, end)) => (read(start)?, read(end)?),
+            None => (read(item)?, read(item)?),
+        };
+        for row in u8::min(start.0, end.0)..=u8::max(start.0, end.0) {
+            for col in u8::min(start.1, end.1)..=u8::max(start.1, end.1) {
+                if !wells.contains(&(row, col)) {
+                    wells.push((row, col));
+                }
+            }
+        }
+    }
+    Ok(wells)
+}
+
+pub fn format_well_list(wells: &[(u8, u8)], size: (u8, u8)) -> String {
+    // The reverse of parse_well_list, joining runs down a column into ranges
+    let mut wells = wells.to_vec();
+    wells.sort_by_key(|w| (w.1, w.0));
+    wells.dedup();
+    let mut runs: Vec<((u8, u8), (u8, u8))> = Vec::new();
+    for well in wells {
+        match runs.last_mut() {
+            Some((_, end)) if end.1 == well.1 && end.0 + 1 == well.0 => *end = well,
+            _ => runs.push((well, well)),
+        }
+    }
+    runs.iter()
+        .map(|(start, end)| {
+            let start_name = format_well(*start, size, WellNameStyle::Letters);
+            if start == end {
+                start_name
+            } else {
+                format!(
+                    "{}:{}",
+                    start_name,
+                    format_well(*end, size, WellNameStyle::Letters)
+                )
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn check_bounds(well: (u8, u8), size: (u8, u8), text: &str) -> Result<(u8, u8), String> {
+    if well.0 == 0 || well.1 == 0 || well.0 > size.0 || well.1 > size.1 {
+        Err(format!("Well \"{}\" is not on the plate", text))
+    } else {
+        Ok(well)
+    }
+}
+
+pub fn letters_to_num(letters: &str) -> Option<u8> {
+    let mut num: u8 = 0;
+    for (i, letter) in letters.to_ascii_uppercase().chars().rev().enumerate() {
+        log::debug!("{}, {}", i, letter);
+        let n = letter as u8;
+        if !(65..=90).contains(&n) {
+            return None;
+        }
+        num = num.checked_add((26_i32.pow(i as u32) * (n as i32 - 64)).try_into().ok()?)?;
+    }
+    Some(num)
+}
+
+pub fn num_to_letters(num: u8) -> Option<String> {
+    if num == 0 {
+        return None;
+    } // Otherwise, we will not return none!
+      // As another note, we can't represent higher than "IV" anyway;
+      // thus there's no reason for a loop (26^n with n>1 will NOT occur).
+    let mut text = "".to_string();
+    let mut digit1 = num.div_euclid(26u8);
+    let mut digit2 = num.rem_euclid(26u8);
+    if digit1 > 0 && digit2 == 0u8 {
+        digit1 -= 1;
+        digit2 = 26;
+    }
+    if digit1 != 0 {
+        text.push((64 + digit1) as char)
+    }
+    text.push((64 + digit2) as char);
+
+    Some(text.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use wasm_bindgen_test::*;
+
+    use super::*;
+
+    #[test]
+    #[wasm_bindgen_test]
+    fn test_letters_to_num() {
+        assert_eq!(letters_to_num("D"), Some(4));
+        assert_eq!(letters_to_num("d"), Some(4)); // Well names are read case-insensitively
+        assert_eq!(letters_to_num("AD"), Some(26 + 4));
+        assert_eq!(letters_to_num("CG"), Some(3 * 26 + 7));
+    }
+
+    #[test]
+    #[wasm_bindgen_test]
+    fn test_num_to_letters() {
+        println!("27 is {:?}", num_to_letters(27));
+        assert_eq!(num_to_letters(1), Some("A".to_string()));
+        assert_eq!(num_to_letters(26), Some("Z".to_string()));
+        assert_eq!(num_to_letters(27), Some("AA".to_string()));
+        assert_eq!(num_to_letters(111), Some("DG".to_string()));
+    }
+
+    #[test]
+    #[wasm_bindgen_test]
+    fn test_l2n_and_n2l() {
+        assert_eq!(
+            num_to_letters(letters_to_num("A").unwrap()),
+            Some("A".to_string())
+        );
+        assert_eq!(
+            num_to_letters(letters_to_num("BJ").unwrap()),
+            Some("BJ".to_string())
+        );
+        for i in 1..=255 {
+            assert_eq!(letters_to_num(&num_to_letters(i).unwrap()), Some(i));
+        }
+    }
+
+    #[test]
+    #[wasm_bindgen_test]
+    fn test_parse_well() {
+        assert_eq!(parse_well("A1", (8, 12)), Ok((1, 1)));
+        assert_eq!(parse_well("b07", (8, 12)), Ok((2, 7)));
+        assert_eq!(parse_well("AF48", (32, 48)), Ok((32, 48)));
+        assert_eq!(parse_well("13", (8, 12)), Ok((2, 1)));
+        assert_eq!(parse_well("96", (8, 12)), Ok((8, 12)));
+        assert_eq!(parse_well("2, 3", (8, 12)), Ok((2, 3)));
+        assert!(parse_well("97", (8, 12)).is_err());
+        assert!(parse_well("I1", (8, 12)).is_err());
+        assert!(parse_well("A0", (8, 12)).is_err());
+        assert!(parse_well("A-1", (8, 12)).is_err());
+        assert_eq!(
+            parse_well_ordered("13", (8, 12), IndexOrder::ColumnMajor),
+            Ok((5, 2))
+        );
+    }
+
+    #[test]
+    #[wasm_bindgen_test]
+    fn test_parse_row_col() {
+        assert_eq!(parse_row_col("2", "3", (8, 12)), Ok((2, 3)));
+        assert_eq!(parse_row_col("B", "3", (8, 12)), Ok((2, 3)));
+        assert!(parse_row_col("9", "3", (8, 12)).is_err());
+    }
+
+    #[test]
+    #[wasm_bindgen_test]
+    fn test_parse_well_list() {
+        assert_eq!(
+            parse_well_list("A1, B2:C3; h12, A1", (8, 12)),
+            Ok(vec![(1, 1), (2, 2), (2, 3), (3, 2), (3, 3), (8, 12)])
+        );
+        assert_eq!(parse_well_list(" ", (8, 12)), Ok(vec![]));
+        assert!(parse_well_list("A1, I1", (8, 12)).is_err());
+        assert!(parse_well_list("A1:", (8, 12)).is_err());
+    }
+
+    #[test]
+    #[wasm_bindgen_test]
+    fn test_format_well_list() {
+        let wells = vec![(3, 1), (1, 1), (2, 1), (5, 1), (1, 2)];
+        assert_eq!(format_well_list(&wells, (8, 12)), "A1:C1, E1, A2");
+        assert_eq!(
+            parse_well_list(&format_well_list(&wells, (8, 12)), (8, 12))
+                .unwrap()
+                .len(),
+            wells.len()
+        );
+        assert_eq!(format_well_list(&[], (8, 12)), "");
+    }
+
+    #[test]
+    #[wasm_bindgen_test]
+    fn test_format_well() {
+        let size = (16, 24);
+        assert_eq!(format_well((2, 3), size, WellNameStyle::Letters), "B3");
+        assert_eq!(
+            format_well((2, 3), size, WellNameStyle::PaddedLetters),
+            "B03"
+        );
+        assert_eq!(
+            format_well((2, 3), size, WellNameStyle::Index(IndexOrder::RowMajor)),
+            "27"
+        );
+        assert_eq!(
+            format_well((2, 3), size, WellNameStyle::Index(IndexOrder::ColumnMajor)),
+            "34"
+        );
+        assert_eq!(format_well((2, 3), size, WellNameStyle::RowColumn), "2,3");
+
+        // Every style should read back as the same well
+        for style in WellNameStyle::ALL {
+            let order = match style {
+                WellNameStyle::Index(order) => order,
+                _ => IndexOrder::RowMajor,
+            };
+            for well in [(1, 1), (16, 24), (9, 13)] {
+                assert_eq!(
+                    parse_well_ordered(&format_well(well, size, style), size, order),
+                    Ok(well)
+                );
+            }
+        }
+    }
+}
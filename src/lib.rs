@@ -1,4 +1,5 @@
 #![allow(non_snake_case)]
+pub mod cli;
 mod components;
 mod data;
 
@@ -27,10 +28,11 @@ pub fn plate_test() {
         dest_region: transfer_region::Region::Rect((2, 2), (11, 11)),
         interleave_source: (1, 1),
         interleave_dest: (3, 3),
+        fill_order: Default::default(),
     };
     println!("{}", transfer);
     let sws = transfer.get_source_wells();
-    let m = transfer.calculate_map();
+    let m = transfer.calculate_map(&[]);
     for w in sws {
         println!("{:?} -> {:?}", w, m(w));
     }